@@ -0,0 +1,356 @@
+// src-tauri/src/app_config.rs
+use crate::auto_updater::UpdateConfig;
+use crate::config_migrations;
+use crate::logger::{LogLevel, LoggerConfig};
+use crate::password_manager::{AutoLockSettings, KdfCost, DEFAULT_HISTORY_RETENTION};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Version written into every `config.json` produced by this build
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid configuration file: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Config directory not found")]
+    NoConfigDir,
+    #[error("Config migration failed: {0}")]
+    Migration(String),
+    #[error("config.json was written by a newer version of PWA Marketplace (config version {found}, this build supports up to {supported}). Please update the app.")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub version: u32,
+    pub apps_folder: PathBuf,
+    pub data_folder: PathBuf,
+    pub marketplace_url: String,
+    #[serde(default)]
+    pub updater: UpdaterSettings,
+    #[serde(default)]
+    pub logger: LoggerSettings,
+    #[serde(default)]
+    pub vault: VaultSettings,
+    /// `docker save` tarball chosen during setup, for machines without registry access
+    #[serde(default)]
+    pub image_bundle: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdaterSettings {
+    pub check_on_startup: bool,
+    pub auto_install: bool,
+    pub beta_channel: bool,
+    pub check_interval_hours: u64,
+}
+
+impl Default for UpdaterSettings {
+    fn default() -> Self {
+        let defaults = UpdateConfig::default();
+
+        Self {
+            check_on_startup: defaults.check_on_startup,
+            auto_install: defaults.auto_install,
+            beta_channel: defaults.beta_channel,
+            check_interval_hours: defaults.check_interval.as_secs() / 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggerSettings {
+    pub level: LogLevel,
+    pub log_to_file: bool,
+    pub log_to_console: bool,
+    pub max_file_size: u64,
+    pub max_files: usize,
+}
+
+impl Default for LoggerSettings {
+    fn default() -> Self {
+        let defaults = LoggerConfig::default();
+
+        Self {
+            level: defaults.level,
+            log_to_file: defaults.log_to_file,
+            log_to_console: defaults.log_to_console,
+            max_file_size: defaults.max_file_size,
+            max_files: defaults.max_files,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultSettings {
+    /// Minutes of inactivity before the vault locks itself, 0 to disable
+    pub auto_lock_minutes: u64,
+    pub lock_on_sleep: bool,
+    /// Argon2id cost for the vault key; weaker vaults are upgraded on unlock
+    pub kdf: KdfCost,
+    /// Entries not updated for this many days are flagged in the health report, 0 to disable
+    pub stale_after_days: u32,
+    /// Prior versions kept per password entry, 0 to keep none
+    pub history_retention: usize,
+}
+
+impl Default for VaultSettings {
+    fn default() -> Self {
+        let defaults = AutoLockSettings::default();
+
+        Self {
+            auto_lock_minutes: defaults.idle_timeout.map(|t| t.as_secs() / 60).unwrap_or(0),
+            lock_on_sleep: defaults.lock_on_sleep,
+            kdf: KdfCost::default(),
+            stale_after_days: 365,
+            history_retention: DEFAULT_HISTORY_RETENTION,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn new(apps_folder: &str, data_folder: &str) -> Self {
+        Self {
+            version: CURRENT_CONFIG_VERSION,
+            apps_folder: PathBuf::from(apps_folder),
+            data_folder: PathBuf::from(data_folder),
+            marketplace_url: "http://localhost:3000".to_string(),
+            updater: UpdaterSettings::default(),
+            logger: LoggerSettings::default(),
+            vault: VaultSettings::default(),
+            image_bundle: None,
+        }
+    }
+
+    /// Location of `config.json` inside the platform config directory
+    pub fn config_path() -> Result<PathBuf, ConfigError> {
+        let mut path = dirs::config_dir().ok_or(ConfigError::NoConfigDir)?;
+        path.push("PWA-Marketplace");
+        path.push("config.json");
+        Ok(path)
+    }
+
+    pub fn exists() -> bool {
+        Self::config_path().map(|path| path.exists()).unwrap_or(false)
+    }
+
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(&Self::config_path()?)
+    }
+
+    /// Load the config, upgrading older schema versions in place. The original
+    /// file is kept next to it as `config.v<N>.json.bak` before it is rewritten.
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        let mut value: serde_json::Value = serde_json::from_str(&content)?;
+
+        let from_version = config_migrations::migrate(&mut value)?;
+        let config: AppConfig = serde_json::from_value(value)?;
+
+        if from_version < CURRENT_CONFIG_VERSION {
+            let backup_path = Self::backup_path(path, from_version);
+            std::fs::copy(path, &backup_path)?;
+            config.save_to(path)?;
+            log::info!(
+                "Upgraded config.json from version {} to {}, backup saved to {}",
+                from_version, CURRENT_CONFIG_VERSION, backup_path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Keep a copy of a config that failed to load as `config.v<N>.json.bak`, so
+    /// it survives whatever the user does to config.json next. An existing backup
+    /// of the same version is left alone. Returns the backup path.
+    pub fn back_up_unreadable(path: &Path) -> Result<PathBuf, ConfigError> {
+        let version = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|value| config_migrations::detect_version(&value).ok())
+            .unwrap_or(0);
+
+        let backup_path = Self::backup_path(path, version);
+        if !backup_path.exists() {
+            std::fs::copy(path, &backup_path)?;
+        }
+        Ok(backup_path)
+    }
+
+    fn backup_path(path: &Path, version: u32) -> PathBuf {
+        path.with_file_name(format!("config.v{}.json.bak", version))
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        self.save_to(&Self::config_path()?)
+    }
+
+    /// Write the config atomically so a crash mid-write never leaves a truncated file
+    pub fn save_to(&self, path: &Path) -> Result<(), ConfigError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+
+    pub fn apps_folder_str(&self) -> String {
+        self.apps_folder.display().to_string()
+    }
+
+    pub fn data_folder_str(&self) -> String {
+        self.data_folder.display().to_string()
+    }
+
+    pub fn update_config(&self) -> UpdateConfig {
+        UpdateConfig {
+            check_interval: Duration::from_secs(self.updater.check_interval_hours.max(1) * 3600),
+            auto_install: self.updater.auto_install,
+            check_on_startup: self.updater.check_on_startup,
+            beta_channel: self.updater.beta_channel,
+            ..UpdateConfig::default()
+        }
+    }
+
+    pub fn logger_config(&self) -> LoggerConfig {
+        LoggerConfig {
+            level: self.logger.level.clone(),
+            log_to_file: self.logger.log_to_file,
+            log_to_console: self.logger.log_to_console,
+            max_file_size: self.logger.max_file_size,
+            max_files: self.logger.max_files,
+            ..LoggerConfig::default()
+        }
+    }
+
+    pub fn auto_lock_settings(&self) -> AutoLockSettings {
+        AutoLockSettings {
+            idle_timeout: match self.vault.auto_lock_minutes {
+                0 => None,
+                minutes => Some(Duration::from_secs(minutes * 60)),
+            },
+            lock_on_sleep: self.vault.lock_on_sleep,
+            ..AutoLockSettings::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+
+        let mut config = AppConfig::new("/home/user/PWA-Apps", "/home/user/PWA-Data");
+        config.updater.auto_install = true;
+        config.logger.level = LogLevel::Debug;
+        config.save_to(&path).unwrap();
+
+        let loaded = AppConfig::load_from(&path).unwrap();
+        assert_eq!(loaded.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(loaded.apps_folder, PathBuf::from("/home/user/PWA-Apps"));
+        assert_eq!(loaded.data_folder, PathBuf::from("/home/user/PWA-Data"));
+        assert_eq!(loaded.marketplace_url, "http://localhost:3000");
+        assert!(loaded.updater.auto_install);
+        assert!(matches!(loaded.logger.level, LogLevel::Debug));
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_missing_sections_use_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"version":1,"apps_folder":"/a","data_folder":"/d","marketplace_url":"http://localhost:3000"}"#,
+        ).unwrap();
+
+        let loaded = AppConfig::load_from(&path).unwrap();
+        assert_eq!(loaded.updater.check_on_startup, UpdaterSettings::default().check_on_startup);
+        assert_eq!(loaded.logger.max_files, LoggerSettings::default().max_files);
+    }
+
+    #[test]
+    fn test_old_config_is_backed_up_and_upgraded() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        let original = include_str!("../fixtures/config/v0.json");
+        std::fs::write(&path, original).unwrap();
+
+        let loaded = AppConfig::load_from(&path).unwrap();
+        assert_eq!(loaded.version, CURRENT_CONFIG_VERSION);
+
+        let backup = std::fs::read_to_string(temp_dir.path().join("config.v0.json.bak")).unwrap();
+        assert_eq!(backup, original);
+
+        let rewritten: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["version"], CURRENT_CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_newer_config_is_left_untouched() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        let future = format!(
+            r#"{{"version":{},"apps_folder":"/a","data_folder":"/d","marketplace_url":"http://localhost:3000"}}"#,
+            CURRENT_CONFIG_VERSION + 1
+        );
+        std::fs::write(&path, &future).unwrap();
+
+        let result = AppConfig::load_from(&path);
+        assert!(matches!(result, Err(ConfigError::UnsupportedVersion { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), future);
+    }
+
+    #[test]
+    fn test_unreadable_config_is_backed_up() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.json");
+        let broken = r#"{"version":1,"apps_folder":"/a""#;
+        std::fs::write(&path, broken).unwrap();
+
+        assert!(matches!(AppConfig::load_from(&path), Err(ConfigError::Parse(_))));
+        let backup_path = AppConfig::back_up_unreadable(&path).unwrap();
+        assert_eq!(backup_path, temp_dir.path().join("config.v0.json.bak"));
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), broken);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), broken);
+    }
+
+    #[test]
+    fn test_auto_lock_disabled_with_zero_minutes() {
+        let mut config = AppConfig::new("/a", "/d");
+        assert!(config.auto_lock_settings().idle_timeout.is_some());
+
+        config.vault.auto_lock_minutes = 0;
+        assert!(config.auto_lock_settings().idle_timeout.is_none());
+    }
+
+    #[test]
+    fn test_update_config_conversion() {
+        let mut config = AppConfig::new("/a", "/d");
+        config.updater.check_interval_hours = 6;
+        config.updater.beta_channel = true;
+
+        let update_config = config.update_config();
+        assert_eq!(update_config.check_interval, Duration::from_secs(6 * 3600));
+        assert!(update_config.beta_channel);
+    }
+}
@@ -718,11 +718,17 @@ pub async fn restart_app(app_handle: tauri::AppHandle) -> Result<(), String> {
 
 // Public function for main.rs integration
 pub async fn check_for_updates(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let config = UpdateConfig::default();
+    start_with_config(app_handle, UpdateConfig::default()).await
+}
+
+pub async fn start_with_config(
+    app_handle: &AppHandle,
+    config: UpdateConfig
+) -> Result<(), Box<dyn std::error::Error>> {
     let updater = AutoUpdater::new(app_handle.clone(), config);
-    
+
     updater.start().await?;
-    
+
     Ok(())
 }
 
@@ -0,0 +1,376 @@
+// src-tauri/src/autofill.rs
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use tauri::api::dialog::blocking::MessageDialogBuilder;
+use tauri::api::dialog::{MessageDialogButtons, MessageDialogKind};
+use thiserror::Error;
+use url::Url;
+use crate::password_manager::{manager_from_state, PasswordEntry};
+use crate::totp::Totp;
+use crate::AppState;
+
+/// Multi-label public suffixes and shared hosting domains. Single-label TLDs
+/// are implied, anything unlisted falls back to its last label.
+const PUBLIC_SUFFIXES: &str = include_str!("../resources/public_suffixes.txt");
+
+/// The marketplace UI may look up credentials for any origin; every other
+/// window only gets those for the page it is showing
+const TRUSTED_WINDOW: &str = "main";
+
+#[derive(Error, Debug)]
+pub enum AutofillError {
+    #[error("Not a web origin: {0}")]
+    InvalidOrigin(String),
+}
+
+/// Which origins an entry may be filled into, relative to its URL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// Same registrable domain (eTLD+1), including subdomains
+    #[default]
+    Domain,
+    /// Same host and port
+    Host,
+    /// Same scheme, host and port
+    Exact,
+    /// Never offered for autofill
+    Never,
+}
+
+impl MatchMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchMode::Domain => "domain",
+            MatchMode::Host => "host",
+            MatchMode::Exact => "exact",
+            MatchMode::Never => "never",
+        }
+    }
+
+    /// Parse a stored value; rows written before match modes existed are NULL
+    pub fn from_db(value: Option<&str>) -> Self {
+        match value {
+            Some("host") => MatchMode::Host,
+            Some("exact") => MatchMode::Exact,
+            Some("never") => MatchMode::Never,
+            _ => MatchMode::Domain,
+        }
+    }
+}
+
+/// How closely an entry matched, weakest first so the best sorts last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchStrength {
+    Domain,
+    Host,
+    Exact,
+}
+
+/// A normalized web origin: lowercase scheme and host, IDNs in punycode and
+/// the scheme's default port dropped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    pub scheme: String,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl Origin {
+    /// Parse the origin of a URL. Bare hosts such as `example.com/login` are
+    /// taken to be https.
+    pub fn parse(raw: &str) -> Result<Self, AutofillError> {
+        let raw = raw.trim();
+        let invalid = || AutofillError::InvalidOrigin(raw.to_string());
+
+        let url = if raw.contains("://") {
+            Url::parse(raw)
+        } else {
+            Url::parse(&format!("https://{}", raw))
+        }
+        .map_err(|_| invalid())?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid());
+        }
+
+        let host = url.host_str()
+            .map(|host| host.trim_end_matches('.'))
+            .filter(|host| !host.is_empty())
+            .ok_or_else(invalid)?;
+
+        Ok(Self {
+            scheme: url.scheme().to_string(),
+            host: host.to_string(),
+            port: url.port(),
+        })
+    }
+
+    pub fn registrable_domain(&self) -> Option<String> {
+        registrable_domain(&self.host)
+    }
+
+    fn same_host(&self, other: &Origin) -> bool {
+        self.host == other.host && self.port == other.port
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+fn public_suffixes() -> &'static HashSet<&'static str> {
+    static SUFFIXES: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    SUFFIXES.get_or_init(|| {
+        PUBLIC_SUFFIXES
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .collect()
+    })
+}
+
+/// The eTLD+1 of a host, e.g. `example.co.uk` for `login.example.co.uk`.
+/// IP addresses, single-label hosts like `localhost` and public suffixes
+/// themselves have none and only ever match by host.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    if host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok() {
+        return None;
+    }
+
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() < 2 || labels.iter().any(|label| label.is_empty()) {
+        return None;
+    }
+
+    // Longest listed suffix wins
+    let suffix_len = (1..=labels.len())
+        .rev()
+        .find(|&len| public_suffixes().contains(labels[labels.len() - len..].join(".").as_str()))
+        .unwrap_or(1);
+
+    if suffix_len >= labels.len() {
+        return None;
+    }
+    Some(labels[labels.len() - suffix_len - 1..].join("."))
+}
+
+/// Whether an entry saved for `entry_url` may be offered to `origin`
+pub fn match_entry(entry_url: &str, mode: MatchMode, origin: &Origin) -> Option<MatchStrength> {
+    if mode == MatchMode::Never {
+        return None;
+    }
+    let saved = Origin::parse(entry_url).ok()?;
+
+    // Never hand a login saved for https to a page that isn't
+    if saved.scheme == "https" && origin.scheme != "https" {
+        return None;
+    }
+
+    let strength = if saved == *origin {
+        MatchStrength::Exact
+    } else if saved.same_host(origin) {
+        MatchStrength::Host
+    } else if saved.registrable_domain().is_some()
+        && saved.registrable_domain() == origin.registrable_domain()
+    {
+        MatchStrength::Domain
+    } else {
+        return None;
+    };
+
+    match mode {
+        MatchMode::Exact if strength != MatchStrength::Exact => None,
+        MatchMode::Host if strength == MatchStrength::Domain => None,
+        _ => Some(strength),
+    }
+}
+
+/// A credential offered to a page, without its secrets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialMatch {
+    pub entry_id: String,
+    pub title: String,
+    pub username: String,
+    pub url: Option<String>,
+    pub strength: MatchStrength,
+}
+
+impl CredentialMatch {
+    fn new(entry: &PasswordEntry, strength: MatchStrength) -> Self {
+        Self {
+            entry_id: entry.id.clone(),
+            title: entry.title.clone(),
+            username: entry.username.clone(),
+            url: entry.url.clone(),
+            strength,
+        }
+    }
+}
+
+/// Secrets released to a page once the user allowed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credential {
+    pub username: String,
+    pub password: String,
+    pub totp_code: Option<String>,
+}
+
+// The origin a window may ask about. Sandboxed apps are pinned to the page
+// they are showing, whatever they claim.
+fn requesting_origin(window: &tauri::Window, url: Option<String>) -> Result<String, String> {
+    match url {
+        Some(url) if window.label() == TRUSTED_WINDOW => Ok(url),
+        Some(_) => Err("Only the marketplace can look up credentials for another origin".to_string()),
+        None => Ok(window.url().to_string()),
+    }
+}
+
+// Ask in a native dialog, which the requesting page can't script or restyle
+async fn confirm_autofill(window: &tauri::Window, origin: &Origin, entry: &PasswordEntry) -> bool {
+    let parent = window.clone();
+    let message = format!(
+        "{} is asking for your saved login \"{}\" ({}).\n\nAllow it to receive this password?",
+        origin, entry.title, entry.username
+    );
+
+    tokio::task::spawn_blocking(move || {
+        MessageDialogBuilder::new("Allow autofill?", message)
+            .parent(&parent)
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancel)
+            .show()
+    })
+    .await
+    .unwrap_or(false)
+}
+
+// Tauri commands for frontend integration
+
+#[tauri::command]
+pub async fn find_credentials_for_origin(
+    url: Option<String>,
+    window: tauri::Window,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<CredentialMatch>, String> {
+    let origin = requesting_origin(&window, url)?;
+    let matches = manager_from_state(&state)?
+        .find_credentials_for_origin(&origin).await
+        .map_err(|e| e.to_string())?;
+
+    Ok(matches.iter().map(|(entry, strength)| CredentialMatch::new(entry, *strength)).collect())
+}
+
+#[tauri::command]
+pub async fn request_autofill(
+    entry_id: String,
+    url: Option<String>,
+    window: tauri::Window,
+    state: tauri::State<'_, AppState>,
+) -> Result<Credential, String> {
+    let origin = Origin::parse(&requesting_origin(&window, url)?).map_err(|e| e.to_string())?;
+    let password_manager = manager_from_state(&state)?;
+
+    // Re-check the match so an app can't ask for an id it was never offered
+    let (entry, _) = password_manager.find_credentials_for_origin(&origin.to_string()).await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|(entry, _)| entry.id == entry_id)
+        .ok_or_else(|| format!("No saved login for {} with that id", origin))?;
+
+    if !confirm_autofill(&window, &origin, &entry).await {
+        log::info!("Autofill of {} into {} was declined", entry.id, origin);
+        return Err("Autofill request was declined".to_string());
+    }
+
+    password_manager.mark_used(&entry.id).await
+        .map_err(|e| e.to_string())?;
+    log::info!("Released credentials for {} to {}", entry.id, origin);
+
+    let totp_code = entry.totp_secret.as_deref()
+        .map(|secret| Totp::parse(secret).map(|totp| totp.current().code))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Credential {
+        username: entry.username.clone(),
+        password: entry.password.clone(),
+        totp_code,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(url: &str) -> Origin {
+        Origin::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_origin_normalization() {
+        assert_eq!(origin("HTTPS://Login.Example.COM:443/path?q=1").to_string(), "https://login.example.com");
+        assert_eq!(origin("example.com/login").to_string(), "https://example.com");
+        assert_eq!(origin("http://localhost:3000/app").to_string(), "http://localhost:3000");
+        assert_eq!(origin("https://bücher.de").host, "xn--bcher-kva.de");
+        assert_eq!(origin("https://example.com.").host, "example.com");
+
+        assert!(Origin::parse("ftp://example.com").is_err());
+        assert!(Origin::parse("tauri://localhost").is_err());
+        assert!(Origin::parse("").is_err());
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("mail.google.com").as_deref(), Some("google.com"));
+        assert_eq!(registrable_domain("a.b.example.co.uk").as_deref(), Some("example.co.uk"));
+        assert_eq!(registrable_domain("alice.github.io").as_deref(), Some("alice.github.io"));
+        assert_eq!(registrable_domain("example.dev").as_deref(), Some("example.dev"));
+
+        assert_eq!(registrable_domain("co.uk"), None);
+        assert_eq!(registrable_domain("github.io"), None);
+        assert_eq!(registrable_domain("localhost"), None);
+        assert_eq!(registrable_domain("127.0.0.1"), None);
+        assert_eq!(registrable_domain("[::1]"), None);
+    }
+
+    #[test]
+    fn test_match_modes() {
+        let request = origin("https://accounts.example.com/signin");
+
+        let domain = match_entry("https://example.com", MatchMode::Domain, &request);
+        assert_eq!(domain, Some(MatchStrength::Domain));
+        assert_eq!(match_entry("https://example.com", MatchMode::Host, &request), None);
+        assert_eq!(
+            match_entry("http://accounts.example.com", MatchMode::Host, &request),
+            Some(MatchStrength::Host)
+        );
+        assert_eq!(match_entry("http://accounts.example.com", MatchMode::Exact, &request), None);
+        assert_eq!(
+            match_entry("accounts.example.com/other", MatchMode::Exact, &request),
+            Some(MatchStrength::Exact)
+        );
+        assert_eq!(match_entry("https://accounts.example.com", MatchMode::Never, &request), None);
+
+        // Different sites under shared hosting and look-alike domains never match
+        let pages = origin("https://bob.github.io");
+        assert_eq!(match_entry("https://alice.github.io", MatchMode::Domain, &pages), None);
+        assert_eq!(match_entry("https://example.com.evil.net", MatchMode::Domain, &request), None);
+
+        // https logins stay off plain http, and local apps are told apart by port
+        assert_eq!(match_entry("https://example.com", MatchMode::Domain, &origin("http://example.com")), None);
+        let app = origin("http://localhost:3000");
+        assert_eq!(match_entry("http://localhost:3000/", MatchMode::Domain, &app), Some(MatchStrength::Exact));
+        assert_eq!(match_entry("http://localhost:4000", MatchMode::Domain, &app), None);
+    }
+}
@@ -0,0 +1,120 @@
+// src-tauri/src/config_migrations.rs
+use crate::app_config::{ConfigError, CURRENT_CONFIG_VERSION};
+use serde_json::{json, Value};
+
+/// A single upgrade step. `MIGRATIONS[n]` turns a version `n` document into version `n + 1`.
+type MigrationStep = fn(&mut Value) -> Result<(), ConfigError>;
+
+const MIGRATIONS: &[MigrationStep] = &[
+    migrate_v0_to_v1,
+];
+
+/// Read the schema version of a raw config document. Files written before
+/// versioning was introduced carry no `version` field and count as version 0.
+pub fn detect_version(config: &Value) -> Result<u32, ConfigError> {
+    match config.get("version") {
+        None | Some(Value::Null) => Ok(0),
+        Some(Value::Number(n)) => n.as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| ConfigError::Migration(format!("invalid version field: {}", n))),
+        Some(other) => Err(ConfigError::Migration(format!("invalid version field: {}", other))),
+    }
+}
+
+/// Apply every pending upgrade step in order. Returns the version the document started at.
+pub fn migrate(config: &mut Value) -> Result<u32, ConfigError> {
+    debug_assert_eq!(MIGRATIONS.len() as u32, CURRENT_CONFIG_VERSION);
+
+    if !config.is_object() {
+        return Err(ConfigError::Migration("config root must be a JSON object".to_string()));
+    }
+
+    let from_version = detect_version(config)?;
+    if from_version > CURRENT_CONFIG_VERSION {
+        return Err(ConfigError::UnsupportedVersion {
+            found: from_version,
+            supported: CURRENT_CONFIG_VERSION,
+        });
+    }
+
+    for version in from_version..CURRENT_CONFIG_VERSION {
+        log::info!("Migrating config.json from version {} to {}", version, version + 1);
+        MIGRATIONS[version as usize](config)?;
+        config["version"] = json!(version + 1);
+    }
+
+    Ok(from_version)
+}
+
+// Version 0: untyped file with only the folders chosen in the setup wizard
+fn migrate_v0_to_v1(config: &mut Value) -> Result<(), ConfigError> {
+    for field in ["apps_folder", "data_folder"] {
+        if !config.get(field).map(Value::is_string).unwrap_or(false) {
+            return Err(ConfigError::Migration(format!("version 0 config is missing {}", field)));
+        }
+    }
+
+    if config.get("marketplace_url").is_none() {
+        config["marketplace_url"] = json!("http://localhost:3000");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::AppConfig;
+
+    const FIXTURES: &[(u32, &str)] = &[
+        (0, include_str!("../fixtures/config/v0.json")),
+        (1, include_str!("../fixtures/config/v1.json")),
+    ];
+
+    #[test]
+    fn test_every_historical_version_has_a_fixture() {
+        let versions: Vec<u32> = FIXTURES.iter().map(|(v, _)| *v).collect();
+        let expected: Vec<u32> = (0..=CURRENT_CONFIG_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn test_fixtures_migrate_to_current() {
+        for (version, fixture) in FIXTURES {
+            let mut value: Value = serde_json::from_str(fixture).unwrap();
+            assert_eq!(detect_version(&value).unwrap(), *version);
+
+            let from = migrate(&mut value).unwrap();
+            assert_eq!(from, *version);
+            assert_eq!(detect_version(&value).unwrap(), CURRENT_CONFIG_VERSION);
+
+            let config: AppConfig = serde_json::from_value(value)
+                .unwrap_or_else(|e| panic!("fixture v{} did not migrate cleanly: {}", version, e));
+            assert_eq!(config.apps_folder.to_string_lossy(), "/home/user/PWA-Apps");
+            assert_eq!(config.data_folder.to_string_lossy(), "/home/user/PWA-Data");
+        }
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut value = json!({
+            "version": CURRENT_CONFIG_VERSION + 1,
+            "apps_folder": "/a",
+            "data_folder": "/d",
+        });
+
+        match migrate(&mut value) {
+            Err(ConfigError::UnsupportedVersion { found, supported }) => {
+                assert_eq!(found, CURRENT_CONFIG_VERSION + 1);
+                assert_eq!(supported, CURRENT_CONFIG_VERSION);
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_v0_without_folders_fails() {
+        let mut value = json!({ "marketplace_url": "http://localhost:3000" });
+        assert!(matches!(migrate(&mut value), Err(ConfigError::Migration(_))));
+    }
+}
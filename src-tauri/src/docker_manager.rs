@@ -2,17 +2,50 @@
 use bollard::{Docker, API_DEFAULT_VERSION};
 use bollard::container::{
     Config, CreateContainerOptions, StartContainerOptions, 
-    StopContainerOptions, RemoveContainerOptions, ListContainersOptions
+    StopContainerOptions, RemoveContainerOptions, ListContainersOptions, RenameContainerOptions,
+    LogOutput, LogsOptions,
 };
-use bollard::image::{CreateImageOptions, ListImagesOptions};
-use bollard::service::{ContainerSummary, HostConfig, PortBinding};
-use bollard::network::{CreateNetworkOptions, ListNetworksOptions};
+use bollard::image::{CreateImageOptions, ListImagesOptions, TagImageOptions};
+use bollard::service::{
+    ContainerInspectResponse, ContainerSummary, Health, HealthConfig, HealthStatusEnum, HostConfig, PortBinding,
+};
+use bollard::network::{CreateNetworkOptions, InspectNetworkOptions, ListNetworksOptions};
+use bollard::system::EventsOptions;
+use bollard::service::{EventMessage, EventMessageTypeEnum};
 use futures::stream::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
-use tokio::time::{sleep, Duration};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
+use crate::image_pull::{registry_of, PullPhase, PullProgress};
+use crate::image_trust::{repository, ImageTrustError, LocalImage, RecordedDigests, TrustedKeys};
+use crate::logger::{self, LogLevel};
+use crate::service_spec::{Healthcheck, Probe, ServiceSpec, ServiceSpecError, ServiceStack};
+
+/// Time allowed for a single HTTP or TCP probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Probe failures closer together than this count once towards the failing
+/// streak, so checking status more often than the supervisor polls doesn't
+/// get a service restarted sooner
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Largest `docker save` tarball `load_image_bundle` accepts
+const MAX_IMAGE_BUNDLE_BYTES: u64 = 16 * 1024 * 1024 * 1024;
+
+/// Minimum time between pull progress reports
+const PULL_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Health checks of an upgraded container, two seconds apart
+const UPGRADE_HEALTH_ATTEMPTS: u32 = 60;
+
+/// Checks a container without any health check must survive before it counts as up
+const UPGRADE_GRACE_ATTEMPTS: u32 = 3;
 
 #[derive(Error, Debug)]
 pub enum DockerError {
@@ -30,17 +63,429 @@ pub enum DockerError {
     Network(String),
     #[error("Configuration error: {0}")]
     Config(String),
+    #[error("Service definition error: {0}")]
+    ServiceSpec(#[from] ServiceSpecError),
+    #[error("Failed to pull {image} from {registry}: {message}")]
+    Pull { image: String, registry: String, message: String },
+    #[error("Image pull cancelled")]
+    PullCancelled,
+    #[error("Image bundle {path}: {message}")]
+    Bundle { path: String, message: String },
+    #[error("Untrusted image: {0}")]
+    ImageTrust(#[from] ImageTrustError),
+    #[error("Upgrade of {service} failed: {message}")]
+    Upgrade { service: String, message: String },
+}
+
+fn bundle_error(path: &Path, message: impl ToString) -> DockerError {
+    DockerError::Bundle {
+        path: path.display().to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Unhealthy,
+    /// Running, but no health check has passed yet
+    Starting,
+    /// The container isn't running
+    Stopped,
+    /// Running, with neither a HEALTHCHECK nor a probe to ask
+    Unknown,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Unhealthy => "unhealthy",
+            HealthStatus::Starting => "starting",
+            HealthStatus::Stopped => "stopped",
+            HealthStatus::Unknown => "unknown",
+        }
+    }
+    
+    /// Whether the service can be used, as far as we can tell
+    pub fn is_ready(&self) -> bool {
+        matches!(self, HealthStatus::Healthy | HealthStatus::Unknown)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceStatus {
     pub name: String,
     pub status: String,
-    pub health: String,
+    pub health: HealthStatus,
+    /// Consecutive failed health checks
+    pub failing_streak: u32,
+    /// Output of the last failed health check
+    pub health_detail: Option<String>,
     pub ports: Vec<String>,
     pub uptime: Option<String>,
 }
 
+impl ServiceStatus {
+    /// Status of a service whose container doesn't exist
+    fn missing(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: "missing".to_string(),
+            health: HealthStatus::Stopped,
+            failing_streak: 0,
+            health_detail: None,
+            ports: Vec::new(),
+            uptime: None,
+        }
+    }
+}
+
+struct HealthReport {
+    status: HealthStatus,
+    failing_streak: u32,
+    detail: Option<String>,
+}
+
+impl HealthReport {
+    fn new(status: HealthStatus) -> Self {
+        Self { status, failing_streak: 0, detail: None }
+    }
+}
+
+/// Failing probe streak of a service without a Docker HEALTHCHECK
+#[derive(Debug, Clone, Copy)]
+struct ProbeFailures {
+    streak: u32,
+    /// When the last failure that counted was seen
+    counted_at: Instant,
+}
+
+impl ProbeFailures {
+    // Count a failure seen at `now`, unless one was already counted within `PROBE_INTERVAL`
+    fn record(previous: Option<Self>, now: Instant) -> Self {
+        match previous {
+            Some(previous) if now.duration_since(previous.counted_at) < PROBE_INTERVAL => previous,
+            Some(previous) => Self { streak: previous.streak + 1, counted_at: now },
+            None => Self { streak: 1, counted_at: now },
+        }
+    }
+}
+
+/// Container events followed through the Docker events API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerAction {
+    Start,
+    Die,
+    Oom,
+    HealthStatus,
+}
+
+/// Something that happened to a container on the marketplace network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerEvent {
+    pub id: String,
+    pub name: String,
+    pub action: ContainerAction,
+    /// New HEALTHCHECK result, for `health_status` events
+    pub health: Option<HealthStatus>,
+    /// For `die` events
+    pub exit_code: Option<i64>,
+    /// Unix time of the event
+    pub time: i64,
+}
+
+impl ContainerEvent {
+    /// The event in a Docker event message, if it's one we follow
+    fn from_message(message: &EventMessage) -> Option<Self> {
+        if message.typ != Some(EventMessageTypeEnum::CONTAINER) {
+            return None;
+        }
+        let actor = message.actor.as_ref()?;
+        let attribute = |key: &str| actor.attributes.as_ref().and_then(|attributes| attributes.get(key));
+        
+        // Health changes arrive as e.g. "health_status: healthy"
+        let (action, health) = match message.action.as_deref()?.split_once(':') {
+            Some(("health_status", health)) => {
+                let health = match health.trim() {
+                    "healthy" => HealthStatus::Healthy,
+                    "unhealthy" => HealthStatus::Unhealthy,
+                    _ => HealthStatus::Starting,
+                };
+                (ContainerAction::HealthStatus, Some(health))
+            }
+            Some(_) => return None,
+            None => match message.action.as_deref()? {
+                "start" => (ContainerAction::Start, None),
+                "die" => (ContainerAction::Die, None),
+                "oom" => (ContainerAction::Oom, None),
+                _ => return None,
+            },
+        };
+        
+        Some(Self {
+            id: actor.id.clone()?,
+            name: attribute("name")?.clone(),
+            action,
+            health,
+            exit_code: attribute("exitCode").and_then(|code| code.parse().ok()),
+            time: message.time.unwrap_or(0),
+        })
+    }
+}
+
+/// Last known state of a container, kept up to date from Docker events
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LiveState {
+    pub running: bool,
+    /// None until the container reports a HEALTHCHECK result
+    pub health: Option<HealthStatus>,
+    /// Exit code of the last run
+    pub exit_code: Option<i64>,
+    /// Whether the kernel killed the container for running out of memory since it last started
+    pub oom_killed: bool,
+    /// Unix time of the last event
+    pub updated_at: i64,
+}
+
+impl LiveState {
+    fn apply(&mut self, event: &ContainerEvent) {
+        match event.action {
+            ContainerAction::Start => {
+                *self = LiveState { running: true, ..Default::default() };
+            }
+            ContainerAction::Die => {
+                self.running = false;
+                self.health = Some(HealthStatus::Stopped);
+                self.exit_code = event.exit_code;
+            }
+            ContainerAction::Oom => self.oom_killed = true,
+            ContainerAction::HealthStatus => self.health = event.health,
+        }
+        self.updated_at = event.time;
+    }
+}
+
+/// Which container logs to read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    /// Last lines to start from, all of them when None
+    pub tail: Option<u64>,
+    /// Unix time of the oldest line to include
+    pub since: Option<i64>,
+    /// Unix time of the newest line to include
+    pub until: Option<i64>,
+    pub stdout: bool,
+    pub stderr: bool,
+}
+
+impl Default for LogQuery {
+    fn default() -> Self {
+        Self {
+            tail: Some(200),
+            since: None,
+            until: None,
+            stdout: true,
+            stderr: true,
+        }
+    }
+}
+
+impl LogQuery {
+    fn options(&self, follow: bool) -> LogsOptions<String> {
+        LogsOptions {
+            follow,
+            stdout: self.stdout,
+            stderr: self.stderr,
+            since: self.since.unwrap_or(0),
+            until: self.until.unwrap_or(0),
+            timestamps: true,
+            tail: self.tail.map_or_else(|| "all".to_string(), |tail| tail.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of a service's output, also the payload of `service-log` events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    pub service: String,
+    pub stream: LogStream,
+    /// RFC 3339 time Docker received the line
+    pub timestamp: Option<String>,
+    pub message: String,
+}
+
+impl LogLine {
+    /// Logger target for the service's lines, e.g. `docker::mcp-bridge`
+    pub fn target(&self) -> String {
+        format!("docker::{}", self.service)
+    }
+    
+    /// Write the line to the app log, stderr as warnings
+    pub fn log(&self) {
+        if let Some(logger) = logger::get_logger() {
+            let level = match self.stream {
+                LogStream::Stdout => LogLevel::Info,
+                LogStream::Stderr => LogLevel::Warn,
+            };
+            logger.log(level, &self.target(), &self.message);
+        }
+    }
+}
+
+// Tags reported by `docker load`, e.g. `Loaded image: mcp-bridge:latest`
+fn loaded_images(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Loaded image: "))
+        .map(str::to_string)
+        .collect()
+}
+
+// Split a chunk of timestamped Docker log output into lines
+fn log_lines(service: &str, output: LogOutput) -> Vec<LogLine> {
+    let (stream, message) = match output {
+        LogOutput::StdErr { message } => (LogStream::Stderr, message),
+        LogOutput::StdOut { message } | LogOutput::Console { message } => (LogStream::Stdout, message),
+        LogOutput::StdIn { .. } => return Vec::new(),
+    };
+    
+    String::from_utf8_lossy(&message)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (timestamp, message) = match line.split_once(' ') {
+                Some((timestamp, message)) if timestamp.ends_with('Z') && timestamp.contains('T') => {
+                    (Some(timestamp.to_string()), message)
+                }
+                _ => (None, line),
+            };
+            LogLine {
+                service: service.to_string(),
+                stream,
+                timestamp,
+                message: message.trim_end_matches('\r').to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Result of `upgrade_service`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceUpgrade {
+    pub service: String,
+    /// Image ID now running
+    pub image: String,
+    /// Image ID `rollback_service` returns to, if there was a previous container
+    pub previous_image: Option<String>,
+    pub switch_over: SwitchOver,
+}
+
+/// How `upgrade_service` moves a service onto its proven candidate container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchOver {
+    /// The candidate takes over the container name, and with it the DNS name
+    /// on the network, while the old container still runs: no downtime
+    Rename,
+    /// Host ports can't be bound twice, so the old container is stopped before
+    /// the new one starts: the service is down until that passes its health check
+    Restart,
+}
+
+impl SwitchOver {
+    pub fn for_service(spec: &ServiceSpec) -> Self {
+        if spec.ports.is_empty() {
+            SwitchOver::Rename
+        } else {
+            SwitchOver::Restart
+        }
+    }
+    
+    pub fn is_zero_downtime(self) -> bool {
+        self == SwitchOver::Rename
+    }
+}
+
+/// One container operation of an upgrade's switch-over or a rollback
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SwitchStep {
+    /// Best effort, the container may not exist
+    Remove(String),
+    /// Best effort, the container may not be running
+    Stop(String),
+    Rename { from: String, to: String },
+    /// Create the service's container from an image ID
+    Create { name: String, image: String },
+    Start(String),
+}
+
+// Steps that move service `name` onto the healthy `<name>-candidate` running
+// `image`, keeping the current container, if any, as `<name>-previous` for a rollback
+fn switch_steps(name: &str, image: &str, switch_over: SwitchOver, has_current: bool) -> Vec<SwitchStep> {
+    let previous = previous_name(name);
+    let candidate = candidate_name(name);
+    // Only one previous version is kept
+    let mut steps = vec![SwitchStep::Remove(previous.clone())];
+    
+    match switch_over {
+        SwitchOver::Rename => {
+            if has_current {
+                steps.push(SwitchStep::Rename { from: name.to_string(), to: previous.clone() });
+            }
+            steps.push(SwitchStep::Rename { from: candidate, to: name.to_string() });
+            // Requests in flight to the old container finish first
+            if has_current {
+                steps.push(SwitchStep::Stop(previous));
+            }
+        }
+        SwitchOver::Restart => {
+            // The candidate ran without host ports, so the proven image gets a restart under the real name
+            steps.push(SwitchStep::Remove(candidate));
+            if has_current {
+                steps.push(SwitchStep::Stop(name.to_string()));
+                steps.push(SwitchStep::Rename { from: name.to_string(), to: previous });
+            }
+            steps.push(SwitchStep::Create { name: name.to_string(), image: image.to_string() });
+            steps.push(SwitchStep::Start(name.to_string()));
+        }
+    }
+    
+    steps
+}
+
+// Steps that put `<name>-previous` back in place of service `name`
+fn rollback_steps(name: &str) -> Vec<SwitchStep> {
+    vec![
+        SwitchStep::Remove(name.to_string()),
+        SwitchStep::Rename { from: previous_name(name), to: name.to_string() },
+        SwitchStep::Start(name.to_string()),
+    ]
+}
+
+// Steps that bring back the container service `name` ran before a switch-over
+// failed part way, `renamed` once it has become `<name>-previous`
+fn restore_steps(name: &str, renamed: bool) -> Vec<SwitchStep> {
+    let mut steps = vec![SwitchStep::Remove(candidate_name(name))];
+    if renamed {
+        steps.extend(rollback_steps(name));
+    } else {
+        // It never left its name, but may have been stopped
+        steps.push(SwitchStep::Start(name.to_string()));
+    }
+    steps
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DockerInfo {
     pub version: String,
@@ -51,11 +496,29 @@ pub struct DockerInfo {
     pub memory_total: u64,
 }
 
+#[derive(Clone)]
 pub struct DockerManager {
     docker: Docker,
     apps_folder: PathBuf,
     data_folder: PathBuf,
     network_name: String,
+    services: ServiceStack,
+    /// Consecutive probe failures per service; Docker tracks its own HEALTHCHECKs
+    probe_failures: Arc<Mutex<HashMap<String, ProbeFailures>>>,
+    /// Whether services should be up, false until started and after a shutdown
+    services_expected: Arc<AtomicBool>,
+    /// Container states by name, as of the last Docker event for each
+    live_states: Arc<Mutex<HashMap<String, LiveState>>>,
+    /// Set to true to abort the image pull in progress
+    pull_cancel: Arc<watch::Sender<bool>>,
+    /// `docker save` tarball that missing images are loaded from before pulling
+    image_bundle: Option<PathBuf>,
+    /// Pins for images services.toml doesn't pin, taken when they first arrive
+    recorded_digests: Arc<Mutex<RecordedDigests>>,
+    /// Services in the middle of `upgrade_service`, left alone by the supervisor
+    upgrading: Arc<Mutex<HashSet<String>>>,
+    /// One per followed service; dropping it ends that `follow_logs`
+    log_followers: Arc<Mutex<HashMap<String, watch::Sender<()>>>>,
 }
 
 impl DockerManager {
@@ -63,14 +526,52 @@ impl DockerManager {
         let docker = Docker::connect_with_local_defaults()
             .unwrap_or_else(|_| Docker::connect_with_http_defaults().unwrap());
         
+        let services = ServiceStack::load().unwrap_or_else(|e| {
+            log::error!("Ignoring services.toml, using the bundled services: {}", e);
+            ServiceStack::bundled()
+        });
+        let recorded_digests = RecordedDigests::default_path()
+            .map(|path| RecordedDigests::load_from(&path))
+            .transpose()
+            .unwrap_or_else(|e| {
+                log::error!("Ignoring recorded image digests, images will be trusted anew: {}", e);
+                None
+            })
+            .unwrap_or_default();
+        
         DockerManager {
             docker,
             apps_folder: PathBuf::from(apps_folder),
             data_folder: PathBuf::from(data_folder),
             network_name: "pwa-marketplace".to_string(),
+            services,
+            probe_failures: Arc::new(Mutex::new(HashMap::new())),
+            services_expected: Arc::new(AtomicBool::new(false)),
+            live_states: Arc::new(Mutex::new(HashMap::new())),
+            pull_cancel: Arc::new(watch::channel(false).0),
+            image_bundle: None,
+            recorded_digests: Arc::new(Mutex::new(recorded_digests)),
+            upgrading: Arc::new(Mutex::new(HashSet::new())),
+            log_followers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
     
+    /// Load images from a `docker save` tarball instead of a registry where possible
+    pub fn with_image_bundle(mut self, image_bundle: Option<PathBuf>) -> Self {
+        self.image_bundle = image_bundle;
+        self
+    }
+    
+    /// The services this manager runs
+    pub fn services(&self) -> &ServiceStack {
+        &self.services
+    }
+    
+    /// Whether the services have been started and not deliberately stopped since
+    pub fn services_expected(&self) -> bool {
+        self.services_expected.load(Ordering::SeqCst)
+    }
+    
     pub async fn check_docker_available(&self) -> Result<bool, DockerError> {
         match self.docker.ping().await {
             Ok(_) => Ok(true),
@@ -104,7 +605,11 @@ impl DockerManager {
         Ok(())
     }
     
-    pub async fn start_marketplace_services(&self) -> Result<(), DockerError> {
+    /// Bring the stack up, reporting image pull progress to `on_pull_progress`
+    pub async fn start_marketplace_services<F>(&self, on_pull_progress: F) -> Result<(), DockerError>
+    where
+        F: FnMut(&PullProgress),
+    {
         // Ensure Docker is available
         self.install_docker_if_needed().await?;
         
@@ -112,12 +617,13 @@ impl DockerManager {
         self.ensure_network_exists().await?;
         
         // Pull required images
-        self.pull_marketplace_images().await?;
+        self.pull_marketplace_images(on_pull_progress).await?;
         
-        // Start core services
-        self.start_marketplace_container().await?;
-        self.start_mcp_bridge_container().await?;
-        self.start_resource_controller_container().await?;
+        // Start services after the ones they depend on
+        for (name, spec) in self.services.start_order()? {
+            self.start_service(name, spec).await?;
+        }
+        self.services_expected.store(true, Ordering::SeqCst);
         
         // Wait for services to be ready
         self.wait_for_services_ready().await?;
@@ -126,9 +632,9 @@ impl DockerManager {
     }
     
     pub async fn stop_marketplace_services(&self) -> Result<(), DockerError> {
-        let containers = ["pwa-marketplace", "mcp-bridge", "resource-controller"];
+        self.services_expected.store(false, Ordering::SeqCst);
         
-        for container_name in &containers {
+        for (container_name, _) in self.services.stop_order()? {
             if let Err(e) = self.stop_container(container_name).await {
                 log::warn!("Failed to stop container {}: {}", container_name, e);
             }
@@ -139,16 +645,373 @@ impl DockerManager {
     
     pub async fn get_services_status(&self) -> Result<Vec<ServiceStatus>, DockerError> {
         let mut statuses = Vec::new();
-        let containers = ["pwa-marketplace", "mcp-bridge", "resource-controller"];
         
-        for container_name in &containers {
-            let status = self.get_container_status(container_name).await?;
+        for (container_name, _) in self.services.start_order()? {
+            let status = match self.get_container_status(container_name).await {
+                Err(DockerError::ContainerNotFound(_)) => ServiceStatus::missing(container_name),
+                result => result?,
+            };
             statuses.push(status);
         }
         
         Ok(statuses)
     }
     
+    /// Recreate a single service's container from its definition
+    pub async fn restart_service(&self, name: &str) -> Result<(), DockerError> {
+        let spec = self.services.services.get(name)
+            .ok_or_else(|| DockerError::ContainerNotFound(name.to_string()))?;
+        
+        self.probe_failures.lock().unwrap().remove(name);
+        self.start_service(name, spec).await
+    }
+    
+    /// Container states seen by `watch_events`, by container name
+    pub fn live_states(&self) -> HashMap<String, LiveState> {
+        self.live_states.lock().unwrap().clone()
+    }
+    
+    /// Follow start, die, oom and health_status events of the containers on the
+    /// marketplace network, updating the live state cache and passing every event
+    /// on to `on_event`. Returns when Docker closes the stream.
+    pub async fn watch_events<F>(&self, mut on_event: F) -> Result<(), DockerError>
+    where
+        F: FnMut(&ContainerEvent, &LiveState),
+    {
+        let options = EventsOptions::<String> {
+            filters: HashMap::from([
+                ("type".to_string(), vec!["container".to_string()]),
+                (
+                    "event".to_string(),
+                    vec!["start".to_string(), "die".to_string(), "oom".to_string(), "health_status".to_string()],
+                ),
+            ]),
+            ..Default::default()
+        };
+        
+        // Docker's `network` filter only matches network events, so membership is tracked here
+        let mut members = self.network_members().await?;
+        let mut events = self.docker.events(Some(options));
+        
+        while let Some(message) = events.next().await {
+            let Some(event) = ContainerEvent::from_message(&message?) else {
+                continue;
+            };
+            
+            if !members.contains(&event.id) {
+                // Containers join the network as they start, recreated ones with a new ID
+                if event.action != ContainerAction::Start || !self.is_on_network(&event.id).await {
+                    continue;
+                }
+                members.insert(event.id.clone());
+            }
+            
+            let state = {
+                let mut states = self.live_states.lock().unwrap();
+                let state = states.entry(event.name.clone()).or_default();
+                state.apply(&event);
+                state.clone()
+            };
+            on_event(&event, &state);
+        }
+        
+        Ok(())
+    }
+    
+    async fn network_members(&self) -> Result<HashSet<String>, DockerError> {
+        let network = self.docker
+            .inspect_network(&self.network_name, None::<InspectNetworkOptions<String>>)
+            .await?;
+        
+        Ok(network.containers.unwrap_or_default().into_keys().collect())
+    }
+    
+    async fn is_on_network(&self, id: &str) -> bool {
+        match self.docker.inspect_container(id, None).await {
+            Ok(details) => details.network_settings
+                .and_then(|settings| settings.networks)
+                .is_some_and(|networks| networks.contains_key(&self.network_name)),
+            Err(_) => false,
+        }
+    }
+    
+    /// A service's logs as they are now
+    pub async fn service_logs(&self, name: &str, query: &LogQuery) -> Result<Vec<LogLine>, DockerError> {
+        self.check_service(name)?;
+        
+        let mut stream = self.docker.logs(name, Some(query.options(false)));
+        let mut lines = Vec::new();
+        while let Some(output) = stream.next().await {
+            lines.extend(log_lines(name, output?));
+        }
+        
+        Ok(lines)
+    }
+    
+    /// Pass a service's log lines to `on_line` as they are written, until the
+    /// container goes away, `stop_following_logs` is called or the service is
+    /// followed again
+    pub async fn follow_logs<F>(&self, name: &str, query: &LogQuery, mut on_line: F) -> Result<(), DockerError>
+    where
+        F: FnMut(LogLine),
+    {
+        self.check_service(name)?;
+        
+        let (follower, mut stopped) = watch::channel(());
+        self.log_followers.lock().unwrap().insert(name.to_string(), follower);
+        
+        let mut stream = self.docker.logs(name, Some(query.options(true)));
+        loop {
+            let output = tokio::select! {
+                output = stream.next() => output,
+                _ = stopped.changed() => break,
+            };
+            
+            match output {
+                Some(output) => log_lines(name, output?).into_iter().for_each(&mut on_line),
+                None => break,
+            }
+        }
+        
+        Ok(())
+    }
+    
+    pub fn stop_following_logs(&self, name: &str) {
+        self.log_followers.lock().unwrap().remove(name);
+    }
+    
+    // Logs are only offered for the stack's own containers
+    fn check_service(&self, name: &str) -> Result<(), DockerError> {
+        if self.services.services.contains_key(name) {
+            Ok(())
+        } else {
+            Err(DockerError::ContainerNotFound(name.to_string()))
+        }
+    }
+    
+    /// Whether `upgrade_service` is replacing this service's container right now
+    pub fn is_upgrading(&self, name: &str) -> bool {
+        self.upgrading.lock().unwrap().contains(name)
+    }
+    
+    /// Pull a service's image again and move to it only once it has proven healthy.
+    /// The image tag and its pin stay on the old image until the new one has taken
+    /// over, and a failed switch-over brings the old container back. The replaced
+    /// container is kept, stopped, for `rollback_service`. Services that publish
+    /// host ports are briefly down, see `SwitchOver::Restart`.
+    pub async fn upgrade_service<F>(&self, name: &str, on_pull_progress: F) -> Result<ServiceUpgrade, DockerError>
+    where
+        F: FnMut(&PullProgress),
+    {
+        let spec = self.services.services.get(name)
+            .ok_or_else(|| DockerError::ContainerNotFound(name.to_string()))?;
+        
+        if !self.upgrading.lock().unwrap().insert(name.to_string()) {
+            return Err(upgrade_error(name, "an upgrade is already in progress"));
+        }
+        let result = self.replace_service(name, spec, on_pull_progress).await;
+        self.upgrading.lock().unwrap().remove(name);
+        
+        result
+    }
+    
+    /// Go back to the container `upgrade_service` replaced, and point the image tag at its image again
+    pub async fn rollback_service(&self, name: &str) -> Result<(), DockerError> {
+        let spec = self.services.services.get(name)
+            .ok_or_else(|| DockerError::ContainerNotFound(name.to_string()))?;
+        let previous = previous_name(name);
+        let details = self.docker.inspect_container(&previous, None).await
+            .map_err(|_| upgrade_error(name, "there is no previous version to roll back to"))?;
+        
+        // Keep later recreations, e.g. by the supervisor, on the old image too
+        if let Some(image_id) = details.image.as_deref() {
+            self.retag(image_id, &spec.image).await?;
+            if spec.digest.is_none() {
+                self.record_digest(&spec.image, true).await?;
+            }
+        }
+        
+        self.run_switch_steps(&rollback_steps(name), spec).await?;
+        self.probe_failures.lock().unwrap().remove(name);
+        
+        log::info!("Rolled {} back to its previous version", name);
+        Ok(())
+    }
+    
+    async fn replace_service<F>(&self, name: &str, spec: &ServiceSpec, on_pull_progress: F) -> Result<ServiceUpgrade, DockerError>
+    where
+        F: FnMut(&PullProgress),
+    {
+        // Pulled by digest, so the tag keeps pointing at the running image for now
+        let reference = self.upgrade_reference(spec).await?;
+        self.pull_images(vec![(spec.image.as_str(), reference.clone())], on_pull_progress).await?;
+        let image = match self.verify_image_as(spec, &reference, spec.digest.clone()).await? {
+            Some(image_id) => image_id,
+            // Upgrading is how an image trusted on first use moves on
+            None => self.local_image(&reference).await
+                .map(|local| local.id)
+                .ok_or_else(|| ImageTrustError::Missing(reference.clone()))?,
+        };
+        let current = self.docker.inspect_container(name, None).await.ok();
+        let previous_image = current.as_ref().and_then(|details| details.image.clone());
+        let current_id = current.and_then(|details| details.id);
+        
+        // A candidate next to the running container, without host ports as those are taken
+        let candidate = candidate_name(name);
+        let unpublished = ServiceSpec { ports: Vec::new(), ..spec.clone() };
+        let mut config = self.container_config(name, &unpublished)?;
+        config.image = Some(image.clone());
+        self.check_host_resources(name, spec).await?;
+        
+        let _ = self.remove_container(&candidate).await;
+        self.docker.create_container(Some(CreateContainerOptions { name: candidate.as_str(), platform: None }), config).await?;
+        self.docker.start_container(&candidate, None::<StartContainerOptions<String>>).await?;
+        log::info!("Started {} to upgrade {}", candidate, name);
+        
+        if let Err(e) = self.wait_until_healthy(&candidate, &unpublished).await {
+            let _ = self.remove_container(&candidate).await;
+            return Err(upgrade_error(name, e));
+        }
+        
+        let has_current = current_id.is_some();
+        let switch_over = SwitchOver::for_service(spec);
+        if !switch_over.is_zero_downtime() {
+            log::warn!("{} publishes host ports, so it is down while its upgraded container starts", name);
+        }
+        let switched = match self.run_switch_steps(&switch_steps(name, &image, switch_over, has_current), spec).await {
+            Ok(()) if switch_over == SwitchOver::Restart => self.wait_until_healthy(name, spec).await,
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = switched {
+            match &current_id {
+                Some(current_id) => self.restore_current(name, current_id, spec).await,
+                None => {
+                    let _ = self.remove_container(&candidate).await;
+                }
+            }
+            return Err(upgrade_error(name, e));
+        }
+        
+        // Later recreations, e.g. by the supervisor, only use the new image from here on
+        self.retag(&image, &spec.image).await?;
+        if spec.digest.is_none() {
+            self.record_digest(&spec.image, true).await?;
+        }
+        
+        self.probe_failures.lock().unwrap().remove(name);
+        log::info!("Upgraded {} to {}", name, image);
+        Ok(ServiceUpgrade {
+            service: name.to_string(),
+            image,
+            previous_image,
+            switch_over,
+        })
+    }
+    
+    // Where an upgrade pulls from: the pinned digest, else whatever the tag points to on the registry now
+    async fn upgrade_reference(&self, spec: &ServiceSpec) -> Result<String, DockerError> {
+        if let Some(digest) = &spec.digest {
+            return Ok(digest_reference(&spec.image, digest));
+        }
+        
+        let pull_error = |message: String| DockerError::Pull {
+            image: spec.image.clone(),
+            registry: registry_of(&spec.image).to_string(),
+            message,
+        };
+        let distribution = self.docker.inspect_registry_image(&spec.image, None).await
+            .map_err(|e| pull_error(e.to_string()))?;
+        let digest = distribution.descriptor.digest
+            .ok_or_else(|| pull_error("the registry returned no digest".to_string()))?;
+        
+        Ok(digest_reference(&spec.image, &digest))
+    }
+    
+    // Put the container that ran before a failed switch-over back in place. The
+    // tag hasn't moved yet, so only containers need undoing.
+    async fn restore_current(&self, name: &str, current_id: &str, spec: &ServiceSpec) {
+        let renamed = self.docker.inspect_container(&previous_name(name), None).await.ok()
+            .and_then(|details| details.id)
+            .is_some_and(|id| id == current_id);
+        
+        match self.run_switch_steps(&restore_steps(name, renamed), spec).await {
+            Ok(()) => log::info!("Restored {} to the container it ran before the upgrade", name),
+            Err(e) => log::error!("Failed to restore {} after its upgrade failed: {}", name, e),
+        }
+    }
+    
+    // Carry out switch-over or rollback steps for the service defined by `spec`
+    async fn run_switch_steps(&self, steps: &[SwitchStep], spec: &ServiceSpec) -> Result<(), DockerError> {
+        for step in steps {
+            match step {
+                SwitchStep::Remove(container) => {
+                    let _ = self.remove_container(container).await;
+                }
+                SwitchStep::Stop(container) => {
+                    let _ = self.stop_container(container).await;
+                }
+                SwitchStep::Rename { from, to } => {
+                    self.docker.rename_container(from, RenameContainerOptions { name: to.as_str() }).await?;
+                }
+                SwitchStep::Create { name, image } => {
+                    let mut config = self.container_config(name, spec)?;
+                    config.image = Some(image.clone());
+                    self.docker.create_container(Some(CreateContainerOptions { name: name.as_str(), platform: None }), config).await?;
+                }
+                SwitchStep::Start(container) => {
+                    self.docker.start_container(container, None::<StartContainerOptions<String>>).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    // Wait for a newly started container to pass its health check, or give up
+    async fn wait_until_healthy(&self, container: &str, spec: &ServiceSpec) -> Result<(), String> {
+        let mut last_detail = None;
+        
+        for attempt in 1..=UPGRADE_HEALTH_ATTEMPTS {
+            let details = self.docker.inspect_container(container, None).await
+                .map_err(|e| e.to_string())?;
+            let state = details.state.as_ref();
+            
+            if !state.and_then(|state| state.running).unwrap_or(false) {
+                let exit_code = state.and_then(|state| state.exit_code).unwrap_or_default();
+                return Err(format!("{} exited with code {}", container, exit_code));
+            }
+            
+            let native = state.and_then(|state| state.health.as_ref()).and_then(native_health);
+            let has_native = native.is_some();
+            let report = match (native, &spec.probe) {
+                (Some(report), _) => report,
+                (None, Some(probe)) => match self.run_probe(container, spec, probe, Some(&details)).await {
+                    Ok(()) => HealthReport::new(HealthStatus::Healthy),
+                    Err(e) => HealthReport { detail: Some(e), ..HealthReport::new(HealthStatus::Unhealthy) },
+                },
+                (None, None) => HealthReport::new(HealthStatus::Unknown),
+            };
+            
+            match report.status {
+                HealthStatus::Healthy => return Ok(()),
+                HealthStatus::Unknown if attempt >= UPGRADE_GRACE_ATTEMPTS => return Ok(()),
+                // Docker only reports unhealthy once the retries are used up
+                HealthStatus::Unhealthy if has_native => {
+                    return Err(format!("{} is unhealthy: {}", container, report.detail.unwrap_or_default()));
+                }
+                _ => last_detail = report.detail,
+            }
+            
+            sleep(Duration::from_secs(2)).await;
+        }
+        
+        Err(format!(
+            "{} did not become healthy: {}",
+            container,
+            last_detail.as_deref().unwrap_or("no health check output")
+        ))
+    }
+    
     pub async fn get_docker_info(&self) -> Result<DockerInfo, DockerError> {
         let info = self.docker.info().await?;
         let version = self.docker.version().await?;
@@ -167,8 +1030,7 @@ impl DockerManager {
         self.stop_marketplace_services().await?;
         
         // Remove containers
-        let containers = ["pwa-marketplace", "mcp-bridge", "resource-controller"];
-        for container_name in &containers {
+        for (container_name, _) in self.services.stop_order()? {
             if let Err(e) = self.remove_container(container_name).await {
                 log::warn!("Failed to remove container {}: {}", container_name, e);
             }
@@ -199,175 +1061,374 @@ impl DockerManager {
         Ok(())
     }
     
-    async fn pull_marketplace_images(&self) -> Result<(), DockerError> {
-        let images = [
-            "pwa-marketplace:latest",
-            "mcp-bridge:latest", 
-            "resource-controller:latest"
-        ];
+    /// Load the images in a `docker save` tarball, returning the tags it contained
+    pub async fn load_image_bundle(&self, path: &Path) -> Result<Vec<String>, DockerError> {
+        let size = tokio::fs::metadata(path).await.map_err(|e| bundle_error(path, e))?.len();
+        if size > MAX_IMAGE_BUNDLE_BYTES {
+            return Err(bundle_error(path, format!(
+                "{} bytes is more than the {} bytes an image bundle may have",
+                size, MAX_IMAGE_BUNDLE_BYTES
+            )));
+        }
+        log::info!("Loading Docker images from {} ({} bytes)", path.display(), size);
+        
+        // bollard sends the body of `/images/load` from memory, so the CLI
+        // streams the archive from disk instead
+        let output = tokio::process::Command::new("docker")
+            .arg("load")
+            .arg("--input")
+            .arg(path)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| bundle_error(path, format!("Failed to run docker load: {}", e)))?;
+        if !output.status.success() {
+            return Err(bundle_error(path, String::from_utf8_lossy(&output.stderr).trim()));
+        }
         
+        let loaded = loaded_images(&String::from_utf8_lossy(&output.stdout));
+        log::info!("Loaded {} from {}", loaded.join(", "), path.display());
+        Ok(loaded)
+    }
+    
+    /// Save every image of the stack to a `docker save` tarball, for `load_image_bundle` on another machine
+    pub async fn save_image_bundle(&self, path: &Path) -> Result<(), DockerError> {
+        let images = self.services.images();
+        log::info!("Saving {} to {}", images.join(", "), path.display());
+        
+        // Written under a temporary name so a failed export leaves no truncated bundle behind
+        let partial = path.with_extension("partial");
+        let result = async {
+            let mut file = tokio::fs::File::create(&partial).await.map_err(|e| bundle_error(path, e))?;
+            let mut stream = self.docker.export_images(&images);
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk.map_err(|e| bundle_error(path, e))?;
+                file.write_all(&chunk).await.map_err(|e| bundle_error(path, e))?;
+            }
+            file.flush().await.map_err(|e| bundle_error(path, e))
+        }.await;
+        
+        match result {
+            Ok(()) => tokio::fs::rename(&partial, path).await.map_err(|e| bundle_error(path, e)),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                Err(e)
+            }
+        }
+    }
+    
+    async fn local_image(&self, image: &str) -> Option<LocalImage> {
+        let details = self.docker.inspect_image(image).await.ok()?;
+        Some(LocalImage {
+            id: details.id.unwrap_or_default(),
+            repo_digests: details.repo_digests.unwrap_or_default(),
+        })
+    }
+    
+    // The digest from services.toml, else the one recorded when the image first arrived
+    fn image_pin(&self, spec: &ServiceSpec) -> Option<String> {
+        spec.digest.clone().or_else(|| {
+            self.recorded_digests.lock().unwrap().get(&spec.image).map(str::to_string)
+        })
+    }
+    
+    // Whether every service running `image` pins it, to the image that's present
+    async fn pins_satisfied(&self, image: &str) -> bool {
+        let pins: Vec<Option<String>> = self.services.services.values()
+            .filter(|spec| spec.image == image)
+            .map(|spec| self.image_pin(spec))
+            .collect();
+        if pins.iter().any(Option::is_none) {
+            return false;
+        }
+        
+        match self.local_image(image).await {
+            Some(local) => pins.iter().flatten().all(|digest| local.matches(image, digest)),
+            None => false,
+        }
+    }
+    
+    // Trust the local `image` from now on, unless it is already recorded and `replace` is false
+    async fn record_digest(&self, image: &str, replace: bool) -> Result<(), DockerError> {
+        if !replace && self.recorded_digests.lock().unwrap().get(image).is_some() {
+            return Ok(());
+        }
+        let Some(local) = self.local_image(image).await else {
+            return Ok(());
+        };
+        
+        self.recorded_digests.lock().unwrap().record(image, &local.id)?;
+        log::info!("Trusting {} as {} from now on", image, local.id);
+        Ok(())
+    }
+    
+    /// Check an image's pin and signature, returning the image ID it was verified as.
+    /// Once signing keys are bundled, every service needs a signature.
+    async fn verify_image(&self, spec: &ServiceSpec) -> Result<Option<String>, DockerError> {
+        self.verify_image_as(spec, &spec.image, self.image_pin(spec)).await
+    }
+    
+    // `verify_image` for the local image `reference` of the service, against `pin`
+    async fn verify_image_as(&self, spec: &ServiceSpec, reference: &str, pin: Option<String>) -> Result<Option<String>, DockerError> {
+        let keys = TrustedKeys::bundled();
+        if spec.signature.is_none() && !keys.is_empty() {
+            return Err(ImageTrustError::Unsigned(spec.image.clone()).into());
+        }
+        let Some(digest) = pin else {
+            return Ok(None);
+        };
+        
+        let local = self.local_image(reference).await
+            .ok_or_else(|| ImageTrustError::Missing(reference.to_string()))?;
+        let image_id = local.verify(&spec.image, &digest)?;
+        if let Some(signature) = &spec.signature {
+            keys.verify(&spec.image, &digest, signature)?;
+        }
+        
+        Ok(Some(image_id))
+    }
+    
+    async fn missing_images<'a>(&self, images: &[&'a str]) -> Vec<&'a str> {
+        let mut missing = Vec::new();
+        for image in images {
+            if self.docker.inspect_image(image).await.is_err() {
+                missing.push(*image);
+            }
+        }
+        missing
+    }
+    
+    /// Abort the image pull in progress, if any; it fails with `PullCancelled`
+    pub fn cancel_image_pull(&self) {
+        self.pull_cancel.send_replace(true);
+    }
+    
+    async fn pull_marketplace_images<F>(&self, on_progress: F) -> Result<(), DockerError>
+    where
+        F: FnMut(&PullProgress),
+    {
+        let mut images = self.services.images();
+        if let Some(bundle) = &self.image_bundle {
+            // Images already present are never pulled, so air-gapped machines don't need a registry
+            if !self.missing_images(&images).await.is_empty() {
+                self.load_image_bundle(bundle).await?;
+            }
+            images = self.missing_images(&images).await;
+        }
+        
+        // Pulling can't improve on a pinned image that is already here, only move its tag away
+        let mut pinned = Vec::new();
         for image in &images {
-            log::info!("Pulling Docker image: {}", image);
+            if self.pins_satisfied(image).await {
+                pinned.push(*image);
+            }
+        }
+        images.retain(|image| !pinned.contains(image));
+        
+        // Images services.toml pins are fetched by digest, wherever their tag points now
+        let references: Vec<(&str, String)> = images.iter()
+            .map(|image| (*image, self.pull_reference(image)))
+            .collect();
+        self.pull_images(references.clone(), on_progress).await?;
+        for (image, reference) in &references {
+            if reference != image {
+                self.retag(reference, image).await?;
+            }
+        }
+        
+        // Pin what arrived, so a moved tag is refused rather than run from now on
+        for spec in self.services.services.values().filter(|spec| spec.digest.is_none()) {
+            self.record_digest(&spec.image, false).await?;
+        }
+        Ok(())
+    }
+    
+    // `repository@digest` for an image services.toml pins, else the image itself
+    fn pull_reference(&self, image: &str) -> String {
+        self.services.services.values()
+            .filter(|spec| spec.image == image)
+            .find_map(|spec| spec.digest.as_deref())
+            .map(|digest| digest_reference(image, digest))
+            .unwrap_or_else(|| image.to_string())
+    }
+    
+    // Point `image`'s tag at `source`, an image ID or `repository@digest`
+    async fn retag(&self, source: &str, image: &str) -> Result<(), DockerError> {
+        let (repo, tag) = split_tag(image);
+        self.docker.tag_image(source, Some(TagImageOptions { repo, tag })).await?;
+        Ok(())
+    }
+    
+    // Pull each image by the reference paired with it; progress is reported per image
+    async fn pull_images<F>(&self, images: Vec<(&str, String)>, mut on_progress: F) -> Result<(), DockerError>
+    where
+        F: FnMut(&PullProgress),
+    {
+        let names: Vec<&str> = images.iter().map(|(image, _)| *image).collect();
+        let mut progress = PullProgress::new(&names);
+        let mut cancelled = self.pull_cancel.subscribe();
+        self.pull_cancel.send_replace(false);
+        on_progress(&progress);
+        
+        for (index, (image, reference)) in images.iter().enumerate() {
+            log::info!("Pulling Docker image: {}", reference);
             
             let options = Some(CreateImageOptions {
-                from_image: image.to_string(),
+                from_image: reference.clone(),
                 ..Default::default()
             });
             
             let mut stream = self.docker.create_image(options, None, None);
+            let mut last_report = Instant::now();
             
-            while let Some(result) = stream.next().await {
-                match result {
-                    Ok(info) => {
-                        if let Some(status) = info.status {
-                            log::debug!("Pull progress: {}", status);
-                        }
+            loop {
+                let result = tokio::select! {
+                    result = stream.next() => result,
+                    _ = cancelled.wait_for(|cancelled| *cancelled) => {
+                        log::info!("Pull of {} cancelled", image);
+                        progress.finish(index, PullPhase::Cancelled, None);
+                        on_progress(&progress);
+                        return Err(DockerError::PullCancelled);
                     }
-                    Err(e) => {
-                        log::error!("Failed to pull image {}: {}", image, e);
-                        return Err(DockerError::Api(e));
-                    }
-                }
+                };
+                
+                let message = match result {
+                    None => break,
+                    Some(Ok(info)) => match info.error.clone() {
+                        None => {
+                            progress.apply(index, &info);
+                            if last_report.elapsed() >= PULL_PROGRESS_INTERVAL {
+                                on_progress(&progress);
+                                last_report = Instant::now();
+                            }
+                            continue;
+                        }
+                        Some(message) => message,
+                    },
+                    Some(Err(e)) => e.to_string(),
+                };
+                
+                let error = DockerError::Pull {
+                    image: image.to_string(),
+                    registry: registry_of(image).to_string(),
+                    message,
+                };
+                log::error!("{}", error);
+                progress.finish(index, PullPhase::Failed, Some(error.to_string()));
+                on_progress(&progress);
+                return Err(error);
             }
+            
+            progress.finish(index, PullPhase::Done, None);
+            on_progress(&progress);
         }
         
         Ok(())
     }
     
-    async fn start_marketplace_container(&self) -> Result<(), DockerError> {
-        let container_name = "pwa-marketplace";
+    async fn start_service(&self, name: &str, spec: &ServiceSpec) -> Result<(), DockerError> {
+        // Refuse bad definitions and untrusted images before touching the running container
+        let mut config = self.container_config(name, spec)?;
+        self.check_host_resources(name, spec).await?;
+        if let Some(image_id) = self.verify_image(spec).await? {
+            // Run exactly the image that was checked, even if the tag moves meanwhile
+            config.image = Some(image_id);
+        }
         
         // Stop and remove existing container if it exists
-        let _ = self.stop_container(container_name).await;
-        let _ = self.remove_container(container_name).await;
-        
-        let mut port_bindings = HashMap::new();
-        port_bindings.insert(
-            "3000/tcp".to_string(),
-            Some(vec![PortBinding {
-                host_ip: Some("127.0.0.1".to_string()),
-                host_port: Some("3000".to_string()),
-            }]),
-        );
-        
-        let host_config = HostConfig {
-            port_bindings: Some(port_bindings),
-            network_mode: Some(self.network_name.clone()),
-            binds: Some(vec![
-                format!("{}:/app/storage/apps", self.apps_folder.display()),
-                format!("{}:/app/storage/data", self.data_folder.display()),
-            ]),
-            ..Default::default()
-        };
-        
-        let config = Config {
-            image: Some("pwa-marketplace:latest"),
-            env: Some(vec![
-                "NODE_ENV=production",
-                "MCP_BRIDGE_URL=http://mcp-bridge:3001",
-                "RESOURCE_CONTROLLER_URL=http://resource-controller:3002",
-            ]),
-            host_config: Some(host_config),
-            ..Default::default()
-        };
+        let _ = self.stop_container(name).await;
+        let _ = self.remove_container(name).await;
         
         let options = CreateContainerOptions {
-            name: container_name,
+            name,
             platform: None,
         };
         
         self.docker.create_container(Some(options), config).await?;
+        self.docker.start_container(name, None::<StartContainerOptions<String>>).await?;
         
-        let options = StartContainerOptions { detach_keys: None };
-        self.docker.start_container(container_name, Some(options)).await?;
-        
-        log::info!("Started PWA Marketplace container");
+        log::info!("Started {} container", name);
         Ok(())
     }
     
-    async fn start_mcp_bridge_container(&self) -> Result<(), DockerError> {
-        let container_name = "mcp-bridge";
-        
-        // Stop and remove existing container if it exists
-        let _ = self.stop_container(container_name).await;
-        let _ = self.remove_container(container_name).await;
+    fn container_config(&self, name: &str, spec: &ServiceSpec) -> Result<Config<String>, DockerError> {
+        let invalid = |e: String| DockerError::Config(format!("Service {}: {}", name, e));
+        let variables = self.folder_variables();
+        
+        let mut port_bindings: HashMap<String, Option<Vec<PortBinding>>> = HashMap::new();
+        let mut exposed_ports = HashMap::new();
+        for port in spec.port_mappings().map_err(invalid)? {
+            exposed_ports.insert(port.container_key(), HashMap::new());
+            if port.host_port.is_some() {
+                port_bindings
+                    .entry(port.container_key())
+                    .or_default()
+                    .get_or_insert_with(Vec::new)
+                    .push(PortBinding {
+                        host_ip: port.host_ip.clone(),
+                        host_port: port.host_port.map(|p| p.to_string()),
+                    });
+            }
+        }
         
+        let memory = spec.limits.memory_bytes().map_err(invalid)?;
         let host_config = HostConfig {
+            port_bindings: Some(port_bindings),
             network_mode: Some(self.network_name.clone()),
-            binds: Some(vec![
-                format!("{}:/app/storage/data", self.data_folder.display()),
-            ]),
+            binds: Some(spec.binds(&variables).map_err(invalid)?),
+            // Same as `memory`, so the container can't use swap on top
+            memory: Some(memory),
+            memory_swap: Some(memory),
+            nano_cpus: Some(spec.limits.nano_cpus()),
+            pids_limit: Some(spec.limits.pids_limit),
+            cap_drop: Some(spec.security.dropped_capabilities()),
+            cap_add: Some(spec.security.cap_add.clone()),
+            security_opt: Some(spec.security.security_opt()),
+            readonly_rootfs: Some(spec.security.read_only),
+            tmpfs: Some(spec.security.tmpfs_mounts().map_err(invalid)?.into_iter().collect()),
             ..Default::default()
         };
         
-        let config = Config {
-            image: Some("mcp-bridge:latest"),
-            env: Some(vec![
-                "NODE_ENV=production",
-                "MCP_PORT=3001",
-                "STORAGE_PATH=/app/storage/data",
-            ]),
+        Ok(Config {
+            image: Some(spec.image.clone()),
+            env: Some(spec.env(&variables).map_err(invalid)?),
+            exposed_ports: Some(exposed_ports),
+            healthcheck: spec.healthcheck.as_ref().map(health_config),
             host_config: Some(host_config),
             ..Default::default()
-        };
-        
-        let options = CreateContainerOptions {
-            name: container_name,
-            platform: None,
-        };
-        
-        self.docker.create_container(Some(options), config).await?;
-        
-        let options = StartContainerOptions { detach_keys: None };
-        self.docker.start_container(container_name, Some(options)).await?;
-        
-        log::info!("Started MCP Bridge container");
-        Ok(())
+        })
     }
     
-    async fn start_resource_controller_container(&self) -> Result<(), DockerError> {
-        let container_name = "resource-controller";
-        
-        // Stop and remove existing container if it exists
-        let _ = self.stop_container(container_name).await;
-        let _ = self.remove_container(container_name).await;
-        
-        let host_config = HostConfig {
-            network_mode: Some(self.network_name.clone()),
-            binds: Some(vec![
-                format!("{}:/app/storage/apps", self.apps_folder.display()),
-                format!("{}:/app/storage/data", self.data_folder.display()),
-                "/tmp:/host/tmp".to_string(), // For temporary file operations
-            ]),
-            ..Default::default()
-        };
-        
-        let config = Config {
-            image: Some("resource-controller:latest"),
-            env: Some(vec![
-                "NODE_ENV=production",
-                "CONTROLLER_PORT=3002",
-                "APPS_PATH=/app/storage/apps",
-                "DATA_PATH=/app/storage/data",
-            ]),
-            host_config: Some(host_config),
-            ..Default::default()
-        };
-        
-        let options = CreateContainerOptions {
-            name: container_name,
-            platform: None,
-        };
-        
-        self.docker.create_container(Some(options), config).await?;
-        
-        let options = StartContainerOptions { detach_keys: None };
-        self.docker.start_container(container_name, Some(options)).await?;
+    // A limit above what the host has would be no limit at all
+    async fn check_host_resources(&self, name: &str, spec: &ServiceSpec) -> Result<(), DockerError> {
+        let info = self.docker.info().await?;
+        let memory = spec.limits.memory_bytes()
+            .map_err(|e| DockerError::Config(format!("Service {}: {}", name, e)))?;
+        
+        if let Some(total) = info.mem_total.filter(|total| memory > *total) {
+            return Err(DockerError::Config(format!(
+                "Service {}: memory limit {} exceeds the {} MiB available to Docker",
+                name, spec.limits.memory, total / (1024 * 1024)
+            )));
+        }
+        if let Some(cpus) = info.ncpu.filter(|cpus| spec.limits.cpus > *cpus as f64) {
+            return Err(DockerError::Config(format!(
+                "Service {}: cpus {} exceeds the {} CPUs available to Docker",
+                name, spec.limits.cpus, cpus
+            )));
+        }
         
-        log::info!("Started Resource Controller container");
         Ok(())
     }
     
+    // Values for `${...}` in service definitions
+    fn folder_variables(&self) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("APPS_FOLDER", self.apps_folder.display().to_string()),
+            ("DATA_FOLDER", self.data_folder.display().to_string()),
+        ])
+    }
+    
     async fn stop_container(&self, name: &str) -> Result<(), DockerError> {
         let options = StopContainerOptions { t: 10 };
         self.docker.stop_container(name, Some(options)).await?;
@@ -383,12 +1444,12 @@ impl DockerManager {
         Ok(())
     }
     
-    async fn get_container_status(&self, name: &str) -> Result<ServiceStatus, DockerError> {
+    pub async fn get_container_status(&self, name: &str) -> Result<ServiceStatus, DockerError> {
         let options = ListContainersOptions::<String> {
             all: true,
             filters: {
                 let mut filters = HashMap::new();
-                filters.insert("name".to_string(), vec![name.to_string()]);
+                filters.insert("name".to_string(), vec![format!("^/{}$", name)]);
                 filters
             },
             ..Default::default()
@@ -397,8 +1458,8 @@ impl DockerManager {
         let containers = self.docker.list_containers(Some(options)).await?;
         
         if let Some(container) = containers.first() {
-            let status = container.status.as_deref().unwrap_or("unknown");
-            let health = self.determine_health_status(container).await;
+            let status = container.state.as_deref().unwrap_or("unknown");
+            let health = self.determine_health_status(name, container).await;
             let ports = container.ports.as_ref()
                 .map(|ports| {
                     ports.iter()
@@ -413,7 +1474,9 @@ impl DockerManager {
             Ok(ServiceStatus {
                 name: name.to_string(),
                 status: status.to_string(),
-                health,
+                health: health.status,
+                failing_streak: health.failing_streak,
+                health_detail: health.detail,
                 ports,
                 uptime: container.status.clone(),
             })
@@ -422,37 +1485,129 @@ impl DockerManager {
         }
     }
     
-    async fn determine_health_status(&self, container: &ContainerSummary) -> String {
-        // Check if container is running
-        if let Some(state) = &container.state {
-            if state != "running" {
-                return "unhealthy".to_string();
+    async fn determine_health_status(&self, name: &str, container: &ContainerSummary) -> HealthReport {
+        if container.state.as_deref() != Some("running") {
+            return HealthReport::new(HealthStatus::Stopped);
+        }
+        
+        // Docker's own HEALTHCHECK, from the image or the service definition
+        let details = match self.docker.inspect_container(name, None).await {
+            Ok(details) => Some(details),
+            Err(e) => {
+                log::debug!("Failed to inspect {}: {}", name, e);
+                None
+            }
+        };
+        let native = details.as_ref()
+            .and_then(|details| details.state.as_ref())
+            .and_then(|state| state.health.as_ref())
+            .and_then(native_health);
+        if let Some(report) = native {
+            return report;
+        }
+        
+        let probe = self.services.services
+            .get(name)
+            .and_then(|spec| Some((spec, spec.probe.as_ref()?)));
+        match probe {
+            Some((spec, probe)) => {
+                let result = self.run_probe(name, spec, probe, details.as_ref()).await;
+                self.record_probe(name, result)
+            }
+            None => HealthReport::new(HealthStatus::Unknown),
+        }
+    }
+    
+    async fn run_probe(
+        &self,
+        name: &str,
+        spec: &ServiceSpec,
+        probe: &Probe,
+        details: Option<&ContainerInspectResponse>,
+    ) -> Result<(), String> {
+        let address = match spec.published_port(probe.port()) {
+            Some((host, port)) => format!("{}:{}", host, port),
+            None => {
+                let ip = details
+                    .and_then(|details| self.network_address(details))
+                    .ok_or_else(|| format!("{} has no address on {}", name, self.network_name))?;
+                format!("{}:{}", ip, probe.port())
+            }
+        };
+        
+        match probe {
+            Probe::Tcp { .. } => {
+                tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(&address)).await
+                    .map_err(|_| format!("Connecting to {} timed out", address))?
+                    .map_err(|e| format!("Connecting to {} failed: {}", address, e))?;
+                Ok(())
+            }
+            Probe::Http { path, .. } => {
+                let url = format!("http://{}{}", address, path);
+                let response = reqwest::Client::new()
+                    .get(&url)
+                    .timeout(PROBE_TIMEOUT)
+                    .send()
+                    .await
+                    .map_err(|e| format!("GET {} failed: {}", url, e))?;
+                
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!("GET {} returned {}", url, response.status()))
+                }
             }
         }
+    }
+    
+    fn record_probe(&self, name: &str, result: Result<(), String>) -> HealthReport {
+        let mut failures = self.probe_failures.lock().unwrap();
         
-        // Additional health checks could be implemented here
-        // For now, assume running = healthy
-        "healthy".to_string()
+        match result {
+            Ok(()) => {
+                failures.remove(name);
+                HealthReport::new(HealthStatus::Healthy)
+            }
+            Err(e) => {
+                let counted = ProbeFailures::record(failures.get(name).copied(), Instant::now());
+                failures.insert(name.to_string(), counted);
+                HealthReport {
+                    status: HealthStatus::Unhealthy,
+                    failing_streak: counted.streak,
+                    detail: Some(e),
+                }
+            }
+        }
+    }
+    
+    fn network_address(&self, details: &ContainerInspectResponse) -> Option<String> {
+        details.network_settings.as_ref()?
+            .networks.as_ref()?
+            .get(&self.network_name)?
+            .ip_address.clone()
+            .filter(|ip| !ip.is_empty())
     }
     
     async fn wait_for_services_ready(&self) -> Result<(), DockerError> {
-        let services = ["pwa-marketplace", "mcp-bridge", "resource-controller"];
-        let max_attempts = 30; // 30 seconds timeout
+        let max_attempts = 60; // 1 minute timeout
         
-        for service in &services {
+        for (service, _) in self.services.start_order()? {
             log::info!("Waiting for {} to be ready...", service);
             
             for attempt in 1..=max_attempts {
                 match self.get_container_status(service).await {
-                    Ok(status) if status.status.contains("running") => {
-                        log::info!("{} is ready", service);
+                    Ok(status) if status.health.is_ready() => {
+                        log::info!("{} is ready ({})", service, status.health.as_str());
                         break;
                     }
-                    Ok(_) => {
+                    Ok(status) => {
                         if attempt == max_attempts {
-                            return Err(DockerError::Config(
-                                format!("Service {} failed to start within timeout", service)
-                            ));
+                            return Err(DockerError::Config(format!(
+                                "Service {} is {} after waiting: {}",
+                                service,
+                                status.health.as_str(),
+                                status.health_detail.as_deref().unwrap_or("no health check output")
+                            )));
                         }
                         sleep(Duration::from_secs(1)).await;
                     }
@@ -466,37 +1621,9 @@ impl DockerManager {
             }
         }
         
-        // Additional readiness check - try to connect to marketplace
-        self.wait_for_marketplace_endpoint().await?;
-        
         Ok(())
     }
-    
-    async fn wait_for_marketplace_endpoint(&self) -> Result<(), DockerError> {
-        let client = reqwest::Client::new();
-        let url = "http://localhost:3000/health";
-        let max_attempts = 20;
-        
-        for attempt in 1..=max_attempts {
-            match client.get(url).send().await {
-                Ok(response) if response.status().is_success() => {
-                    log::info!("Marketplace endpoint is ready");
-                    return Ok(());
-                }
-                _ => {
-                    if attempt == max_attempts {
-                        return Err(DockerError::Network(
-                            "Marketplace endpoint not responding".to_string()
-                        ));
-                    }
-                    sleep(Duration::from_secs(1)).await;
-                }
-            }
-        }
-        
-        Ok(())
-    }
-    
+
     async fn wait_for_docker_ready(&self) -> Result<(), DockerError> {
         let max_attempts = 60; // 1 minute timeout
         
@@ -624,4 +1751,310 @@ impl DockerManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn upgrade_error(service: &str, message: impl ToString) -> DockerError {
+    DockerError::Upgrade {
+        service: service.to_string(),
+        message: message.to_string(),
+    }
+}
+
+// Name the container replaced by an upgrade is kept under
+fn previous_name(service: &str) -> String {
+    format!("{}-previous", service)
+}
+
+// Container an upgrade is tried out in before it takes over
+fn candidate_name(service: &str) -> String {
+    format!("{}-candidate", service)
+}
+
+// Pulls exactly the image `digest` names, whatever its tag points to
+fn digest_reference(image: &str, digest: &str) -> String {
+    format!("{}@{}", repository(image), digest)
+}
+
+// `repo:tag` split for tagging, with Docker's default tag
+fn split_tag(image: &str) -> (&str, &str) {
+    let repo = repository(image);
+    let tag = image[repo.len()..].strip_prefix(':').unwrap_or("latest");
+    (repo, tag.split('@').next().unwrap_or(tag))
+}
+
+// Docker expects healthcheck durations in nanoseconds
+fn health_config(healthcheck: &Healthcheck) -> HealthConfig {
+    let nanos = |secs: u64| Some(Duration::from_secs(secs).as_nanos() as i64);
+    
+    HealthConfig {
+        test: Some(healthcheck.test.clone()),
+        interval: nanos(healthcheck.interval_secs),
+        timeout: nanos(healthcheck.timeout_secs),
+        retries: Some(healthcheck.retries as i64),
+        start_period: nanos(healthcheck.start_period_secs),
+        ..Default::default()
+    }
+}
+
+// Health from Docker's HEALTHCHECK state, None when the container has none
+fn native_health(health: &Health) -> Option<HealthReport> {
+    let status = match health.status? {
+        HealthStatusEnum::HEALTHY => HealthStatus::Healthy,
+        HealthStatusEnum::UNHEALTHY => HealthStatus::Unhealthy,
+        HealthStatusEnum::STARTING => HealthStatus::Starting,
+        _ => return None,
+    };
+    
+    let detail = health.log.as_ref()
+        .and_then(|log| log.last())
+        .filter(|result| result.exit_code != Some(0))
+        .and_then(|result| result.output.as_deref())
+        .map(|output| output.trim().to_string())
+        .filter(|output| !output.is_empty());
+    
+    Some(HealthReport {
+        status,
+        failing_streak: health.failing_streak.unwrap_or(0).max(0) as u32,
+        detail,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::service::HealthcheckResult;
+    
+    fn check(exit_code: i64, output: &str) -> HealthcheckResult {
+        HealthcheckResult {
+            exit_code: Some(exit_code),
+            output: Some(output.to_string()),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_native_health() {
+        let unhealthy = Health {
+            status: Some(HealthStatusEnum::UNHEALTHY),
+            failing_streak: Some(4),
+            log: Some(vec![check(0, "ok"), check(1, "connection refused\n")]),
+        };
+        let report = native_health(&unhealthy).unwrap();
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        assert_eq!(report.failing_streak, 4);
+        assert_eq!(report.detail.as_deref(), Some("connection refused"));
+        
+        let healthy = Health {
+            status: Some(HealthStatusEnum::HEALTHY),
+            failing_streak: Some(0),
+            log: Some(vec![check(0, "ok")]),
+        };
+        let report = native_health(&healthy).unwrap();
+        assert_eq!(report.status, HealthStatus::Healthy);
+        assert_eq!(report.detail, None);
+        
+        // No HEALTHCHECK configured, so the service's probe decides
+        let none = Health { status: Some(HealthStatusEnum::NONE), ..Default::default() };
+        assert!(native_health(&none).is_none());
+    }
+    
+    #[test]
+    fn test_probe_failures_count_once_per_interval() {
+        let start = Instant::now();
+        let first = ProbeFailures::record(None, start);
+        assert_eq!(first.streak, 1);
+        
+        // The UI and the supervisor checking in between don't add to the streak
+        let mut failures = first;
+        for seconds in 1..5 {
+            failures = ProbeFailures::record(Some(failures), start + Duration::from_secs(seconds));
+        }
+        assert_eq!((failures.streak, failures.counted_at), (1, start));
+        
+        let failures = ProbeFailures::record(Some(failures), start + PROBE_INTERVAL);
+        assert_eq!(failures.streak, 2);
+        let failures = ProbeFailures::record(Some(failures), start + PROBE_INTERVAL * 2);
+        assert_eq!(failures.streak, 3);
+    }
+    
+    // Containers by name with their image and whether they run, after `steps`
+    fn apply(mut containers: BTreeMap<String, (String, bool)>, steps: &[SwitchStep]) -> BTreeMap<String, (String, bool)> {
+        for step in steps {
+            match step {
+                SwitchStep::Remove(name) => {
+                    containers.remove(name);
+                }
+                SwitchStep::Stop(name) => {
+                    if let Some(container) = containers.get_mut(name) {
+                        container.1 = false;
+                    }
+                }
+                SwitchStep::Rename { from, to } => {
+                    assert!(!containers.contains_key(to), "{} is taken", to);
+                    let container = containers.remove(from).expect("renamed container exists");
+                    containers.insert(to.clone(), container);
+                }
+                SwitchStep::Create { name, image } => {
+                    assert!(!containers.contains_key(name), "{} is taken", name);
+                    containers.insert(name.clone(), (image.clone(), false));
+                }
+                SwitchStep::Start(name) => containers.get_mut(name).expect("started container exists").1 = true,
+            }
+        }
+        containers
+    }
+    
+    #[test]
+    fn test_switch_over_and_rollback() {
+        let running = |image: &str| (image.to_string(), true);
+        let before = BTreeMap::from([
+            ("mcp-bridge".to_string(), running("old")),
+            ("mcp-bridge-previous".to_string(), ("older".to_string(), false)),
+            ("mcp-bridge-candidate".to_string(), running("new")),
+        ]);
+        
+        for switch_over in [SwitchOver::Rename, SwitchOver::Restart] {
+            let steps = switch_steps("mcp-bridge", "new", switch_over, true);
+            let after = apply(before.clone(), &steps);
+            assert_eq!(after, BTreeMap::from([
+                ("mcp-bridge".to_string(), running("new")),
+                ("mcp-bridge-previous".to_string(), ("old".to_string(), false)),
+            ]));
+            
+            // The old container only stops once the new one serves, unless ports force a restart
+            let stops_current = steps.contains(&SwitchStep::Stop("mcp-bridge".to_string()));
+            assert_eq!(stops_current, !switch_over.is_zero_downtime());
+            
+            let rolled_back = apply(after, &rollback_steps("mcp-bridge"));
+            assert_eq!(rolled_back, BTreeMap::from([("mcp-bridge".to_string(), running("old"))]));
+        }
+        
+        // A first install has nothing to keep
+        let fresh = BTreeMap::from([("mcp-bridge-candidate".to_string(), running("new"))]);
+        for switch_over in [SwitchOver::Rename, SwitchOver::Restart] {
+            let after = apply(fresh.clone(), &switch_steps("mcp-bridge", "new", switch_over, false));
+            assert_eq!(after, BTreeMap::from([("mcp-bridge".to_string(), running("new"))]));
+        }
+        
+        let published: ServiceSpec = toml::from_str("image = \"a\"\nports = [\"3001:3001\"]").unwrap();
+        assert_eq!(SwitchOver::for_service(&published), SwitchOver::Restart);
+        let internal: ServiceSpec = toml::from_str("image = \"a\"").unwrap();
+        assert_eq!(SwitchOver::for_service(&internal), SwitchOver::Rename);
+    }
+    
+    #[test]
+    fn test_failed_switch_over_is_restored() {
+        let running = |image: &str| (image.to_string(), true);
+        let before = BTreeMap::from([
+            ("mcp-bridge".to_string(), running("old")),
+            ("mcp-bridge-previous".to_string(), ("older".to_string(), false)),
+            ("mcp-bridge-candidate".to_string(), running("new")),
+        ]);
+        
+        for switch_over in [SwitchOver::Rename, SwitchOver::Restart] {
+            let steps = switch_steps("mcp-bridge", "new", switch_over, true);
+            // Fail before each step in turn
+            for done in 0..steps.len() {
+                let partial = apply(before.clone(), &steps[..done]);
+                let renamed = partial.get("mcp-bridge-previous").is_some_and(|(image, _)| image == "old");
+                let restored = apply(partial, &restore_steps("mcp-bridge", renamed));
+                
+                assert_eq!(restored.get("mcp-bridge"), Some(&running("old")), "{:?} failing at {:?}", switch_over, steps[done]);
+                assert!(!restored.contains_key("mcp-bridge-candidate"));
+            }
+        }
+    }
+    
+    #[test]
+    fn test_loaded_images() {
+        let output = "Loaded image: mcp-bridge:latest\nLoaded image ID: sha256:4c1a\nLoaded image: pwa-marketplace:latest\n";
+        assert_eq!(loaded_images(output), ["mcp-bridge:latest", "pwa-marketplace:latest"]);
+        assert!(loaded_images("").is_empty());
+    }
+    
+    #[test]
+    fn test_log_lines() {
+        let output = LogOutput::StdErr {
+            message: "2024-05-01T10:00:00.123456789Z listening on 3001\r\n2024-05-01T10:00:01.5Z ready\n".into(),
+        };
+        let lines = log_lines("mcp-bridge", output);
+        
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].stream, LogStream::Stderr);
+        assert_eq!(lines[0].timestamp.as_deref(), Some("2024-05-01T10:00:00.123456789Z"));
+        assert_eq!(lines[0].message, "listening on 3001");
+        assert_eq!(lines[1].target(), "docker::mcp-bridge");
+        
+        let plain = log_lines("mcp-bridge", LogOutput::Console { message: "no timestamp".into() });
+        assert_eq!((plain[0].stream, plain[0].timestamp.clone()), (LogStream::Stdout, None));
+        assert_eq!(plain[0].message, "no timestamp");
+        
+        assert_eq!(LogQuery { tail: None, ..Default::default() }.options(true).tail, "all");
+    }
+    
+    #[test]
+    fn test_split_tag() {
+        assert_eq!(split_tag("pwa-marketplace:latest"), ("pwa-marketplace", "latest"));
+        assert_eq!(split_tag("mcp-bridge"), ("mcp-bridge", "latest"));
+        assert_eq!(split_tag("localhost:5000/mcp-bridge:1.2"), ("localhost:5000/mcp-bridge", "1.2"));
+        assert_eq!(previous_name("mcp-bridge"), "mcp-bridge-previous");
+    }
+    
+    fn message(action: &str, attributes: &[(&str, &str)]) -> EventMessage {
+        let attributes = attributes.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        
+        EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some(action.to_string()),
+            actor: Some(bollard::service::EventActor {
+                id: Some("c0ffee".to_string()),
+                attributes: Some(attributes),
+            }),
+            time: Some(1_700_000_000),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_container_events() {
+        let event = ContainerEvent::from_message(&message("health_status: unhealthy", &[("name", "mcp-bridge")])).unwrap();
+        assert_eq!(event.action, ContainerAction::HealthStatus);
+        assert_eq!(event.health, Some(HealthStatus::Unhealthy));
+        assert_eq!(event.name, "mcp-bridge");
+        
+        let event = ContainerEvent::from_message(&message("die", &[("name", "mcp-bridge"), ("exitCode", "137")])).unwrap();
+        assert_eq!(event.action, ContainerAction::Die);
+        assert_eq!(event.exit_code, Some(137));
+        
+        // Other actions and object types are ignored
+        assert!(ContainerEvent::from_message(&message("exec_start: sh", &[("name", "mcp-bridge")])).is_none());
+        assert!(ContainerEvent::from_message(&message("attach", &[("name", "mcp-bridge")])).is_none());
+        let network = EventMessage { typ: Some(EventMessageTypeEnum::NETWORK), ..message("connect", &[]) };
+        assert!(ContainerEvent::from_message(&network).is_none());
+    }
+    
+    #[test]
+    fn test_live_state() {
+        let event = |action: &str, attributes: &[(&str, &str)]| {
+            ContainerEvent::from_message(&message(action, attributes)).unwrap()
+        };
+        let mut state = LiveState::default();
+        
+        state.apply(&event("start", &[("name", "mcp-bridge")]));
+        state.apply(&event("health_status: healthy", &[("name", "mcp-bridge")]));
+        assert!(state.running);
+        assert_eq!(state.health, Some(HealthStatus::Healthy));
+        
+        state.apply(&event("oom", &[("name", "mcp-bridge")]));
+        state.apply(&event("die", &[("name", "mcp-bridge"), ("exitCode", "137")]));
+        assert!(!state.running && state.oom_killed);
+        assert_eq!(state.health, Some(HealthStatus::Stopped));
+        assert_eq!(state.exit_code, Some(137));
+        
+        // A restart starts from a clean slate
+        state.apply(&event("start", &[("name", "mcp-bridge")]));
+        assert_eq!(state, LiveState { running: true, updated_at: 1_700_000_000, ..Default::default() });
+    }
+}
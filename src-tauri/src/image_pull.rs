@@ -0,0 +1,228 @@
+// src-tauri/src/image_pull.rs
+use bollard::service::CreateImageInfo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Statuses Docker reports for individual layers; others describe the whole image
+const LAYER_STATUSES: &[&str] = &[
+    "Pulling fs layer",
+    "Waiting",
+    "Downloading",
+    "Verifying Checksum",
+    "Download complete",
+    "Extracting",
+    "Pull complete",
+    "Already exists",
+];
+
+/// Registry an image reference is pulled from, e.g. `ghcr.io` or `docker.io`
+pub fn registry_of(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((first, _)) if first.contains('.') || first.contains(':') || first == "localhost" => first,
+        _ => "docker.io",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PullPhase {
+    Waiting,
+    Pulling,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Layer {
+    /// Compressed size, unknown until the download starts
+    size: u64,
+    downloaded: u64,
+    extracted: u64,
+    complete: bool,
+}
+
+impl Layer {
+    fn update(&mut self, status: &str, current: Option<u64>, total: Option<u64>) {
+        if let Some(total) = total {
+            self.size = self.size.max(total);
+        }
+
+        match status {
+            "Downloading" => self.downloaded = current.unwrap_or(self.downloaded),
+            "Verifying Checksum" | "Download complete" => self.downloaded = self.size,
+            "Extracting" => {
+                self.downloaded = self.size;
+                self.extracted = current.unwrap_or(self.extracted);
+            }
+            "Pull complete" => {
+                self.downloaded = self.size;
+                self.extracted = self.size;
+                self.complete = true;
+            }
+            // Nothing to fetch, so it adds nothing to the byte counts
+            "Already exists" => self.complete = true,
+            _ => {}
+        }
+    }
+}
+
+/// Pull progress of one image, summed over its layers
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageProgress {
+    pub image: String,
+    pub registry: String,
+    pub phase: PullPhase,
+    pub layers: usize,
+    pub layers_complete: usize,
+    pub downloaded_bytes: u64,
+    pub extracted_bytes: u64,
+    /// Size of the layers whose size is known so far
+    pub total_bytes: u64,
+    pub error: Option<String>,
+    #[serde(skip)]
+    layer_progress: BTreeMap<String, Layer>,
+}
+
+impl ImageProgress {
+    fn new(image: &str) -> Self {
+        Self {
+            image: image.to_string(),
+            registry: registry_of(image).to_string(),
+            phase: PullPhase::Waiting,
+            layers: 0,
+            layers_complete: 0,
+            downloaded_bytes: 0,
+            extracted_bytes: 0,
+            total_bytes: 0,
+            error: None,
+            layer_progress: BTreeMap::new(),
+        }
+    }
+
+    fn apply(&mut self, info: &CreateImageInfo) {
+        self.phase = PullPhase::Pulling;
+
+        let (Some(id), Some(status)) = (&info.id, info.status.as_deref()) else {
+            return;
+        };
+        if !LAYER_STATUSES.contains(&status) {
+            return;
+        }
+
+        let detail = info.progress_detail.as_ref();
+        let bytes = |value: Option<i64>| value.and_then(|value| u64::try_from(value).ok());
+        self.layer_progress.entry(id.clone()).or_default().update(
+            status,
+            bytes(detail.and_then(|detail| detail.current)),
+            bytes(detail.and_then(|detail| detail.total)),
+        );
+
+        let layers = self.layer_progress.values();
+        self.layers = layers.len();
+        self.layers_complete = layers.clone().filter(|layer| layer.complete).count();
+        self.downloaded_bytes = layers.clone().map(|layer| layer.downloaded).sum();
+        self.extracted_bytes = layers.clone().map(|layer| layer.extracted).sum();
+        self.total_bytes = layers.map(|layer| layer.size).sum();
+    }
+}
+
+/// Payload of the `image-pull-progress` event: every image of the stack and the overall totals
+#[derive(Debug, Clone, Serialize)]
+pub struct PullProgress {
+    pub images: Vec<ImageProgress>,
+    pub images_complete: usize,
+    pub downloaded_bytes: u64,
+    pub extracted_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl PullProgress {
+    pub fn new(images: &[&str]) -> Self {
+        Self {
+            images: images.iter().map(|image| ImageProgress::new(image)).collect(),
+            images_complete: 0,
+            downloaded_bytes: 0,
+            extracted_bytes: 0,
+            total_bytes: 0,
+        }
+    }
+
+    /// Fold in one item of the `create_image` stream for `images[index]`
+    pub fn apply(&mut self, index: usize, info: &CreateImageInfo) {
+        self.images[index].apply(info);
+        self.update_totals();
+    }
+
+    /// Mark `images[index]` as done, failed or cancelled
+    pub fn finish(&mut self, index: usize, phase: PullPhase, error: Option<String>) {
+        let image = &mut self.images[index];
+        image.phase = phase;
+        image.error = error;
+        self.update_totals();
+    }
+
+    fn update_totals(&mut self) {
+        self.images_complete = self.images.iter().filter(|image| image.phase == PullPhase::Done).count();
+        self.downloaded_bytes = self.images.iter().map(|image| image.downloaded_bytes).sum();
+        self.extracted_bytes = self.images.iter().map(|image| image.extracted_bytes).sum();
+        self.total_bytes = self.images.iter().map(|image| image.total_bytes).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::service::ProgressDetail;
+
+    fn info(id: &str, status: &str, progress: Option<(i64, i64)>) -> CreateImageInfo {
+        CreateImageInfo {
+            id: Some(id.to_string()),
+            status: Some(status.to_string()),
+            progress_detail: progress.map(|(current, total)| ProgressDetail {
+                current: Some(current),
+                total: Some(total),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_registry_of() {
+        assert_eq!(registry_of("pwa-marketplace:latest"), "docker.io");
+        assert_eq!(registry_of("library/node:20"), "docker.io");
+        assert_eq!(registry_of("ghcr.io/emllm/mcp-bridge:1.2"), "ghcr.io");
+        assert_eq!(registry_of("localhost:5000/mcp-bridge"), "localhost:5000");
+    }
+
+    #[test]
+    fn test_layer_progress_is_aggregated() {
+        let mut progress = PullProgress::new(&["mcp-bridge:latest", "pwa-marketplace:latest"]);
+
+        progress.apply(0, &info("latest", "Pulling from library/mcp-bridge", None));
+        progress.apply(0, &info("a1", "Pulling fs layer", None));
+        progress.apply(0, &info("b2", "Already exists", None));
+        progress.apply(0, &info("c3", "Pulling fs layer", None));
+        progress.apply(0, &info("a1", "Downloading", Some((400, 1000))));
+        progress.apply(0, &info("c3", "Downloading", Some((100, 500))));
+
+        let image = &progress.images[0];
+        assert_eq!(image.phase, PullPhase::Pulling);
+        assert_eq!((image.layers, image.layers_complete), (3, 1));
+        assert_eq!((image.downloaded_bytes, image.total_bytes), (500, 1500));
+
+        progress.apply(0, &info("a1", "Download complete", None));
+        progress.apply(0, &info("a1", "Extracting", Some((250, 1000))));
+        assert_eq!(progress.images[0].downloaded_bytes, 1100);
+        assert_eq!(progress.images[0].extracted_bytes, 250);
+
+        progress.apply(0, &info("a1", "Pull complete", None));
+        progress.apply(0, &info("c3", "Pull complete", None));
+        progress.finish(0, PullPhase::Done, None);
+        progress.finish(1, PullPhase::Failed, Some("no such image".to_string()));
+
+        assert_eq!(progress.images[0].layers_complete, 3);
+        assert_eq!(progress.images_complete, 1);
+        assert_eq!((progress.downloaded_bytes, progress.extracted_bytes, progress.total_bytes), (1500, 1500, 1500));
+    }
+}
@@ -0,0 +1,299 @@
+// src-tauri/src/image_trust.rs
+use ring::signature::{UnparsedPublicKey, ED25519};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Public keys that may sign service images, one base64 Ed25519 key per line
+const BUNDLED_SIGNING_KEYS: &str = include_str!("../resources/image-signing-keys.txt");
+
+#[derive(Error, Debug)]
+pub enum ImageTrustError {
+    #[error("Invalid digest {0:?}, expected sha256:<64 hex digits>")]
+    InvalidDigest(String),
+    #[error("Image {image} is pinned to {expected} but the local image is {found}")]
+    DigestMismatch { image: String, expected: String, found: String },
+    #[error("Image {0} is not available locally")]
+    Missing(String),
+    #[error("Invalid signing key: {0}")]
+    InvalidKey(String),
+    #[error("Invalid signature for {0}, expected base64 of 64 bytes")]
+    InvalidSignature(String),
+    #[error("Image {0} is signed but no signing keys are bundled")]
+    NoTrustedKeys(String),
+    #[error("Signature for {image}@{digest} does not match any bundled signing key")]
+    BadSignature { image: String, digest: String },
+    #[error("Image {0} has no signature, but signing keys are bundled so every image needs one")]
+    Unsigned(String),
+    #[error("Failed to save recorded image digests: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid recorded image digests: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Reference without its tag, e.g. `ghcr.io/emllm/mcp-bridge` for `ghcr.io/emllm/mcp-bridge:1.2`
+pub fn repository(image: &str) -> &str {
+    let image = image.split_once('@').map_or(image, |(repository, _)| repository);
+    match image.rsplit_once(':') {
+        // A colon before the last slash belongs to a registry port
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => image,
+    }
+}
+
+pub fn check_digest_format(digest: &str) -> Result<(), ImageTrustError> {
+    match digest.strip_prefix("sha256:") {
+        Some(hex) if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)) => Ok(()),
+        _ => Err(ImageTrustError::InvalidDigest(digest.to_string())),
+    }
+}
+
+/// Bytes covered by an image signature. The digest commits to the manifest,
+/// the repository keeps a signature from vouching for a different service.
+pub fn signed_message(image: &str, digest: &str) -> String {
+    format!("{}@{}", repository(image), digest)
+}
+
+/// What the Docker daemon knows about a local image
+#[derive(Debug, Clone, Default)]
+pub struct LocalImage {
+    /// Image ID, the digest of its config
+    pub id: String,
+    /// `repository@sha256:...` manifest digests from the registries it was pulled from
+    pub repo_digests: Vec<String>,
+}
+
+impl LocalImage {
+    /// Whether the image is the one `digest` pins. Pulled images are identified by
+    /// their manifest digest; loaded bundles often have none, so the image ID counts too.
+    pub fn matches(&self, image: &str, digest: &str) -> bool {
+        self.id == digest || self.repo_digests.iter().any(|repo_digest| {
+            repo_digest.rsplit_once('@').is_some_and(|(repo, found)| {
+                found == digest && short_repository(repo) == short_repository(repository(image))
+            })
+        })
+    }
+
+    /// Check the image against its pin, returning the ID to create containers from
+    pub fn verify(&self, image: &str, digest: &str) -> Result<String, ImageTrustError> {
+        if self.matches(image, digest) {
+            return Ok(self.id.clone());
+        }
+
+        let mut found = vec![self.id.clone()];
+        found.extend(self.repo_digests.iter().cloned());
+        Err(ImageTrustError::DigestMismatch {
+            image: image.to_string(),
+            expected: digest.to_string(),
+            found: found.join(", "),
+        })
+    }
+}
+
+// Docker reports Hub images as `mcp-bridge` or `docker.io/library/mcp-bridge` depending on version
+fn short_repository(repository: &str) -> &str {
+    repository
+        .strip_prefix("docker.io/")
+        .map(|rest| rest.strip_prefix("library/").unwrap_or(rest))
+        .unwrap_or(repository)
+}
+
+/// Ed25519 keys whose signatures are accepted
+#[derive(Debug, Clone)]
+pub struct TrustedKeys {
+    keys: Vec<Vec<u8>>,
+}
+
+impl TrustedKeys {
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_SIGNING_KEYS).expect("bundled image signing keys are valid")
+    }
+
+    /// No keys, so unsigned images are allowed
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// One base64 key per line; blank lines and `#` comments are skipped
+    pub fn parse(content: &str) -> Result<Self, ImageTrustError> {
+        let keys = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match base64::decode(line) {
+                Ok(key) if key.len() == 32 => Ok(key),
+                _ => Err(ImageTrustError::InvalidKey(line.to_string())),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { keys })
+    }
+
+    /// Check a detached base64 signature over `signed_message(image, digest)`
+    pub fn verify(&self, image: &str, digest: &str, signature: &str) -> Result<(), ImageTrustError> {
+        let signature = decode_signature(image, signature)?;
+        if self.keys.is_empty() {
+            return Err(ImageTrustError::NoTrustedKeys(image.to_string()));
+        }
+
+        let message = signed_message(image, digest);
+        let trusted = self.keys.iter().any(|key| {
+            UnparsedPublicKey::new(&ED25519, key).verify(message.as_bytes(), &signature).is_ok()
+        });
+
+        if trusted {
+            Ok(())
+        } else {
+            Err(ImageTrustError::BadSignature {
+                image: image.to_string(),
+                digest: digest.to_string(),
+            })
+        }
+    }
+}
+
+/// Digests of images trusted on first use, by image reference. Services
+/// without a `digest` in services.toml stay on the image they first got
+/// until an upgrade or rollback moves them on.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedDigests {
+    path: Option<PathBuf>,
+    digests: BTreeMap<String, String>,
+}
+
+impl RecordedDigests {
+    /// `image-digests.json` next to `config.json`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("PWA-Marketplace").join("image-digests.json"))
+    }
+
+    /// Digests recorded so far; nothing yet when the file doesn't exist
+    pub fn load_from(path: &Path) -> Result<Self, ImageTrustError> {
+        let digests = match std::fs::read(path) {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self { path: Some(path.to_path_buf()), digests })
+    }
+
+    pub fn get(&self, image: &str) -> Option<&str> {
+        self.digests.get(image).map(String::as_str)
+    }
+
+    /// Pin `image` to `digest` and save
+    pub fn record(&mut self, image: &str, digest: &str) -> Result<(), ImageTrustError> {
+        check_digest_format(digest)?;
+        self.digests.insert(image.to_string(), digest.to_string());
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(&self.digests)?)?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+pub fn decode_signature(image: &str, signature: &str) -> Result<Vec<u8>, ImageTrustError> {
+    match base64::decode(signature.trim()) {
+        Ok(bytes) if bytes.len() == 64 => Ok(bytes),
+        _ => Err(ImageTrustError::InvalidSignature(image.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    const DIGEST: &str = "sha256:4c1a5e4fd8c2b9a1a1b0cd6f0b6e5a7b3f2d9e8c7b6a5f4e3d2c1b0a9f8e7d6c";
+    const OTHER: &str = "sha256:0000000000000000000000000000000000000000000000000000000000000000";
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn sign(key: &Ed25519KeyPair, image: &str, digest: &str) -> String {
+        base64::encode(key.sign(signed_message(image, digest).as_bytes()))
+    }
+
+    #[test]
+    fn test_pinned_digests() {
+        assert_eq!(repository("mcp-bridge:latest"), "mcp-bridge");
+        assert_eq!(repository("localhost:5000/mcp-bridge"), "localhost:5000/mcp-bridge");
+        assert_eq!(repository("localhost:5000/mcp-bridge:1.2@sha256:abc"), "localhost:5000/mcp-bridge");
+        assert!(check_digest_format(DIGEST).is_ok());
+        assert!(check_digest_format("sha256:ABC").is_err());
+
+        // As a local registry would report a pulled image
+        let pulled = LocalImage {
+            id: OTHER.to_string(),
+            repo_digests: vec![format!("docker.io/library/mcp-bridge@{}", DIGEST)],
+        };
+        assert_eq!(pulled.verify("mcp-bridge:latest", DIGEST).unwrap(), OTHER);
+        assert!(!pulled.matches("resource-controller:latest", DIGEST));
+
+        // Loaded from a bundle, so only the ID is known
+        let loaded = LocalImage { id: DIGEST.to_string(), repo_digests: Vec::new() };
+        assert!(loaded.verify("mcp-bridge:latest", DIGEST).is_ok());
+
+        // The tag was moved to something else
+        let replaced = LocalImage { id: OTHER.to_string(), repo_digests: Vec::new() };
+        assert!(matches!(
+            replaced.verify("mcp-bridge:latest", DIGEST),
+            Err(ImageTrustError::DigestMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_signatures() {
+        let signer = key_pair();
+        let keys = TrustedKeys::parse(&format!(
+            "# release key\n{}\n",
+            base64::encode(signer.public_key().as_ref())
+        )).unwrap();
+
+        let signature = sign(&signer, "mcp-bridge:latest", DIGEST);
+        assert!(keys.verify("mcp-bridge:latest", DIGEST, &signature).is_ok());
+        // The tag doesn't matter, the repository and digest do
+        assert!(keys.verify("mcp-bridge:1.2", DIGEST, &signature).is_ok());
+        assert!(matches!(keys.verify("mcp-bridge:latest", OTHER, &signature), Err(ImageTrustError::BadSignature { .. })));
+        assert!(keys.verify("resource-controller:latest", DIGEST, &signature).is_err());
+
+        let stranger = sign(&key_pair(), "mcp-bridge:latest", DIGEST);
+        assert!(keys.verify("mcp-bridge:latest", DIGEST, &stranger).is_err());
+        assert!(matches!(keys.verify("mcp-bridge:latest", DIGEST, "c2ln"), Err(ImageTrustError::InvalidSignature(_))));
+
+        let none = TrustedKeys::parse("# no keys yet\n").unwrap();
+        assert!(matches!(none.verify("mcp-bridge:latest", DIGEST, &signature), Err(ImageTrustError::NoTrustedKeys(_))));
+        assert!(TrustedKeys::parse("bm90IGEga2V5").is_err());
+        assert!(none.is_empty() && !keys.is_empty());
+        TrustedKeys::bundled();
+    }
+
+    #[test]
+    fn test_recorded_digests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image-digests.json");
+
+        let mut recorded = RecordedDigests::load_from(&path).unwrap();
+        assert_eq!(recorded.get("mcp-bridge:latest"), None);
+        recorded.record("mcp-bridge:latest", DIGEST).unwrap();
+        assert!(recorded.record("mcp-bridge:latest", "latest").is_err());
+
+        let reloaded = RecordedDigests::load_from(&path).unwrap();
+        assert_eq!(reloaded.get("mcp-bridge:latest"), Some(DIGEST));
+        assert_eq!(reloaded.get("mcp-bridge:1.2"), None);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(RecordedDigests::load_from(&path), Err(ImageTrustError::Json(_))));
+    }
+}
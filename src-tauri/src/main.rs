@@ -8,6 +8,9 @@ use tauri::{
     CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, 
     SystemTrayMenuItem, Window, WindowBuilder, WindowUrl, App
 };
+use tauri::api::dialog::{MessageDialogBuilder, MessageDialogKind};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tokio::sync::mpsc;
 
@@ -18,15 +21,33 @@ mod github_auth;
 mod folder_selector;
 mod auto_updater;
 mod logger;
+mod app_config;
+mod config_migrations;
+mod vault_transfer;
+mod totp;
+mod password_generator;
+mod password_strength;
+mod search_index;
+mod team_vault;
+mod autofill;
+mod service_spec;
+mod service_supervisor;
+mod image_pull;
+mod image_trust;
 
 use system_tray::{create_system_tray, handle_system_tray_event};
-use docker_manager::DockerManager;
+use docker_manager::{DockerManager, LiveState, LogLine, LogQuery, ServiceStatus, ServiceUpgrade};
+use image_pull::PullProgress;
+use service_supervisor::RestartPolicy;
 use password_manager::PasswordManager;
+use password_strength::PasswordPolicy;
+use app_config::{AppConfig, ConfigError};
 
 #[derive(Default)]
 pub struct AppState {
     docker_manager: Mutex<Option<DockerManager>>,
     password_manager: Mutex<Option<PasswordManager>>,
+    config: Mutex<Option<AppConfig>>,
     is_first_run: Mutex<bool>,
     marketplace_url: Mutex<String>,
 }
@@ -44,31 +65,107 @@ async fn complete_setup(
     apps_folder: String,
     data_folder: String,
     github_token: Option<String>,
-    state: tauri::State<'_, AppState>
+    image_bundle: Option<String>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle
 ) -> Result<(), String> {
+    PasswordPolicy::bundled().check(&master_password)?;
+    
+    let mut config = AppConfig::new(&apps_folder, &data_folder);
+    config.image_bundle = image_bundle.map(PathBuf::from);
+    
     // Initialize password manager with master password
-    let password_manager = PasswordManager::new(&master_password)
-        .map_err(|e| format!("Failed to initialize password manager: {}", e))?;
+    let password_manager = PasswordManager::open().await
+        .map_err(|e| format!("Failed to initialize password manager: {}", e))?
+        .with_kdf_cost(config.vault.kdf)
+        .with_history_retention(config.vault.history_retention);
+    password_manager.set_up(&master_password).await
+        .map_err(|e| format!("Failed to set master password: {}", e))?;
+    
+    let lock_events = app_handle.clone();
+    password_manager.spawn_auto_lock(config.auto_lock_settings(), move |reason| {
+        if let Err(e) = lock_events.emit_all("vault-locked", reason) {
+            log::warn!("Failed to emit vault-locked event: {}", e);
+        }
+    });
     
     // Store GitHub token if provided
     if let Some(token) = github_token {
-        password_manager.store_github_token(&token)
+        password_manager.store_github_token(&token).await
             .map_err(|e| format!("Failed to store GitHub token: {}", e))?;
     }
     
-    // Initialize Docker manager
-    let docker_manager = DockerManager::new(&apps_folder, &data_folder);
+    // Initialize Docker manager; in app state right away so the image pull can be cancelled
+    let docker_manager = DockerManager::new(&config.apps_folder_str(), &config.data_folder_str())
+        .with_image_bundle(image_bundle_for(&app_handle, &config));
+    *state.docker_manager.lock().unwrap() = Some(docker_manager.clone());
     
     // Start marketplace services
-    docker_manager.start_marketplace_services().await
+    docker_manager.start_marketplace_services(|progress| emit_pull_progress(&app_handle, progress)).await
         .map_err(|e| format!("Failed to start services: {}", e))?;
     
+    // Persist configuration so later launches skip the setup wizard
+    config.save()
+        .map_err(|e| format!("Failed to save configuration: {}", e))?;
+    
     // Update app state
-    *state.docker_manager.lock().unwrap() = Some(docker_manager);
     *state.password_manager.lock().unwrap() = Some(password_manager);
     *state.is_first_run.lock().unwrap() = false;
-    *state.marketplace_url.lock().unwrap() = "http://localhost:3000".to_string();
+    *state.marketplace_url.lock().unwrap() = config.marketplace_url.clone();
+    *state.config.lock().unwrap() = Some(config);
+    
+    Ok(())
+}
+
+/// File name of the image bundle shipped with offline builds
+const BUNDLED_IMAGES: &str = "marketplace-images.tar";
+
+// The bundle chosen during setup, else one shipped in the resources or next to the executable
+fn image_bundle_for(app_handle: &tauri::AppHandle, config: &AppConfig) -> Option<PathBuf> {
+    if config.image_bundle.is_some() {
+        return config.image_bundle.clone();
+    }
+    
+    let resources = app_handle.path_resolver().resource_dir();
+    let beside_exe = std::env::current_exe().ok().and_then(|exe| exe.parent().map(PathBuf::from));
+    [resources, beside_exe]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(BUNDLED_IMAGES))
+        .find(|path| path.is_file())
+}
+
+#[tauri::command]
+async fn load_image_bundle(path: String, state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.load_image_bundle(Path::new(&path)).await
+        .map_err(|e| format!("Failed to load images: {}", e))
+}
+
+#[tauri::command]
+async fn export_image_bundle(path: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.save_image_bundle(Path::new(&path)).await
+        .map_err(|e| format!("Failed to export images: {}", e))
+}
+
+fn emit_pull_progress(app_handle: &tauri::AppHandle, progress: &PullProgress) {
+    if let Err(e) = app_handle.emit_all("image-pull-progress", progress) {
+        log::warn!("Failed to emit image-pull-progress event: {}", e);
+    }
+}
+
+#[tauri::command]
+fn cancel_image_pull(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let docker_manager = state.docker_manager.lock().unwrap();
+    let docker_manager = docker_manager.as_ref()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
     
+    docker_manager.cancel_image_pull();
     Ok(())
 }
 
@@ -114,9 +211,9 @@ async fn generate_github_token(
     username: String,
     state: tauri::State<'_, AppState>
 ) -> Result<String, String> {
-    let password_manager_guard = state.password_manager.lock().unwrap();
+    let password_manager = state.password_manager.lock().unwrap().clone();
     
-    if let Some(password_manager) = password_manager_guard.as_ref() {
+    if let Some(password_manager) = password_manager.as_ref() {
         github_auth::generate_token(&username, password_manager).await
             .map_err(|e| format!("Failed to generate GitHub token: {}", e))
     } else {
@@ -125,17 +222,116 @@ async fn generate_github_token(
 }
 
 #[tauri::command]
-async fn get_marketplace_status(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let docker_manager_guard = state.docker_manager.lock().unwrap();
+async fn get_marketplace_status(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle
+) -> Result<Vec<ServiceStatus>, String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
     
-    if let Some(docker_manager) = docker_manager_guard.as_ref() {
-        match docker_manager.get_services_status().await {
-            Ok(status) => Ok(status),
-            Err(e) => Ok(format!("Error: {}", e))
+    let statuses = docker_manager.get_services_status().await
+        .map_err(|e| format!("Failed to get service status: {}", e))?;
+    system_tray::update_service_status(&app_handle, &statuses);
+    
+    Ok(statuses)
+}
+
+/// Container states from the Docker event stream, for seeding the UI before `service-event`s arrive
+#[tauri::command]
+fn get_live_service_states(state: tauri::State<'_, AppState>) -> Result<HashMap<String, LiveState>, String> {
+    let docker_manager = state.docker_manager.lock().unwrap();
+    let docker_manager = docker_manager.as_ref()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    Ok(docker_manager.live_states())
+}
+
+#[tauri::command]
+async fn restart_service(name: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.restart_service(&name).await
+        .map_err(|e| format!("Failed to restart {}: {}", name, e))
+}
+
+/// Upgrade a service in place; `switch_over` in the result says whether it went down meanwhile
+#[tauri::command]
+async fn upgrade_service(
+    name: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle
+) -> Result<ServiceUpgrade, String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.upgrade_service(&name, |progress| emit_pull_progress(&app_handle, progress)).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn rollback_service(name: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.rollback_service(&name).await
+        .map_err(|e| format!("Failed to roll back {}: {}", name, e))
+}
+
+#[tauri::command]
+async fn get_service_logs(
+    name: String,
+    query: Option<LogQuery>,
+    state: tauri::State<'_, AppState>
+) -> Result<Vec<LogLine>, String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.service_logs(&name, &query.unwrap_or_default()).await
+        .map_err(|e| format!("Failed to read {} logs: {}", name, e))
+}
+
+/// Stream a service's new log lines as `service-log` events, and into the app log
+/// under `docker::<service>` when `to_logger` is set
+#[tauri::command]
+fn follow_service_logs(
+    name: String,
+    query: Option<LogQuery>,
+    to_logger: Option<bool>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle
+) -> Result<(), String> {
+    let docker_manager = state.docker_manager.lock().unwrap().clone()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    let query = query.unwrap_or(LogQuery { tail: Some(0), ..Default::default() });
+    let to_logger = to_logger.unwrap_or(false);
+    
+    tokio::spawn(async move {
+        let result = docker_manager.follow_logs(&name, &query, |line| {
+            if to_logger {
+                line.log();
+            }
+            if let Err(e) = app_handle.emit_all("service-log", &line) {
+                log::warn!("Failed to emit service-log event: {}", e);
+            }
+        }).await;
+        
+        if let Err(e) = result {
+            log::warn!("Stopped following {} logs: {}", name, e);
         }
-    } else {
-        Ok("Not initialized".to_string())
-    }
+    });
+    
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_following_service_logs(name: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let docker_manager = state.docker_manager.lock().unwrap();
+    let docker_manager = docker_manager.as_ref()
+        .ok_or_else(|| "Docker manager not initialized".to_string())?;
+    
+    docker_manager.stop_following_logs(&name);
+    Ok(())
 }
 
 #[tauri::command]
@@ -176,13 +372,59 @@ fn create_main_window(app: &App) -> tauri::Result<Window> {
     .build()
 }
 
+fn create_unlock_window(app: &App) -> tauri::Result<Window> {
+    WindowBuilder::new(
+        app,
+        "unlock",
+        WindowUrl::App("unlock.html".into())
+    )
+    .title("Unlock PWA Marketplace")
+    .inner_size(420.0, 320.0)
+    .center()
+    .resizable(false)
+    .maximizable(false)
+    .build()
+}
+
 #[tokio::main]
 async fn main() {
+    // Load saved configuration (absent on first run)
+    let mut startup_error = None;
+    let config = if AppConfig::exists() {
+        match AppConfig::load() {
+            Ok(config) => Some(config),
+            Err(e @ ConfigError::UnsupportedVersion { .. }) => {
+                // Never run setup over a config we cannot understand
+                eprintln!("{}", e);
+                startup_error = Some(e.to_string());
+                None
+            }
+            Err(e) => {
+                // Rerunning setup would overwrite the file, so stop and point at a copy
+                eprintln!("Failed to load configuration: {}", e);
+                let backup = AppConfig::config_path()
+                    .and_then(|path| AppConfig::back_up_unreadable(&path))
+                    .map(|backup| format!("A copy was saved to {}.", backup.display()))
+                    .unwrap_or_else(|e| format!("It could not be backed up: {}.", e));
+                startup_error = Some(format!(
+                    "config.json could not be loaded: {}\n\n{} Fix or remove config.json and start PWA Marketplace again.",
+                    e, backup
+                ));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    
     // Initialize logger
-    logger::init().expect("Failed to initialize logger");
+    match &config {
+        Some(config) => logger::init_with_config(config.logger_config()),
+        None => logger::init(),
+    }.expect("Failed to initialize logger");
     
     let app_state = AppState {
-        is_first_run: Mutex::new(true), // Will be determined during startup
+        is_first_run: Mutex::new(config.is_none()),
         ..Default::default()
     };
     
@@ -190,82 +432,130 @@ async fn main() {
         .manage(app_state)
         .system_tray(create_system_tray())
         .on_system_tray_event(handle_system_tray_event)
-        .setup(|app| {
-            // Check if this is first run
-            let is_first_run = check_first_run();
+        .setup(move |app| {
+            if let Some(message) = startup_error {
+                log::error!("Refusing to start: {}", message);
+                MessageDialogBuilder::new("PWA Marketplace cannot start", message)
+                    .kind(MessageDialogKind::Error)
+                    .show(|_| std::process::exit(1));
+                return Ok(());
+            }
             
-            if is_first_run {
-                // Show setup wizard
-                create_setup_window(app)?;
-            } else {
-                // Initialize existing configuration
-                initialize_existing_config(app);
+            let update_config = config.as_ref()
+                .map(|config| config.update_config())
+                .unwrap_or_default();
+            
+            match config {
+                Some(config) => {
+                    // Initialize existing configuration
+                    initialize_existing_config(app, config)?;
+                }
+                None => {
+                    // Show setup wizard
+                    create_setup_window(app)?;
+                }
             }
             
             // Start background services
-            start_background_services(app.handle());
+            start_background_services(app.handle(), update_config);
             
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             is_first_run,
             complete_setup,
+            cancel_image_pull,
+            load_image_bundle,
+            export_image_bundle,
+            password_manager::unlock_vault,
+            password_manager::lock_vault,
+            password_manager::get_vault_status,
+            password_manager::store_password_entry,
+            password_manager::get_password_entry,
+            password_manager::list_password_entries,
+            password_manager::search_password_entries,
+            password_manager::search_vault,
+            password_manager::delete_password_entry,
+            password_manager::get_entry_history,
+            password_manager::restore_entry_version,
+            password_manager::generate_totp_code,
+            password_manager::store_secure_note,
+            password_manager::get_secure_note,
+            password_manager::list_secure_notes,
+            password_manager::search_secure_notes,
+            password_manager::delete_secure_note,
+            password_manager::change_master_password,
+            password_manager::verify_vault,
+            team_vault::get_vault_public_key,
+            team_vault::create_team_collection,
+            team_vault::list_team_collections,
+            team_vault::delete_team_collection,
+            team_vault::add_team_member,
+            team_vault::remove_team_member,
+            team_vault::store_team_item,
+            team_vault::list_team_items,
+            team_vault::delete_team_item,
+            team_vault::export_team_collection,
+            team_vault::import_team_collections,
+            autofill::find_credentials_for_origin,
+            autofill::request_autofill,
+            password_generator::generate_password,
+            password_strength::check_password_strength,
+            password_strength::get_vault_health_report,
+            vault_transfer::export_vault_archive,
+            vault_transfer::preview_vault_import,
+            vault_transfer::import_vault_file,
             open_marketplace,
             select_folder,
             generate_github_token,
             get_marketplace_status,
+            get_live_service_states,
+            restart_service,
+            upgrade_service,
+            rollback_service,
+            get_service_logs,
+            follow_service_logs,
+            stop_following_service_logs,
             shutdown_services
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
-fn check_first_run() -> bool {
-    // Check if config file exists
-    let config_path = get_config_path();
-    !config_path.exists()
-}
-
-fn get_config_path() -> std::path::PathBuf {
-    let mut path = dirs::config_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-    path.push("PWA-Marketplace");
-    path.push("config.json");
-    path
-}
-
-fn initialize_existing_config(app: &App) {
-    // Load existing configuration
-    if let Ok(config) = load_existing_config() {
-        // Initialize services with existing config
-        tokio::spawn(async move {
-            // Initialize Docker manager
-            // Initialize password manager
-            // Start services
-        });
-    }
+fn initialize_existing_config(app: &App, config: AppConfig) -> tauri::Result<()> {
+    let state = app.state::<AppState>();
+    
+    // Rebuild Docker manager from the saved folders and bring services back up
+    let app_handle = app.handle();
+    let docker_manager = DockerManager::new(&config.apps_folder_str(), &config.data_folder_str())
+        .with_image_bundle(image_bundle_for(&app_handle, &config));
+    let services = docker_manager.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services.start_marketplace_services(|progress| emit_pull_progress(&app_handle, progress)).await {
+            log::error!("Failed to start marketplace services: {}", e);
+        }
+    });
+    
+    *state.docker_manager.lock().unwrap() = Some(docker_manager);
+    *state.marketplace_url.lock().unwrap() = config.marketplace_url.clone();
+    *state.config.lock().unwrap() = Some(config);
+    
+    // Password manager stays locked until the user enters the master password
+    create_unlock_window(app)?;
+    
+    Ok(())
 }
 
-fn load_existing_config() -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    let config_path = get_config_path();
-    let config_content = std::fs::read_to_string(config_path)?;
-    let config: serde_json::Value = serde_json::from_str(&config_content)?;
-    Ok(config)
-}
+fn start_background_services(app_handle: tauri::AppHandle, update_config: auto_updater::UpdateConfig) {
+    // Restart crashed or unhealthy marketplace services, keep the tray current
+    tokio::spawn(service_supervisor::run(app_handle.clone(), RestartPolicy::default()));
+    // Push container start, die, oom and health changes to the frontend as they happen
+    tokio::spawn(service_supervisor::forward_events(app_handle.clone()));
 
-fn start_background_services(app_handle: tauri::AppHandle) {
     tokio::spawn(async move {
         // Auto-updater check
-        if let Err(e) = auto_updater::check_for_updates(&app_handle).await {
+        if let Err(e) = auto_updater::start_with_config(&app_handle, update_config).await {
             log::error!("Auto-updater error: {}", e);
         }
-        
-        // Health monitoring
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-            
-            // Check Docker services health
-            // Check marketplace availability
-            // Update system tray status
-        }
     });
 }
\ No newline at end of file
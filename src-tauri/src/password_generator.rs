@@ -0,0 +1,557 @@
+// src-tauri/src/password_generator.rs
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Bundled diceware-style wordlist, one lowercase word per line
+const WORDLIST: &str = include_str!("../resources/wordlist.txt");
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+const AMBIGUOUS: &str = "Il1O0o|";
+
+// Letters used for pronounceable mode; q, x and y read poorly in syllables
+const CONSONANTS: &str = "bcdfghjklmnprstvwz";
+const VOWELS: &str = "aeiou";
+
+const MAX_LENGTH: usize = 128;
+const MAX_WORDS: usize = 20;
+
+#[derive(Error, Debug)]
+pub enum GeneratorError {
+    #[error("Invalid generator options: {0}")]
+    InvalidOptions(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratorMode {
+    /// Characters drawn from the enabled classes
+    #[default]
+    Random,
+    /// Words from the bundled wordlist
+    Passphrase,
+    /// Alternating consonants and vowels
+    Pronounceable,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Capitalization {
+    #[default]
+    Lower,
+    Title,
+    Upper,
+    /// Each word is independently title- or lowercase (one extra bit per word)
+    Random,
+}
+
+/// Restrictions imposed by the site the password is for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteRules {
+    pub max_length: Option<usize>,
+    /// Symbols the site rejects
+    pub forbidden_symbols: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorOptions {
+    pub mode: GeneratorMode,
+    /// Total characters for random and pronounceable modes
+    pub length: usize,
+    /// Number of words in passphrase mode. The bundled list gives about 10
+    /// bits per word, so the default of 7 carries about 70 bits.
+    pub word_count: usize,
+    pub separator: String,
+    pub capitalization: Capitalization,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub min_lowercase: usize,
+    pub min_uppercase: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    /// Leave out characters that are easy to misread, such as l, 1 and O
+    pub exclude_ambiguous: bool,
+    pub site_rules: SiteRules,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::Random,
+            length: 20,
+            word_count: 7,
+            separator: "-".to_string(),
+            capitalization: Capitalization::Lower,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            min_lowercase: 1,
+            min_uppercase: 1,
+            min_digits: 1,
+            min_symbols: 1,
+            exclude_ambiguous: false,
+            site_rules: SiteRules::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedPassword {
+    pub password: String,
+    /// Bits of entropy from the random choices made; a lower bound when
+    /// class minimums apply
+    pub entropy_bits: f64,
+}
+
+/// Character pools after ambiguous and forbidden characters are removed
+struct Pools {
+    lowercase: Vec<char>,
+    uppercase: Vec<char>,
+    digits: Vec<char>,
+    symbols: Vec<char>,
+}
+
+impl Pools {
+    fn new(options: &GeneratorOptions) -> Self {
+        let filter = |set: &str| -> Vec<char> {
+            set.chars()
+                .filter(|c| !(options.exclude_ambiguous && AMBIGUOUS.contains(*c)))
+                .filter(|c| !options.site_rules.forbidden_symbols.contains(*c))
+                .collect()
+        };
+
+        Self {
+            lowercase: filter(LOWERCASE),
+            uppercase: filter(UPPERCASE),
+            digits: filter(DIGITS),
+            symbols: filter(SYMBOLS),
+        }
+    }
+}
+
+/// Builds the password while tracking how much entropy each choice adds
+struct Builder<'a, R: Rng> {
+    rng: &'a mut R,
+    chars: Vec<char>,
+    entropy_bits: f64,
+}
+
+impl<R: Rng> Builder<'_, R> {
+    fn pick(&mut self, pool: &[char], name: &str) -> Result<char, GeneratorError> {
+        let c = *pool
+            .choose(self.rng)
+            .ok_or_else(|| invalid(format!("no {} left after exclusions", name)))?;
+        self.entropy_bits += (pool.len() as f64).log2();
+        Ok(c)
+    }
+
+    fn push_from(&mut self, pool: &[char], count: usize, name: &str) -> Result<(), GeneratorError> {
+        for _ in 0..count {
+            let c = self.pick(pool, name)?;
+            self.chars.push(c);
+        }
+        Ok(())
+    }
+}
+
+/// Generate a password according to `options`
+pub fn generate(options: &GeneratorOptions) -> Result<GeneratedPassword, GeneratorError> {
+    generate_with(options, &mut rand::thread_rng())
+}
+
+fn generate_with<R: Rng>(options: &GeneratorOptions, rng: &mut R) -> Result<GeneratedPassword, GeneratorError> {
+    let pools = Pools::new(options);
+    let max_length = options.site_rules.max_length.unwrap_or(MAX_LENGTH).min(MAX_LENGTH);
+    let mut builder = Builder { rng, chars: Vec::new(), entropy_bits: 0.0 };
+
+    match options.mode {
+        GeneratorMode::Random => random(options, &pools, options.length.min(max_length), &mut builder)?,
+        GeneratorMode::Passphrase => passphrase(options, &pools, max_length, &mut builder)?,
+        GeneratorMode::Pronounceable => pronounceable(options, &pools, options.length.min(max_length), &mut builder)?,
+    }
+
+    Ok(GeneratedPassword {
+        password: builder.chars.into_iter().collect(),
+        entropy_bits: builder.entropy_bits,
+    })
+}
+
+fn random<R: Rng>(
+    options: &GeneratorOptions,
+    pools: &Pools,
+    length: usize,
+    builder: &mut Builder<'_, R>,
+) -> Result<(), GeneratorError> {
+    let classes = [
+        ("lowercase", options.lowercase, options.min_lowercase, &pools.lowercase),
+        ("uppercase", options.uppercase, options.min_uppercase, &pools.uppercase),
+        ("digits", options.digits, options.min_digits, &pools.digits),
+        ("symbols", options.symbols, options.min_symbols, &pools.symbols),
+    ];
+
+    let mut combined = Vec::new();
+    let mut required = 0;
+    for (name, enabled, minimum, pool) in classes {
+        if !enabled {
+            continue;
+        }
+        if pool.is_empty() {
+            return Err(invalid(format!("no {} left after exclusions", name)));
+        }
+        combined.extend_from_slice(pool);
+        required += minimum;
+    }
+
+    if combined.is_empty() {
+        return Err(invalid("at least one character class must be enabled"));
+    }
+    if length == 0 {
+        return Err(invalid("length must be greater than zero"));
+    }
+    if required > length {
+        return Err(invalid(format!("class minimums need {} characters but length is {}", required, length)));
+    }
+
+    for (name, enabled, minimum, pool) in classes {
+        if enabled {
+            builder.push_from(pool, minimum, name)?;
+        }
+    }
+    builder.push_from(&combined, length - required, "characters")?;
+
+    // Spread the required characters out; not counted towards the entropy
+    builder.chars.shuffle(builder.rng);
+    Ok(())
+}
+
+fn passphrase<R: Rng>(
+    options: &GeneratorOptions,
+    pools: &Pools,
+    max_length: usize,
+    builder: &mut Builder<'_, R>,
+) -> Result<(), GeneratorError> {
+    let count = options.word_count;
+    if count == 0 || count > MAX_WORDS {
+        return Err(invalid(format!("word count must be between 1 and {}", MAX_WORDS)));
+    }
+    if options.separator.chars().any(|c| options.site_rules.forbidden_symbols.contains(c)) {
+        return Err(invalid("separator uses a forbidden symbol"));
+    }
+    if !options.uppercase && options.capitalization != Capitalization::Lower {
+        return Err(invalid("capitalized words need uppercase letters enabled"));
+    }
+    // The first words are title-cased to meet the uppercase minimum
+    let title_words = min_uppercase(options);
+    if title_words > count && options.capitalization != Capitalization::Upper {
+        return Err(invalid(format!("{} words cannot hold {} uppercase letters", count, title_words)));
+    }
+
+    // Only draw from words short enough that any combination fits, so the
+    // choice stays uniform and the entropy estimate honest
+    let suffix = suffix_length(options);
+    let separators = options.separator.chars().count() * (count - 1);
+    let budget = max_length.saturating_sub(separators + suffix) / count;
+    let words: Vec<&str> = WORDLIST
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty() && word.len() <= budget)
+        .collect();
+    if words.len() < 2 {
+        return Err(invalid(format!("{} words do not fit in {} characters", count, max_length)));
+    }
+
+    for i in 0..count {
+        if i > 0 {
+            builder.chars.extend(options.separator.chars());
+        }
+
+        let word = *words.choose(builder.rng).unwrap();
+        builder.entropy_bits += (words.len() as f64).log2();
+
+        let capitalize = match options.capitalization {
+            _ if i < title_words => true,
+            Capitalization::Lower => false,
+            Capitalization::Title | Capitalization::Upper => true,
+            Capitalization::Random => {
+                builder.entropy_bits += 1.0;
+                builder.rng.gen_bool(0.5)
+            }
+        };
+
+        if options.capitalization == Capitalization::Upper {
+            builder.chars.extend(word.chars().map(|c| c.to_ascii_uppercase()));
+        } else {
+            let mut letters = word.chars();
+            if let Some(first) = letters.next() {
+                builder.chars.push(if capitalize { first.to_ascii_uppercase() } else { first });
+            }
+            builder.chars.extend(letters);
+        }
+    }
+
+    push_suffix(options, pools, builder)
+}
+
+fn pronounceable<R: Rng>(
+    options: &GeneratorOptions,
+    pools: &Pools,
+    length: usize,
+    builder: &mut Builder<'_, R>,
+) -> Result<(), GeneratorError> {
+    let letters = length.saturating_sub(suffix_length(options));
+    if letters == 0 {
+        return Err(invalid("length leaves no room for letters"));
+    }
+
+    let filter = |set: &str| -> Vec<char> {
+        set.chars()
+            .filter(|c| !(options.exclude_ambiguous && AMBIGUOUS.contains(*c)))
+            .collect()
+    };
+    let consonants = filter(CONSONANTS);
+    let vowels = filter(VOWELS);
+
+    for i in 0..letters {
+        let (pool, name) = if i % 2 == 0 { (&consonants, "consonants") } else { (&vowels, "vowels") };
+        let c = builder.pick(pool, name)?;
+        builder.chars.push(c);
+    }
+
+    // Capitalize syllable starts, which keeps the result readable
+    let uppercase = min_uppercase(options);
+    if uppercase > letters.div_ceil(2) {
+        return Err(invalid(format!("{} letters cannot hold {} uppercase letters", letters, uppercase)));
+    }
+    for c in builder.chars.iter_mut().step_by(2).take(uppercase) {
+        *c = c.to_ascii_uppercase();
+    }
+
+    push_suffix(options, pools, builder)
+}
+
+// Minimums only apply to enabled classes, as in random mode
+fn min_uppercase(options: &GeneratorOptions) -> usize {
+    if options.uppercase { options.min_uppercase } else { 0 }
+}
+
+// Word-based modes satisfy digit and symbol minimums with a trailing suffix
+fn suffix_counts(options: &GeneratorOptions) -> (usize, usize) {
+    (
+        if options.digits { options.min_digits } else { 0 },
+        if options.symbols { options.min_symbols } else { 0 },
+    )
+}
+
+fn suffix_length(options: &GeneratorOptions) -> usize {
+    let (digits, symbols) = suffix_counts(options);
+    digits + symbols
+}
+
+fn push_suffix<R: Rng>(
+    options: &GeneratorOptions,
+    pools: &Pools,
+    builder: &mut Builder<'_, R>,
+) -> Result<(), GeneratorError> {
+    let (digits, symbols) = suffix_counts(options);
+    builder.push_from(&pools.digits, digits, "digits")?;
+    builder.push_from(&pools.symbols, symbols, "symbols")
+}
+
+fn invalid(message: impl Into<String>) -> GeneratorError {
+    GeneratorError::InvalidOptions(message.into())
+}
+
+#[tauri::command]
+pub fn generate_password(options: GeneratorOptions) -> Result<GeneratedPassword, String> {
+    generate(&options).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(password: &str, set: &str) -> usize {
+        password.chars().filter(|c| set.contains(*c)).count()
+    }
+
+    #[test]
+    fn test_random_meets_minimums() {
+        let options = GeneratorOptions {
+            length: 12,
+            min_digits: 3,
+            min_symbols: 2,
+            ..Default::default()
+        };
+
+        for _ in 0..50 {
+            let generated = generate(&options).unwrap();
+            assert_eq!(generated.password.chars().count(), 12);
+            assert!(count(&generated.password, DIGITS) >= 3);
+            assert!(count(&generated.password, SYMBOLS) >= 2);
+            assert!(count(&generated.password, UPPERCASE) >= 1);
+            assert!(count(&generated.password, LOWERCASE) >= 1);
+        }
+    }
+
+    #[test]
+    fn test_exclusions_and_site_rules() {
+        let options = GeneratorOptions {
+            length: 64,
+            exclude_ambiguous: true,
+            site_rules: SiteRules {
+                max_length: Some(16),
+                forbidden_symbols: "<>&".to_string(),
+            },
+            ..Default::default()
+        };
+
+        for _ in 0..50 {
+            let password = generate(&options).unwrap().password;
+            assert_eq!(password.chars().count(), 16);
+            assert_eq!(count(&password, AMBIGUOUS), 0);
+            assert_eq!(count(&password, "<>&"), 0);
+        }
+    }
+
+    #[test]
+    fn test_passphrase() {
+        let options = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            word_count: 4,
+            separator: ".".to_string(),
+            capitalization: Capitalization::Title,
+            min_digits: 0,
+            min_symbols: 0,
+            ..Default::default()
+        };
+
+        let generated = generate(&options).unwrap();
+        let words: Vec<&str> = generated.password.split('.').collect();
+        assert_eq!(words.len(), 4);
+        assert!(words.iter().all(|w| w.chars().next().unwrap().is_ascii_uppercase()));
+
+        let wordlist_size = WORDLIST.lines().count() as f64;
+        assert!((generated.entropy_bits - 4.0 * wordlist_size.log2()).abs() < 1e-9);
+
+        // The default passphrase is at least as strong as 5 diceware words
+        let default_words = GeneratorOptions::default().word_count as f64;
+        assert!(default_words * wordlist_size.log2() >= 64.0);
+    }
+
+    #[test]
+    fn test_passphrase_fits_max_length() {
+        let options = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            word_count: 3,
+            site_rules: SiteRules { max_length: Some(16), ..Default::default() },
+            ..Default::default()
+        };
+
+        for _ in 0..50 {
+            let password = generate(&options).unwrap().password;
+            assert!(password.chars().count() <= 16, "{} is too long", password);
+            assert!(count(&password, DIGITS) >= 1);
+            assert!(count(&password, SYMBOLS) >= 1);
+        }
+
+        let too_short = GeneratorOptions {
+            site_rules: SiteRules { max_length: Some(8), ..Default::default() },
+            ..options
+        };
+        assert!(generate(&too_short).is_err());
+    }
+
+    #[test]
+    fn test_pronounceable() {
+        let options = GeneratorOptions {
+            mode: GeneratorMode::Pronounceable,
+            length: 10,
+            min_symbols: 0,
+            ..Default::default()
+        };
+
+        let generated = generate(&options).unwrap();
+        let password: Vec<char> = generated.password.chars().collect();
+        assert_eq!(password.len(), 10);
+        assert!(password[0].is_ascii_uppercase());
+        assert!(password[1..9].iter().step_by(2).all(|c| VOWELS.contains(*c)));
+        assert!(password[9].is_ascii_digit());
+        assert!(generated.entropy_bits > 20.0);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let no_classes = GeneratorOptions {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            ..Default::default()
+        };
+        assert!(generate(&no_classes).is_err());
+
+        let too_many = GeneratorOptions { length: 3, ..Default::default() };
+        assert!(generate(&too_many).is_err());
+
+        let all_forbidden = GeneratorOptions {
+            site_rules: SiteRules { forbidden_symbols: SYMBOLS.to_string(), ..Default::default() },
+            ..Default::default()
+        };
+        assert!(generate(&all_forbidden).is_err());
+
+        // Digits the site rejects can't satisfy the suffix minimum
+        let no_digits_left = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            site_rules: SiteRules { forbidden_symbols: DIGITS.to_string(), ..Default::default() },
+            ..Default::default()
+        };
+        assert!(matches!(generate(&no_digits_left), Err(GeneratorError::InvalidOptions(_))));
+
+        let capitalized_without_uppercase = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            uppercase: false,
+            capitalization: Capitalization::Title,
+            ..Default::default()
+        };
+        assert!(generate(&capitalized_without_uppercase).is_err());
+    }
+
+    #[test]
+    fn test_word_modes_follow_class_options() {
+        let options = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            word_count: 4,
+            separator: " ".to_string(),
+            digits: false,
+            symbols: false,
+            min_uppercase: 2,
+            ..Default::default()
+        };
+
+        for _ in 0..20 {
+            let password = generate(&options).unwrap().password;
+            assert_eq!(count(&password, DIGITS), 0);
+            assert_eq!(count(&password, SYMBOLS), 0);
+            assert_eq!(count(&password, UPPERCASE), 2);
+        }
+
+        let pronounceable = GeneratorOptions {
+            mode: GeneratorMode::Pronounceable,
+            length: 10,
+            uppercase: false,
+            ..Default::default()
+        };
+        let password = generate(&pronounceable).unwrap().password;
+        assert_eq!(count(&password, UPPERCASE), 0);
+        assert_eq!(password.chars().count(), 10);
+    }
+}
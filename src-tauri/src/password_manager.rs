@@ -1,15 +1,29 @@
 // src-tauri/src/password_manager.rs
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce, Key
 };
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::{rand_core::RngCore, SaltString}};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version, password_hash::{rand_core::RngCore, SaltString}};
+use ring::{constant_time, hkdf, hmac};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, Row};
+use sqlx::{sqlite::{SqlitePool, SqliteRow}, Row, Sqlite, Transaction};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use thiserror::Error;
+use zeroize::Zeroizing;
+use crate::autofill::{self, AutofillError, MatchMode, MatchStrength, Origin};
+use crate::password_generator::{self, GeneratorError, GeneratorOptions};
+use crate::password_strength::PasswordPolicy;
+use crate::search_index::{ItemKind, SearchHit, SearchIndex};
+use crate::team_vault::{
+    self, Collection, CollectionBundle, Identity, SealedItem, TeamVaultError, WrappedMember,
+};
+use crate::totp::{Totp, TotpCode, TotpError};
+use crate::AppState;
 
 #[derive(Error, Debug)]
 pub enum PasswordManagerError {
@@ -19,6 +33,22 @@ pub enum PasswordManagerError {
     Encryption(String),
     #[error("Invalid master password")]
     InvalidMasterPassword,
+    #[error("Vault is locked")]
+    VaultLocked,
+    #[error("Vault has no master password yet")]
+    VaultNotInitialized,
+    #[error("TOTP error: {0}")]
+    Totp(#[from] TotpError),
+    #[error("Team vault error: {0}")]
+    TeamVault(#[from] TeamVaultError),
+    #[error("Autofill error: {0}")]
+    Autofill(#[from] AutofillError),
+    #[error("Password generator error: {0}")]
+    Generator(#[from] GeneratorError),
+    #[error("Vault integrity check failed ({} tampered, {} orphaned rows)", .0.tampered.len(), .0.orphaned.len())]
+    IntegrityCheckFailed(IntegrityReport),
+    #[error("{table} row {id} failed its integrity check")]
+    Tampered { table: String, id: String },
     #[error("Entry not found")]
     EntryNotFound,
     #[error("IO error: {0}")]
@@ -39,6 +69,21 @@ pub struct PasswordEntry {
     pub updated_at: DateTime<Utc>,
     pub last_used: Option<DateTime<Utc>>,
     pub is_favorite: bool,
+    /// `otpauth://` URI or bare base32 seed, encrypted at rest
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    /// Which origins `url` may be autofilled into
+    #[serde(default)]
+    pub match_mode: MatchMode,
+}
+
+/// A previous state of a password entry, newest versions have the highest number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryVersion {
+    pub version: i64,
+    /// When this version was replaced
+    pub archived_at: DateTime<Utc>,
+    pub entry: PasswordEntry,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,14 +97,221 @@ pub struct SecureNote {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Bumped whenever the on-disk vault layout changes. Stored in `PRAGMA user_version`.
+const VAULT_SCHEMA_VERSION: i64 = 6;
+
+/// Prior versions kept per entry unless configured otherwise
+pub const DEFAULT_HISTORY_RETENTION: usize = 10;
+
+/// Salt used by vaults created before each vault got its own random salt
+const LEGACY_KDF_SALT: &[u8] = b"pwa_marketplace_salt";
+
+/// HKDF info for the row MAC key, kept separate from the AES-GCM data key
+const METADATA_MAC_INFO: &[u8] = b"pwa-marketplace vault metadata mac v1";
+
+/// Bumped whenever the columns covered by row MACs change. Rows sealed under
+/// an older layout are checked against it and re-sealed on the next unlock.
+const METADATA_MAC_VERSION: i64 = 3;
+
+/// A table of vault items. Each ciphertext is bound to "scope:id:column" via
+/// the AES-GCM associated data, so it can't be moved to another row or column,
+/// and each row carries an HMAC over its table, owning id and all of `columns`.
+struct VaultTable {
+    name: &'static str,
+    scope: &'static str,
+    /// Column holding the id ciphertexts are bound to. History rows use their
+    /// entry's id, so archived ciphertexts stay valid when copied.
+    id_column: &'static str,
+    columns: &'static [&'static str],
+    encrypted: &'static [&'static str],
+}
+
+const ENTRY_COLUMNS: &[&str] = &[
+    "title", "username", "encrypted_password", "url", "encrypted_notes", "folder", "tags",
+    "created_at", "updated_at", "last_used", "is_favorite", "encrypted_totp", "match_mode",
+];
+const ENTRY_CIPHERTEXTS: &[&str] = &["encrypted_password", "encrypted_notes", "encrypted_totp"];
+
+// History rows also MAC their version, so they can't be replayed as another
+// version or copied back into `password_entries`
+const HISTORY_COLUMNS: &[&str] = &[
+    "title", "username", "encrypted_password", "url", "encrypted_notes", "folder", "tags",
+    "created_at", "updated_at", "last_used", "is_favorite", "encrypted_totp", "match_mode",
+    "version",
+];
+
+const ENTRIES: VaultTable = VaultTable {
+    name: "password_entries",
+    scope: "entry",
+    id_column: "id",
+    columns: ENTRY_COLUMNS,
+    encrypted: ENTRY_CIPHERTEXTS,
+};
+
+const HISTORY: VaultTable = VaultTable {
+    name: "password_history",
+    scope: "entry",
+    id_column: "entry_id",
+    columns: HISTORY_COLUMNS,
+    encrypted: ENTRY_CIPHERTEXTS,
+};
+
+const NOTES: VaultTable = VaultTable {
+    name: "secure_notes",
+    scope: "note",
+    id_column: "id",
+    columns: &["title", "encrypted_content", "folder", "tags", "created_at", "updated_at"],
+    encrypted: &["encrypted_content"],
+};
+
+/// The vault's X25519 identity for team collections, a single row with id "self"
+const IDENTITY: VaultTable = VaultTable {
+    name: "vault_identity",
+    scope: "identity",
+    id_column: "id",
+    columns: &["public_key", "encrypted_private_key", "created_at"],
+    encrypted: &["encrypted_private_key"],
+};
+
+/// Every table holding data encrypted with the vault data key. Anything that
+/// replaces the data key walks this list. Collection items are encrypted
+/// under their collection key instead.
+const VAULT_TABLES: &[VaultTable] = &[ENTRIES, HISTORY, NOTES, IDENTITY];
+
+/// Argon2id cost parameters for deriving the vault key
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KdfCost {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfCost {
+    /// What `Argon2::default()` used before the cost was stored per vault
+    pub const LEGACY: KdfCost = KdfCost { memory_kib: 19 * 1024, iterations: 2, parallelism: 1 };
+    pub const RECOMMENDED: KdfCost = KdfCost { memory_kib: 64 * 1024, iterations: 3, parallelism: 1 };
+    
+    pub(crate) fn argon2(&self) -> Result<Argon2<'static>, PasswordManagerError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+    
+    pub fn is_weaker_than(&self, other: &KdfCost) -> bool {
+        self.memory_kib < other.memory_kib || self.iterations < other.iterations
+    }
+}
+
+impl Default for KdfCost {
+    fn default() -> Self {
+        KdfCost::RECOMMENDED
+    }
+}
+
+#[derive(Debug, Clone)]
+struct KdfParams {
+    salt: Vec<u8>,
+    cost: KdfCost,
+}
+
+impl KdfParams {
+    fn generate(cost: KdfCost) -> Self {
+        let mut salt = vec![0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        KdfParams { salt, cost }
+    }
+    
+    fn legacy() -> Self {
+        KdfParams { salt: LEGACY_KDF_SALT.to_vec(), cost: KdfCost::LEGACY }
+    }
+    
+    fn is_legacy(&self) -> bool {
+        self.salt == LEGACY_KDF_SALT
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LockReason {
+    Manual,
+    Idle,
+    SystemSleep,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub locked: bool,
+    pub idle_seconds: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AutoLockSettings {
+    /// Lock after this long without vault activity. `None` disables the idle timer.
+    pub idle_timeout: Option<Duration>,
+    pub lock_on_sleep: bool,
+    pub check_interval: Duration,
+}
+
+impl Default for AutoLockSettings {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Some(Duration::from_secs(15 * 60)),
+            lock_on_sleep: true,
+            check_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+/// Key material and decrypted search data that only exist while the vault
+/// is unlocked. The cipher and index are zeroized when this is dropped.
+struct UnlockedVault {
+    cipher: Aes256Gcm,
+    mac_key: hmac::Key,
+    index: SearchIndex,
+    /// Items written while the index is being rebuilt; `None` outside a rebuild
+    changed_during_rebuild: Option<HashSet<(ItemKind, String)>>,
+}
+
+/// A vault row that failed an integrity check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowIssue {
+    pub table: String,
+    pub id: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub rows_checked: usize,
+    /// Rows whose MAC or ciphertexts don't verify
+    pub tampered: Vec<RowIssue>,
+    /// History rows whose entry no longer exists
+    pub orphaned: Vec<RowIssue>,
+    /// Rows were deleted, added or swapped in since the vault last wrote them
+    pub row_set_changed: bool,
+}
+
+impl IntegrityReport {
+    pub fn is_intact(&self) -> bool {
+        self.tampered.is_empty() && self.orphaned.is_empty() && !self.row_set_changed
+    }
+}
+
+struct VaultState {
+    unlocked: Option<UnlockedVault>,
+    last_activity: Instant,
+}
+
+#[derive(Clone)]
 pub struct PasswordManager {
     pool: SqlitePool,
-    master_key: Vec<u8>,
-    cipher: Aes256Gcm,
+    vault: Arc<Mutex<VaultState>>,
+    kdf_cost: KdfCost,
+    history_retention: usize,
 }
 
 impl PasswordManager {
-    pub async fn new(master_password: &str) -> Result<Self, PasswordManagerError> {
+    /// Open the vault database. The vault starts locked; call `unlock` before use.
+    pub async fn open() -> Result<Self, PasswordManagerError> {
         let db_path = Self::get_database_path()?;
         
         // Ensure directory exists
@@ -67,18 +319,18 @@ impl PasswordManager {
             std::fs::create_dir_all(parent)?;
         }
         
-        // Generate master key from password
-        let master_key = Self::derive_master_key(master_password)?;
-        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&master_key));
-        
         // Connect to database
-        let database_url = format!("sqlite:{}", db_path.display());
+        let database_url = format!("sqlite:{}?mode=rwc", db_path.display());
         let pool = SqlitePool::connect(&database_url).await?;
         
         let manager = PasswordManager {
             pool,
-            master_key,
-            cipher,
+            vault: Arc::new(Mutex::new(VaultState {
+                unlocked: None,
+                last_activity: Instant::now(),
+            })),
+            kdf_cost: KdfCost::default(),
+            history_retention: DEFAULT_HISTORY_RETENTION,
         };
         
         // Initialize database schema
@@ -87,397 +339,3099 @@ impl PasswordManager {
         Ok(manager)
     }
     
-    pub async fn verify_master_password(&self, password: &str) -> Result<bool, PasswordManagerError> {
-        // Get stored hash from database
-        let row = sqlx::query("SELECT master_password_hash FROM master_config WHERE id = 1")
-            .fetch_optional(&self.pool)
+    /// In-memory vault for unit tests
+    #[cfg(test)]
+    pub(crate) async fn open_in_memory() -> Result<Self, PasswordManagerError> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
             .await?;
-            
-        if let Some(row) = row {
-            let stored_hash: String = row.get("master_password_hash");
-            let parsed_hash = PasswordHash::new(&stored_hash)
-                .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-                
-            Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
-        } else {
-            // First time setup
-            self.store_master_password_hash(password).await?;
-            Ok(true)
-        }
+        
+        let manager = PasswordManager {
+            pool,
+            vault: Arc::new(Mutex::new(VaultState {
+                unlocked: None,
+                last_activity: Instant::now(),
+            })),
+            // Keep tests fast; production vaults use `KdfCost::RECOMMENDED`
+            kdf_cost: KdfCost { memory_kib: 1024, iterations: 1, parallelism: 1 },
+            history_retention: DEFAULT_HISTORY_RETENTION,
+        };
+        manager.init_database().await?;
+        Ok(manager)
     }
     
-    async fn store_master_password_hash(&self, password: &str) -> Result<(), PasswordManagerError> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?
-            .to_string();
-            
-        sqlx::query("INSERT OR REPLACE INTO master_config (id, master_password_hash) VALUES (1, ?)")
-            .bind(&password_hash)
-            .execute(&self.pool)
-            .await?;
-            
-        Ok(())
+    /// Argon2id cost for new vaults. Existing vaults with a weaker cost are
+    /// upgraded the next time they are unlocked.
+    pub fn with_kdf_cost(mut self, cost: KdfCost) -> Self {
+        self.kdf_cost = cost;
+        self
     }
     
-    pub async fn store_password(&self, entry: &PasswordEntry) -> Result<(), PasswordManagerError> {
-        let encrypted_password = self.encrypt_data(&entry.password)?;
-        let encrypted_notes = entry.notes.as_ref()
-            .map(|notes| self.encrypt_data(notes))
-            .transpose()?;
-        let tags_json = serde_json::to_string(&entry.tags)
-            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-            
-        sqlx::query(
-            r#"
-            INSERT OR REPLACE INTO password_entries 
-            (id, title, username, encrypted_password, url, encrypted_notes, folder, tags, 
-             created_at, updated_at, last_used, is_favorite)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#
-        )
-        .bind(&entry.id)
-        .bind(&entry.title)
-        .bind(&entry.username)
-        .bind(&encrypted_password)
-        .bind(&entry.url)
-        .bind(&encrypted_notes)
-        .bind(&entry.folder)
-        .bind(&tags_json)
-        .bind(&entry.created_at)
-        .bind(&entry.updated_at)
-        .bind(&entry.last_used)
-        .bind(&entry.is_favorite)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
+    /// Number of prior versions kept per entry; 0 disables history
+    pub fn with_history_retention(mut self, retention: usize) -> Self {
+        self.history_retention = retention;
+        self
     }
     
-    pub async fn get_password(&self, id: &str) -> Result<Option<PasswordEntry>, PasswordManagerError> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, title, username, encrypted_password, url, encrypted_notes, folder, tags,
-                   created_at, updated_at, last_used, is_favorite
-            FROM password_entries WHERE id = ?
-            "#
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
-        
-        if let Some(row) = row {
-            let encrypted_password: String = row.get("encrypted_password");
-            let password = self.decrypt_data(&encrypted_password)?;
-            
-            let encrypted_notes: Option<String> = row.get("encrypted_notes");
-            let notes = encrypted_notes
-                .map(|enc| self.decrypt_data(&enc))
-                .transpose()?;
-                
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json)
-                .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-            
-            Ok(Some(PasswordEntry {
-                id: row.get("id"),
-                title: row.get("title"),
-                username: row.get("username"),
-                password,
-                url: row.get("url"),
-                notes,
-                folder: row.get("folder"),
-                tags,
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                last_used: row.get("last_used"),
-                is_favorite: row.get("is_favorite"),
-            }))
-        } else {
-            Ok(None)
+    /// Create the master password on a fresh vault and unlock it. A vault
+    /// that already has one is unlocked with it as usual.
+    pub async fn set_up(&self, master_password: &str) -> Result<(), PasswordManagerError> {
+        let initialized = sqlx::query("SELECT 1 FROM master_config WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !initialized {
+            self.store_master_password_hash(master_password).await?;
         }
+        
+        self.unlock(master_password).await
     }
     
-    pub async fn list_passwords(&self, folder: Option<&str>) -> Result<Vec<PasswordEntry>, PasswordManagerError> {
-        let rows = if let Some(folder) = folder {
-            sqlx::query(
-                r#"
-                SELECT id, title, username, encrypted_password, url, encrypted_notes, folder, tags,
-                       created_at, updated_at, last_used, is_favorite
-                FROM password_entries WHERE folder = ? ORDER BY title
-                "#
-            )
-            .bind(folder)
-            .fetch_all(&self.pool)
-            .await?
+    /// Verify the master password and load the vault key into memory. Fails
+    /// with `VaultNotInitialized` until `set_up` has created one.
+    pub async fn unlock(&self, master_password: &str) -> Result<(), PasswordManagerError> {
+        if !self.verify_master_password(master_password).await? {
+            return Err(PasswordManagerError::InvalidMasterPassword);
+        }
+        
+        let params = self.load_kdf_params().await?;
+        let key_encryption_key = Self::derive_master_key(master_password, &params)?;
+        
+        let mut tx = self.pool.begin().await?;
+        let data_key = Self::open_data_key(&mut tx, &key_encryption_key).await?;
+        
+        if !Self::ciphertexts_bound(&mut tx).await? {
+            // Bind ciphertexts written by older builds to their rows and MAC every row
+            Self::reencrypt_vault(&mut tx, &data_key, &data_key).await?;
         } else {
-            sqlx::query(
-                r#"
-                SELECT id, title, username, encrypted_password, url, encrypted_notes, folder, tags,
-                       created_at, updated_at, last_used, is_favorite
-                FROM password_entries ORDER BY title
-                "#
-            )
-            .fetch_all(&self.pool)
-            .await?
-        };
+            // The stored version isn't authenticated, so it only picks the layout
+            // rows are checked against; rows that fail are never re-sealed
+            let sealed_under = Self::mac_version(&mut tx).await?;
+            if sealed_under < METADATA_MAC_VERSION {
+                let failed = Self::seal_vault(&mut tx, &Self::metadata_key(&data_key), Some(sealed_under)).await?;
+                if !failed.is_empty() {
+                    log::warn!("{} vault rows failed their integrity check and were not re-sealed", failed.len());
+                }
+            }
+        }
+
+        if params.is_legacy() || params.cost.is_weaker_than(&self.kdf_cost) {
+            // Only the wrapped data key changes; entries are left as they are
+            Self::wrap_and_store_data_key(&mut tx, master_password, &data_key, self.kdf_cost).await?;
+        }
+        tx.commit().await?;
         
-        let mut entries = Vec::new();
-        for row in rows {
-            let encrypted_password: String = row.get("encrypted_password");
-            let password = self.decrypt_data(&encrypted_password)?;
-            
-            let encrypted_notes: Option<String> = row.get("encrypted_notes");
-            let notes = encrypted_notes
-                .map(|enc| self.decrypt_data(&enc))
-                .transpose()?;
-                
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json)
-                .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-            
-            entries.push(PasswordEntry {
-                id: row.get("id"),
-                title: row.get("title"),
-                username: row.get("username"),
-                password,
-                url: row.get("url"),
-                notes,
-                folder: row.get("folder"),
-                tags,
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                last_used: row.get("last_used"),
-                is_favorite: row.get("is_favorite"),
+        {
+            let mut vault = self.vault.lock().unwrap();
+            vault.unlocked = Some(UnlockedVault {
+                cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key)),
+                mac_key: Self::metadata_key(&data_key),
+                index: SearchIndex::default(),
+                changed_during_rebuild: None,
             });
+            vault.last_activity = Instant::now();
         }
         
-        Ok(entries)
-    }
-    
-    pub async fn delete_password(&self, id: &str) -> Result<bool, PasswordManagerError> {
-        let result = sqlx::query("DELETE FROM password_entries WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
-            
-        Ok(result.rows_affected() > 0)
+        if let Err(e) = self.rebuild_search_index().await {
+            self.lock();
+            return Err(e);
+        }
+        
+        log::info!("Password vault unlocked");
+        Ok(())
     }
     
-    pub async fn search_passwords(&self, query: &str) -> Result<Vec<PasswordEntry>, PasswordManagerError> {
-        let search_pattern = format!("%{}%", query);
+    async fn rebuild_search_index(&self) -> Result<(), PasswordManagerError> {
+        if let Some(unlocked) = self.vault.lock().unwrap().unlocked.as_mut() {
+            unlocked.changed_during_rebuild = Some(HashSet::new());
+        }
         
-        let rows = sqlx::query(
-            r#"
-            SELECT id, title, username, encrypted_password, url, encrypted_notes, folder, tags,
-                   created_at, updated_at, last_used, is_favorite
-            FROM password_entries 
-            WHERE title LIKE ? OR username LIKE ? OR url LIKE ?
-            ORDER BY title
-            "#
-        )
-        .bind(&search_pattern)
-        .bind(&search_pattern)
-        .bind(&search_pattern)
-        .fetch_all(&self.pool)
-        .await?;
+        let mut index = SearchIndex::default();
+        for entry in self.list_passwords(None).await? {
+            index.upsert_entry(&entry);
+        }
+        for note in self.list_notes(None, None).await? {
+            index.upsert_note(&note);
+        }
         
-        let mut entries = Vec::new();
-        for row in rows {
-            let encrypted_password: String = row.get("encrypted_password");
-            let password = self.decrypt_data(&encrypted_password)?;
+        // Writes that landed after the listing above are re-read until none are
+        // left, then the index is swapped in under the same lock writers take
+        loop {
+            let changed = {
+                let mut vault = self.vault.lock().unwrap();
+                let Some(unlocked) = vault.unlocked.as_mut() else {
+                    return Ok(());
+                };
+                let changed = unlocked.changed_during_rebuild.take().unwrap_or_default();
+                if changed.is_empty() {
+                    log::debug!("Search index built with {} items", index.len());
+                    unlocked.index = index;
+                    return Ok(());
+                }
+                unlocked.changed_during_rebuild = Some(HashSet::new());
+                changed
+            };
             
-            let encrypted_notes: Option<String> = row.get("encrypted_notes");
-            let notes = encrypted_notes
-                .map(|enc| self.decrypt_data(&enc))
-                .transpose()?;
-                
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json)
-                .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-            
-            entries.push(PasswordEntry {
-                id: row.get("id"),
-                title: row.get("title"),
-                username: row.get("username"),
-                password,
-                url: row.get("url"),
-                notes,
-                folder: row.get("folder"),
-                tags,
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                last_used: row.get("last_used"),
-                is_favorite: row.get("is_favorite"),
-            });
+            for (kind, id) in changed {
+                match kind {
+                    ItemKind::Entry => match self.get_password(&id).await? {
+                        Some(entry) => index.upsert_entry(&entry),
+                        None => index.remove(kind, &id),
+                    },
+                    ItemKind::Note => match self.get_note(&id).await? {
+                        Some(note) => index.upsert_note(&note),
+                        None => index.remove(kind, &id),
+                    },
+                }
+            }
         }
-        
-        Ok(entries)
     }
     
-    pub fn generate_password(&self, length: usize, include_symbols: bool) -> String {
-        use rand::Rng;
-        
-        let mut chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789".chars().collect::<Vec<_>>();
-        
-        if include_symbols {
-            chars.extend("!@#$%^&*()_+-=[]{}|;:,.<>?".chars());
+    // Apply `f` to the search index; a no-op if the vault was locked meanwhile.
+    // During a rebuild the item is also remembered so the new index picks it up.
+    fn update_index(&self, kind: ItemKind, id: &str, f: impl FnOnce(&mut SearchIndex)) {
+        if let Some(unlocked) = self.vault.lock().unwrap().unlocked.as_mut() {
+            f(&mut unlocked.index);
+            if let Some(changed) = unlocked.changed_during_rebuild.as_mut() {
+                changed.insert((kind, id.to_string()));
+            }
         }
-        
-        let mut rng = rand::thread_rng();
-        (0..length)
-            .map(|_| chars[rng.gen_range(0..chars.len())])
-            .collect()
     }
     
-    pub async fn store_github_token(&self, token: &str) -> Result<(), PasswordManagerError> {
-        let entry = PasswordEntry {
-            id: "github_api_token".to_string(),
-            title: "GitHub API Token".to_string(),
-            username: "api".to_string(),
-            password: token.to_string(),
-            url: Some("https://github.com".to_string()),
-            notes: Some("Auto-generated for PWA Marketplace".to_string()),
-            folder: Some("System".to_string()),
-            tags: vec!["api".to_string(), "github".to_string()],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-            last_used: None,
-            is_favorite: false,
-        };
-        
-        self.store_password(&entry).await
+    /// Drop all key material. Returns false if the vault was already locked.
+    pub fn lock(&self) -> bool {
+        let was_unlocked = self.vault.lock().unwrap().unlocked.take().is_some();
+        if was_unlocked {
+            log::info!("Password vault locked");
+        }
+        was_unlocked
     }
     
-    pub async fn get_github_token(&self) -> Result<Option<String>, PasswordManagerError> {
-        if let Some(entry) = self.get_password("github_api_token").await? {
-            Ok(Some(entry.password))
-        } else {
-            Ok(None)
+    pub fn is_locked(&self) -> bool {
+        self.vault.lock().unwrap().unlocked.is_none()
+    }
+    
+    pub fn status(&self) -> VaultStatus {
+        let vault = self.vault.lock().unwrap();
+        VaultStatus {
+            locked: vault.unlocked.is_none(),
+            idle_seconds: vault.last_activity.elapsed().as_secs(),
         }
     }
     
-    // Private helper methods
+    /// Lock the vault if it has been idle for at least `timeout`
+    pub fn lock_if_idle(&self, timeout: Duration) -> bool {
+        let mut vault = self.vault.lock().unwrap();
+        if vault.unlocked.is_some() && vault.last_activity.elapsed() >= timeout {
+            vault.unlocked = None;
+            log::info!("Password vault locked after {}s of inactivity", timeout.as_secs());
+            return true;
+        }
+        false
+    }
     
-    fn get_database_path() -> Result<PathBuf, PasswordManagerError> {
-        let mut path = dirs::config_dir()
-            .ok_or_else(|| PasswordManagerError::Io(
-                std::io::Error::new(std::io::ErrorKind::NotFound, "Config directory not found")
-            ))?;
-        path.push("PWA-Marketplace");
-        path.push("passwords.db");
-        Ok(path)
+    /// Hook for platform power notifications: lock before the machine suspends
+    pub fn handle_system_sleep(&self) -> bool {
+        self.lock()
     }
     
-    fn derive_master_key(password: &str) -> Result<Vec<u8>, PasswordManagerError> {
-        let salt = b"pwa_marketplace_salt"; // In production, this should be randomly generated and stored
-        let mut key = [0u8; 32];
+    /// Spawn the idle/sleep watcher. `on_lock` is called whenever it locks the vault.
+    pub fn spawn_auto_lock<F>(&self, settings: AutoLockSettings, on_lock: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(LockReason) + Send + 'static,
+    {
+        let manager = self.clone();
         
-        argon2::Argon2::default()
-            .hash_password_into(password.as_bytes(), salt, &mut key)
-            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(settings.check_interval);
+            let mut last_tick = (Instant::now(), SystemTime::now());
             
-        Ok(key.to_vec())
+            loop {
+                interval.tick().await;
+                
+                let now = (Instant::now(), SystemTime::now());
+                let slept = Self::detect_sleep(last_tick, now, settings.check_interval);
+                last_tick = now;
+                
+                if settings.lock_on_sleep && slept && manager.handle_system_sleep() {
+                    on_lock(LockReason::SystemSleep);
+                    continue;
+                }
+                
+                if let Some(timeout) = settings.idle_timeout {
+                    if manager.lock_if_idle(timeout) {
+                        on_lock(LockReason::Idle);
+                    }
+                }
+            }
+        })
     }
     
-    fn encrypt_data(&self, data: &str) -> Result<String, PasswordManagerError> {
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
+    // The monotonic clock stops while the machine is suspended but the wall
+    // clock keeps going, so a large gap between the two means we just woke up.
+    fn detect_sleep(last: (Instant, SystemTime), now: (Instant, SystemTime), interval: Duration) -> bool {
+        let monotonic = now.0.duration_since(last.0);
+        let wall = now.1.duration_since(last.1).unwrap_or_default();
+        wall > monotonic + interval.max(Duration::from_secs(30))
+    }
+    
+    /// Re-wrap the data key under a fresh salt and `cost`. Entries are not
+    /// touched, so raising the cost is as cheap as a single key derivation.
+    pub async fn upgrade_kdf(&self, master_password: &str, cost: KdfCost) -> Result<(), PasswordManagerError> {
+        if !self.verify_master_password(master_password).await? {
+            return Err(PasswordManagerError::InvalidMasterPassword);
+        }
         
-        let ciphertext = self.cipher
-            .encrypt(nonce, data.as_bytes())
+        let params = self.load_kdf_params().await?;
+        let key_encryption_key = Self::derive_master_key(master_password, &params)?;
+        
+        let mut tx = self.pool.begin().await?;
+        let data_key = Self::open_data_key(&mut tx, &key_encryption_key).await?;
+        Self::wrap_and_store_data_key(&mut tx, master_password, &data_key, cost).await?;
+        tx.commit().await?;
+        
+        self.replace_data_key_if_unlocked(&data_key);
+        Ok(())
+    }
+    
+    /// Change the master password. The data key is re-wrapped under the new
+    /// password; vaults still on the old direct-key layout have every entry
+    /// re-encrypted in the same transaction.
+    pub async fn change_master_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), PasswordManagerError> {
+        if !self.verify_master_password(old_password).await? {
+            return Err(PasswordManagerError::InvalidMasterPassword);
+        }
+        
+        let params = self.load_kdf_params().await?;
+        let key_encryption_key = Self::derive_master_key(old_password, &params)?;
+        let password_hash = self.hash_master_password(new_password)?;
+        
+        let mut tx = self.pool.begin().await?;
+        let data_key = Self::open_data_key(&mut tx, &key_encryption_key).await?;
+        
+        sqlx::query("UPDATE master_config SET master_password_hash = ? WHERE id = 1")
+            .bind(&password_hash)
+            .execute(&mut *tx)
+            .await?;
+        Self::wrap_and_store_data_key(&mut tx, new_password, &data_key, self.kdf_cost).await?;
+        
+        tx.commit().await?;
+        
+        self.replace_data_key_if_unlocked(&data_key);
+        log::info!("Master password changed");
+        Ok(())
+    }
+    
+    /// Replace the data key itself, re-encrypting every entry in one transaction
+    pub async fn rotate_data_key(&self, master_password: &str) -> Result<(), PasswordManagerError> {
+        if !self.verify_master_password(master_password).await? {
+            return Err(PasswordManagerError::InvalidMasterPassword);
+        }
+        
+        let params = self.load_kdf_params().await?;
+        let key_encryption_key = Self::derive_master_key(master_password, &params)?;
+        
+        let mut tx = self.pool.begin().await?;
+        let old_key = Self::open_data_key(&mut tx, &key_encryption_key).await?;
+        let new_key = Self::generate_data_key();
+        
+        Self::reencrypt_vault(&mut tx, &old_key, &new_key).await?;
+        Self::wrap_and_store_data_key(&mut tx, master_password, &new_key, self.kdf_cost).await?;
+        
+        tx.commit().await?;
+        
+        self.replace_data_key_if_unlocked(&new_key);
+        log::info!("Vault data key rotated");
+        Ok(())
+    }
+    
+    fn replace_data_key_if_unlocked(&self, data_key: &[u8]) {
+        if let Some(unlocked) = self.vault.lock().unwrap().unlocked.as_mut() {
+            unlocked.cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(data_key));
+            unlocked.mac_key = Self::metadata_key(data_key);
+        }
+    }
+    
+    // Row MACs use their own key so the data key is only ever used with AES-GCM
+    fn metadata_key(data_key: &[u8]) -> hmac::Key {
+        hkdf::Salt::new(hkdf::HKDF_SHA256, &[])
+            .extract(data_key)
+            .expand(&[METADATA_MAC_INFO], hmac::HMAC_SHA256)
+            .expect("HMAC-SHA256 key length is within HKDF limits")
+            .into()
+    }
+    
+    fn generate_data_key() -> Zeroizing<Vec<u8>> {
+        let mut data_key = Zeroizing::new(vec![0u8; 32]);
+        OsRng.fill_bytes(&mut data_key);
+        data_key
+    }
+    
+    // Unwrap the stored data key. Vaults created before key wrapping encrypt
+    // entries directly with the password-derived key; for those a data key is
+    // created here and every entry is re-encrypted under it inside `tx`.
+    async fn open_data_key(
+        tx: &mut Transaction<'_, Sqlite>,
+        key_encryption_key: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, PasswordManagerError> {
+        let wrapped: Option<String> = sqlx::query_scalar("SELECT wrapped_data_key FROM master_config WHERE id = 1")
+            .fetch_optional(&mut **tx)
+            .await?
+            .flatten();
+        
+        if let Some(wrapped) = wrapped {
+            return Self::unwrap_data_key(key_encryption_key, &wrapped);
+        }
+        
+        let data_key = Self::generate_data_key();
+        Self::reencrypt_vault(tx, key_encryption_key, &data_key).await?;
+        
+        sqlx::query("UPDATE master_config SET wrapped_data_key = ? WHERE id = 1")
+            .bind(Self::wrap_data_key(key_encryption_key, &data_key)?)
+            .execute(&mut **tx)
+            .await?;
+        
+        log::info!("Vault migrated to a wrapped data key");
+        Ok(data_key)
+    }
+    
+    // Derive a new key-encryption key from `password` with a fresh salt and
+    // store the data key wrapped under it, together with the KDF parameters.
+    async fn wrap_and_store_data_key(
+        tx: &mut Transaction<'_, Sqlite>,
+        password: &str,
+        data_key: &[u8],
+        cost: KdfCost,
+    ) -> Result<(), PasswordManagerError> {
+        let params = KdfParams::generate(cost);
+        let key_encryption_key = Self::derive_master_key(password, &params)?;
+        let wrapped = Self::wrap_data_key(&key_encryption_key, data_key)?;
+        
+        sqlx::query(
+            r#"
+            UPDATE master_config
+            SET kdf_salt = ?, kdf_memory_kib = ?, kdf_iterations = ?, kdf_parallelism = ?,
+                wrapped_data_key = ?
+            WHERE id = 1
+            "#
+        )
+        .bind(base64::encode(&params.salt))
+        .bind(params.cost.memory_kib as i64)
+        .bind(params.cost.iterations as i64)
+        .bind(params.cost.parallelism as i64)
+        .bind(&wrapped)
+        .execute(&mut **tx)
+        .await?;
+        
+        log::info!(
+            "Vault data key wrapped (Argon2id m={}KiB t={} p={})",
+            cost.memory_kib, cost.iterations, cost.parallelism
+        );
+        Ok(())
+    }
+    
+    fn wrap_data_key(key_encryption_key: &[u8], data_key: &[u8]) -> Result<String, PasswordManagerError> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_encryption_key));
+        let mut nonce_bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), data_key)
             .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-            
+        
         let mut result = nonce_bytes.to_vec();
         result.extend_from_slice(&ciphertext);
-        
         Ok(base64::encode(result))
     }
     
-    fn decrypt_data(&self, encrypted_data: &str) -> Result<String, PasswordManagerError> {
-        let data = base64::decode(encrypted_data)
+    fn unwrap_data_key(key_encryption_key: &[u8], wrapped: &str) -> Result<Zeroizing<Vec<u8>>, PasswordManagerError> {
+        let data = base64::decode(wrapped)
             .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
-            
+        
         if data.len() < 12 {
-            return Err(PasswordManagerError::Encryption("Invalid encrypted data".to_string()));
+            return Err(PasswordManagerError::Encryption("Invalid wrapped data key".to_string()));
         }
         
         let (nonce_bytes, ciphertext) = data.split_at(12);
-        let nonce = Nonce::from_slice(nonce_bytes);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_encryption_key));
         
-        let plaintext = self.cipher
-            .decrypt(nonce, ciphertext)
-            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+            .map(Zeroizing::new)
+            .map_err(|_| PasswordManagerError::Encryption("Failed to unwrap vault data key".to_string()))
+    }
+    
+    // Re-encrypt every ciphertext under `new_key` and re-seal every row with
+    // the matching MAC key. Ciphertexts written before rows were bound through
+    // the associated data are bound on the way. A sealed vault is checked
+    // first, so tampered rows are never re-sealed under the new key. Rows
+    // that verify on their own are accepted as the new row set, which is how
+    // a vault whose rows were deleted behind its back is brought back.
+    async fn reencrypt_vault(
+        tx: &mut Transaction<'_, Sqlite>,
+        old_key: &[u8],
+        new_key: &[u8],
+    ) -> Result<(), PasswordManagerError> {
+        let old_cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(old_key));
+        let new_cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(new_key));
+        let bound = Self::ciphertexts_bound(tx).await?;
+        
+        if bound {
+            let report = Self::check_vault(tx, &old_cipher, &Self::metadata_key(old_key)).await?;
+            if !report.tampered.is_empty() || !report.orphaned.is_empty() {
+                return Err(PasswordManagerError::IntegrityCheckFailed(report));
+            }
+        }
+        
+        for table in VAULT_TABLES {
+            for column in table.encrypted {
+                let select = format!(
+                    "SELECT id, {} AS owner_id, {column} FROM {} WHERE {column} IS NOT NULL",
+                    table.id_column, table.name
+                );
+                let rows = sqlx::query(&select).fetch_all(&mut **tx).await?;
+                
+                let update = format!("UPDATE {} SET {column} = ? WHERE id = ?", table.name);
+                for row in rows {
+                    let id: String = row.get("id");
+                    let owner_id: String = row.get("owner_id");
+                    let encrypted: String = row.get(*column);
+                    
+                    let aad = associated_data(table, &owner_id, column);
+                    let old_aad: &[u8] = if bound { &aad } else { &[] };
+                    let plaintext = Zeroizing::new(Self::decrypt_with(&old_cipher, &encrypted, old_aad)?);
+                    let reencrypted = Self::encrypt_with(&new_cipher, &plaintext, &aad)?;
+                    
+                    sqlx::query(&update)
+                        .bind(&reencrypted)
+                        .bind(&id)
+                        .execute(&mut **tx)
+                        .await?;
+                }
+            }
+        }
+        
+        sqlx::query("UPDATE master_config SET ciphertexts_bound = 1 WHERE id = 1")
+            .execute(&mut **tx)
+            .await?;
+        
+        Self::seal_vault(tx, &Self::metadata_key(new_key), None).await?;
+        Ok(())
+    }
+    
+    async fn ciphertexts_bound(tx: &mut Transaction<'_, Sqlite>) -> Result<bool, PasswordManagerError> {
+        let bound: Option<i64> = sqlx::query_scalar("SELECT ciphertexts_bound FROM master_config WHERE id = 1")
+            .fetch_optional(&mut **tx)
+            .await?;
+        Ok(bound.unwrap_or(0) != 0)
+    }
+    
+    async fn mac_version(tx: &mut Transaction<'_, Sqlite>) -> Result<i64, PasswordManagerError> {
+        let version: Option<i64> = sqlx::query_scalar("SELECT mac_version FROM master_config WHERE id = 1")
+            .fetch_optional(&mut **tx)
+            .await?;
+        Ok(version.unwrap_or(METADATA_MAC_VERSION))
+    }
+    
+    // Recompute the MAC of every row under the current layout, e.g. after the
+    // data key or the MACed columns changed. With `sealed_under`, each row must
+    // first verify under that older layout; rows that don't keep their MAC, so
+    // they still show up as tampered, and are returned.
+    async fn seal_vault(
+        tx: &mut Transaction<'_, Sqlite>,
+        mac_key: &hmac::Key,
+        sealed_under: Option<i64>,
+    ) -> Result<Vec<RowIssue>, PasswordManagerError> {
+        let mut failed = Vec::new();
+        
+        for table in VAULT_TABLES {
+            let rows = sqlx::query(&authenticated_select(table)).fetch_all(&mut **tx).await?;
             
-        String::from_utf8(plaintext)
-            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))
+            let update = format!("UPDATE {} SET mac = ? WHERE id = ?", table.name);
+            for row in rows {
+                let id: String = row.get("id");
+                if let Some(version) = sealed_under {
+                    if !mac_matches(mac_key, table, version, &row) {
+                        failed.push(RowIssue {
+                            table: table.name.to_string(),
+                            id,
+                            detail: format!("metadata MAC mismatch under layout version {}", version),
+                        });
+                        continue;
+                    }
+                }
+                
+                sqlx::query(&update)
+                    .bind(row_mac(mac_key, table, METADATA_MAC_VERSION, &row))
+                    .bind(&id)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+        
+        sqlx::query("UPDATE master_config SET mac_version = ? WHERE id = 1")
+            .bind(METADATA_MAC_VERSION)
+            .execute(&mut **tx)
+            .await?;
+        
+        // A row that failed may stand for rows deleted around it too
+        if failed.is_empty() {
+            Self::seal_row_set(tx, mac_key).await?;
+        }
+        
+        Ok(failed)
     }
     
-    async fn init_database(&self) -> Result<(), PasswordManagerError> {
-        // Create master config table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS master_config (
-                id INTEGER PRIMARY KEY,
-                master_password_hash TEXT NOT NULL
-            )
-            "#
+    // MAC a single row as written, so the MAC covers exactly what is stored
+    async fn seal_row(
+        tx: &mut Transaction<'_, Sqlite>,
+        mac_key: &hmac::Key,
+        table: &VaultTable,
+        id: &str,
+    ) -> Result<(), PasswordManagerError> {
+        let row = sqlx::query(&format!("{} WHERE id = ?", authenticated_select(table)))
+            .bind(id)
+            .fetch_one(&mut **tx)
+            .await?;
+        
+        sqlx::query(&format!("UPDATE {} SET mac = ? WHERE id = ?", table.name))
+            .bind(row_mac(mac_key, table, METADATA_MAC_VERSION, &row))
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Whether the rows of every vault table are still the set the vault last
+    // sealed. A vault that never sealed its row set must still be empty.
+    async fn row_set_intact(tx: &mut Transaction<'_, Sqlite>, mac_key: &hmac::Key) -> Result<bool, PasswordManagerError> {
+        let stored: Option<String> = sqlx::query_scalar("SELECT vault_mac FROM master_config WHERE id = 1")
+            .fetch_optional(&mut **tx)
+            .await?
+            .flatten();
+        let (mac, rows) = row_set_mac(tx, mac_key).await?;
+        
+        Ok(match stored {
+            Some(stored) => constant_time::verify_slices_are_equal(stored.as_bytes(), mac.as_bytes()).is_ok(),
+            None => rows == 0,
+        })
+    }
+    
+    // Record the current row set. Writers only call this when `row_set_intact`
+    // held before their change, so a deletion made behind the vault's back
+    // isn't folded into the next legitimate write.
+    async fn seal_row_set(tx: &mut Transaction<'_, Sqlite>, mac_key: &hmac::Key) -> Result<(), PasswordManagerError> {
+        let (mac, _) = row_set_mac(tx, mac_key).await?;
+        sqlx::query("UPDATE master_config SET vault_mac = ? WHERE id = 1")
+            .bind(mac)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+    
+    /// Check every row's MAC and ciphertexts, the vault-level MAC over the set
+    /// of rows, and look for history rows whose entry is gone.
+    pub async fn verify_vault(&self) -> Result<IntegrityReport, PasswordManagerError> {
+        let cipher = self.with_cipher(|cipher| Ok(cipher.clone()))?;
+        let mac_key = self.mac_key()?;
+        
+        // Read everything from one snapshot
+        let mut tx = self.pool.begin().await?;
+        let report = Self::check_vault(&mut tx, &cipher, &mac_key).await?;
+        tx.rollback().await?;
+        
+        if !report.is_intact() {
+            log::warn!(
+                "Vault integrity check found {} tampered and {} orphaned rows{}",
+                report.tampered.len(), report.orphaned.len(),
+                if report.row_set_changed { ", and the row set changed" } else { "" }
+            );
+        }
+        
+        Ok(report)
+    }
+    
+    async fn check_vault(
+        tx: &mut Transaction<'_, Sqlite>,
+        cipher: &Aes256Gcm,
+        mac_key: &hmac::Key,
+    ) -> Result<IntegrityReport, PasswordManagerError> {
+        let mut report = IntegrityReport::default();
+        
+        for table in VAULT_TABLES {
+            let rows = sqlx::query(&authenticated_select(table)).fetch_all(&mut **tx).await?;
+            
+            for row in &rows {
+                report.rows_checked += 1;
+                let id: String = row.get("id");
+                let owner_id: String = row.get("owner_id");
+                let mut problems = Vec::new();
+                
+                if !mac_matches(mac_key, table, METADATA_MAC_VERSION, row) {
+                    problems.push("metadata MAC mismatch".to_string());
+                }
+                
+                for column in table.encrypted {
+                    let encrypted: Option<String> = row.get(*column);
+                    if let Some(encrypted) = encrypted {
+                        let aad = associated_data(table, &owner_id, column);
+                        if Self::decrypt_with(cipher, &encrypted, &aad).map(Zeroizing::new).is_err() {
+                            problems.push(format!("{} does not decrypt for this row", column));
+                        }
+                    }
+                }
+                
+                if !problems.is_empty() {
+                    report.tampered.push(RowIssue {
+                        table: table.name.to_string(),
+                        id,
+                        detail: problems.join("; "),
+                    });
+                }
+            }
+        }
+        
+        let orphans = sqlx::query(
+            "SELECT id, entry_id FROM password_history WHERE entry_id NOT IN (SELECT id FROM password_entries)"
         )
-        .execute(&self.pool)
+        .fetch_all(&mut **tx)
         .await?;
         
-        // Create password entries table
+        for row in orphans {
+            let entry_id: String = row.get("entry_id");
+            report.orphaned.push(RowIssue {
+                table: HISTORY.name.to_string(),
+                id: row.get("id"),
+                detail: format!("entry {} no longer exists", entry_id),
+            });
+        }
+        
+        report.row_set_changed = !Self::row_set_intact(tx, mac_key).await?;
+        Ok(report)
+    }
+    
+    async fn load_kdf_params(&self) -> Result<KdfParams, PasswordManagerError> {
+        let row = sqlx::query(
+            "SELECT kdf_salt, kdf_memory_kib, kdf_iterations, kdf_parallelism FROM master_config WHERE id = 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(KdfParams::legacy()),
+        };
+        
+        let salt: Option<String> = row.get("kdf_salt");
+        match salt {
+            Some(salt) => Ok(KdfParams {
+                salt: base64::decode(salt)
+                    .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?,
+                cost: KdfCost {
+                    memory_kib: row.get::<i64, _>("kdf_memory_kib") as u32,
+                    iterations: row.get::<i64, _>("kdf_iterations") as u32,
+                    parallelism: row.get::<i64, _>("kdf_parallelism") as u32,
+                },
+            }),
+            // Vault created before per-vault salts
+            None => Ok(KdfParams::legacy()),
+        }
+    }
+    
+    pub async fn verify_master_password(&self, password: &str) -> Result<bool, PasswordManagerError> {
+        // Get stored hash from database
+        let row = sqlx::query("SELECT master_password_hash FROM master_config WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+            
+        if let Some(row) = row {
+            let stored_hash: String = row.get("master_password_hash");
+            let parsed_hash = PasswordHash::new(&stored_hash)
+                .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+                
+            Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+        } else {
+            // Only setup may create the master password, so it goes through the policy
+            Err(PasswordManagerError::VaultNotInitialized)
+        }
+    }
+    
+    fn hash_master_password(&self, password: &str) -> Result<String, PasswordManagerError> {
+        let salt = SaltString::generate(&mut OsRng);
+        
+        Ok(self.kdf_cost.argon2()?
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?
+            .to_string())
+    }
+    
+    async fn store_master_password_hash(&self, password: &str) -> Result<(), PasswordManagerError> {
+        let password_hash = self.hash_master_password(password)?;
+        
+        // New vaults get their own random KDF salt from the start
+        let params = KdfParams::generate(self.kdf_cost);
+        
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS password_entries (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                username TEXT NOT NULL,
-                encrypted_password TEXT NOT NULL,
-                url TEXT,
-                encrypted_notes TEXT,
-                folder TEXT,
-                tags TEXT NOT NULL DEFAULT '[]',
-                created_at DATETIME NOT NULL,
-                updated_at DATETIME NOT NULL,
-                last_used DATETIME,
-                is_favorite BOOLEAN NOT NULL DEFAULT FALSE
-            )
+            INSERT OR REPLACE INTO master_config
+            (id, master_password_hash, kdf_salt, kdf_memory_kib, kdf_iterations, kdf_parallelism,
+             ciphertexts_bound, mac_version)
+            VALUES (1, ?, ?, ?, ?, ?, 1, ?)
             "#
         )
+        .bind(&password_hash)
+        .bind(base64::encode(&params.salt))
+        .bind(params.cost.memory_kib as i64)
+        .bind(params.cost.iterations as i64)
+        .bind(params.cost.parallelism as i64)
+        .bind(METADATA_MAC_VERSION)
         .execute(&self.pool)
         .await?;
         
-        // Create secure notes table
+        Ok(())
+    }
+    
+    pub async fn store_password(&self, entry: &PasswordEntry) -> Result<(), PasswordManagerError> {
+        if let Some(totp) = &entry.totp_secret {
+            Totp::parse(totp)?;
+        }
+        
+        // A tampered row may be overwritten, but is never archived
+        let previous = match self.get_password(&entry.id).await {
+            Err(PasswordManagerError::Tampered { table, id }) => {
+                log::warn!("Overwriting {} row {}, which failed its integrity check", table, id);
+                None
+            }
+            previous => previous?,
+        };
+        
+        let aad = |column| associated_data(&ENTRIES, &entry.id, column);
+        let encrypted_password = self.encrypt_data(&entry.password, &aad("encrypted_password"))?;
+        let encrypted_notes = entry.notes.as_ref()
+            .map(|notes| self.encrypt_data(notes, &aad("encrypted_notes")))
+            .transpose()?;
+        let encrypted_totp = entry.totp_secret.as_ref()
+            .map(|totp| self.encrypt_data(totp, &aad("encrypted_totp")))
+            .transpose()?;
+        let mac_key = self.mac_key()?;
+        let tags_json = serde_json::to_string(&entry.tags)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        
+        let mut tx = self.pool.begin().await?;
+        let row_set_intact = Self::row_set_intact(&mut tx, &mac_key).await?;
+        
+        // Bookkeeping updates such as `last_used` don't create a version
+        if previous.map(|previous| Self::content_changed(&previous, entry)).unwrap_or(false) {
+            Self::archive_entry(&mut tx, &mac_key, &entry.id, self.history_retention).await?;
+        }
+        
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS secure_notes (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                encrypted_content TEXT NOT NULL,
-                folder TEXT,
-                tags TEXT NOT NULL DEFAULT '[]',
-                created_at DATETIME NOT NULL,
-                updated_at DATETIME NOT NULL
-            )
+            INSERT OR REPLACE INTO password_entries 
+            (id, title, username, encrypted_password, url, encrypted_notes, folder, tags, 
+             created_at, updated_at, last_used, is_favorite, encrypted_totp, match_mode)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
-        .execute(&self.pool)
+        .bind(&entry.id)
+        .bind(&entry.title)
+        .bind(&entry.username)
+        .bind(&encrypted_password)
+        .bind(&entry.url)
+        .bind(&encrypted_notes)
+        .bind(&entry.folder)
+        .bind(&tags_json)
+        .bind(&entry.created_at)
+        .bind(&entry.updated_at)
+        .bind(&entry.last_used)
+        .bind(&entry.is_favorite)
+        .bind(&encrypted_totp)
+        .bind(entry.match_mode.as_str())
+        .execute(&mut *tx)
         .await?;
         
-        // Create indexes for better performance
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_entries_title ON password_entries(title)")
-            .execute(&self.pool)
+        Self::seal_row(&mut tx, &mac_key, &ENTRIES, &entry.id).await?;
+        if row_set_intact {
+            Self::seal_row_set(&mut tx, &mac_key).await?;
+        }
+        tx.commit().await?;
+        
+        self.update_index(ItemKind::Entry, &entry.id, |index| index.upsert_entry(entry));
+        Ok(())
+    }
+    
+    // Copy the current row of `entry_id` into `password_history` as its next
+    // version, then drop versions beyond `retention`. Ciphertexts are copied
+    // as-is; the history row gets its own MAC, and only if the entry's verifies.
+    async fn archive_entry(
+        tx: &mut Transaction<'_, Sqlite>,
+        mac_key: &hmac::Key,
+        entry_id: &str,
+        retention: usize,
+    ) -> Result<(), PasswordManagerError> {
+        let current = sqlx::query(&format!("{} WHERE id = ?", authenticated_select(&ENTRIES)))
+            .bind(entry_id)
+            .fetch_optional(&mut **tx)
             .await?;
-            
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_entries_folder ON password_entries(folder)")
-            .execute(&self.pool)
+        let verified = current.is_some_and(|row| mac_matches(mac_key, &ENTRIES, METADATA_MAC_VERSION, &row));
+        
+        if retention > 0 && verified {
+            let history_id = Uuid::new_v4().to_string();
+            sqlx::query(
+                r#"
+                INSERT INTO password_history
+                (id, entry_id, version, title, username, encrypted_password, url, encrypted_notes,
+                 folder, tags, created_at, updated_at, last_used, is_favorite, encrypted_totp, match_mode,
+                 archived_at)
+                SELECT ?, id,
+                       COALESCE((SELECT MAX(version) FROM password_history WHERE entry_id = ?), 0) + 1,
+                       title, username, encrypted_password, url, encrypted_notes,
+                       folder, tags, created_at, updated_at, last_used, is_favorite, encrypted_totp, match_mode,
+                       ?
+                FROM password_entries WHERE id = ?
+                "#
+            )
+            .bind(&history_id)
+            .bind(entry_id)
+            .bind(Utc::now())
+            .bind(entry_id)
+            .execute(&mut **tx)
             .await?;
+            Self::seal_row(tx, mac_key, &HISTORY, &history_id).await?;
+        }
+        
+        sqlx::query(
+            r#"
+            DELETE FROM password_history
+            WHERE entry_id = ?
+              AND version <= (SELECT MAX(version) FROM password_history WHERE entry_id = ?) - ?
+            "#
+        )
+        .bind(entry_id)
+        .bind(entry_id)
+        .bind(retention as i64)
+        .execute(&mut **tx)
+        .await?;
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    // Whether `updated` changes anything a user would want to roll back
+    fn content_changed(previous: &PasswordEntry, updated: &PasswordEntry) -> bool {
+        previous.title != updated.title
+            || previous.username != updated.username
+            || previous.password != updated.password
+            || previous.url != updated.url
+            || previous.notes != updated.notes
+            || previous.folder != updated.folder
+            || previous.tags != updated.tags
+            || previous.totp_secret != updated.totp_secret
+            || previous.match_mode != updated.match_mode
+    }
+    
+    /// Prior versions of an entry, newest first
+    pub async fn get_entry_history(&self, id: &str) -> Result<Vec<EntryVersion>, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let rows = sqlx::query(&format!(
+            "{} WHERE entry_id = ? ORDER BY version DESC",
+            authenticated_select(&HISTORY)
+        ))
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        skip_tampered(rows.iter().map(|row| {
+            Ok(EntryVersion {
+                version: row.get("version"),
+                archived_at: row.get("archived_at"),
+                entry: self.entry_from_row(&HISTORY, row)?,
+            })
+        }))
+    }
+    
+    /// Make an archived version current again. The version being replaced is
+    /// archived in turn, so a restore can itself be undone.
+    pub async fn restore_entry_version(&self, id: &str, version: i64) -> Result<PasswordEntry, PasswordManagerError> {
+        let mut entry = self.get_entry_history(id).await?
+            .into_iter()
+            .find(|v| v.version == version)
+            .ok_or(PasswordManagerError::EntryNotFound)?
+            .entry;
+        
+        entry.updated_at = Utc::now();
+        if let Some(current) = self.get_password(id).await? {
+            entry.created_at = current.created_at;
+            entry.last_used = current.last_used;
+        }
+        
+        self.store_password(&entry).await?;
+        log::info!("Restored entry {} to version {}", id, version);
+        Ok(entry)
+    }
+    
+    pub async fn get_password(&self, id: &str) -> Result<Option<PasswordEntry>, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let row = sqlx::query(&format!("{} WHERE id = ?", authenticated_select(&ENTRIES)))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        row.map(|row| self.entry_from_row(&ENTRIES, &row)).transpose()
+    }
+    
+    pub async fn list_passwords(&self, folder: Option<&str>) -> Result<Vec<PasswordEntry>, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let rows = sqlx::query(&format!(
+            "{} WHERE (?1 IS NULL OR folder = ?1) ORDER BY title",
+            authenticated_select(&ENTRIES)
+        ))
+        .bind(folder)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        skip_tampered(rows.iter().map(|row| self.entry_from_row(&ENTRIES, row)))
+    }
+    
+    pub async fn delete_password(&self, id: &str) -> Result<bool, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let mac_key = self.mac_key()?;
+        let mut tx = self.pool.begin().await?;
+        let row_set_intact = Self::row_set_intact(&mut tx, &mac_key).await?;
+        
+        sqlx::query("DELETE FROM password_history WHERE entry_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        let result = sqlx::query("DELETE FROM password_entries WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        if row_set_intact {
+            Self::seal_row_set(&mut tx, &mac_key).await?;
+        }
+        tx.commit().await?;
+        
+        self.update_index(ItemKind::Entry, id, |index| index.remove(ItemKind::Entry, id));
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Fuzzy search over titles, usernames, URL hosts, tags and notes, best match first
+    pub async fn search_passwords(&self, query: &str) -> Result<Vec<PasswordEntry>, PasswordManagerError> {
+        let hits = self.search_vault(query, Some(ItemKind::Entry), usize::MAX)?;
+        let rows = self.fetch_rows_by_id(&authenticated_select(&ENTRIES), &hits).await?;
+        
+        let mut entries: HashMap<String, PasswordEntry> = skip_tampered(
+            rows.iter().map(|row| self.entry_from_row(&ENTRIES, row).map(|entry| (entry.id.clone(), entry)))
+        )?.into_iter().collect();
+        Ok(hits.iter().filter_map(|hit| entries.remove(&hit.id)).collect())
+    }
+    
+    // Fetch the rows for `hits` in one query per chunk of ids, in no particular order
+    async fn fetch_rows_by_id(&self, select: &str, hits: &[SearchHit]) -> Result<Vec<SqliteRow>, PasswordManagerError> {
+        let mut rows = Vec::with_capacity(hits.len());
+        
+        // Stay well below SQLite's limit on bound parameters
+        for chunk in hits.chunks(500) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("{} WHERE id IN ({})", select, placeholders);
+            let query = chunk.iter().fold(sqlx::query(&sql), |query, hit| query.bind(&hit.id));
+            rows.extend(query.fetch_all(&self.pool).await?);
+        }
+        
+        Ok(rows)
+    }
+    
+    /// Query the in-memory index of the unlocked vault
+    pub fn search_vault(
+        &self,
+        query: &str,
+        kind: Option<ItemKind>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>, PasswordManagerError> {
+        let mut vault = self.vault.lock().unwrap();
+        vault.last_activity = Instant::now();
+        let unlocked = vault.unlocked.as_ref().ok_or(PasswordManagerError::VaultLocked)?;
+        
+        Ok(unlocked.index.search(query, kind, limit))
+    }
+    
+    /// Entries saved for a URL that may be filled into `url`'s origin under
+    /// their match mode, best match first and most recently used first
+    /// within a match. Doesn't touch `last_used`; see `mark_used`.
+    pub async fn find_credentials_for_origin(
+        &self,
+        url: &str,
+    ) -> Result<Vec<(PasswordEntry, MatchStrength)>, PasswordManagerError> {
+        let origin = Origin::parse(url)?;
+        
+        let mut matches: Vec<_> = self.list_passwords(None).await?
+            .into_iter()
+            .filter_map(|entry| {
+                let strength = autofill::match_entry(entry.url.as_deref()?, entry.match_mode, &origin)?;
+                Some((entry, strength))
+            })
+            .collect();
+        
+        matches.sort_by(|(a, a_strength), (b, b_strength)| {
+            b_strength.cmp(a_strength).then_with(|| b.last_used.cmp(&a.last_used))
+        });
+        Ok(matches)
+    }
+    
+    /// Record that an entry's credentials were just handed out
+    pub async fn mark_used(&self, id: &str) -> Result<(), PasswordManagerError> {
+        let mut entry = self.get_password(id).await?
+            .ok_or(PasswordManagerError::EntryNotFound)?;
+        entry.last_used = Some(Utc::now());
+        
+        self.store_password(&entry).await
+    }
+    
+    /// Current TOTP code for an entry with a stored one-time-password secret
+    pub async fn generate_totp(&self, id: &str) -> Result<TotpCode, PasswordManagerError> {
+        let entry = self.get_password(id).await?
+            .ok_or(PasswordManagerError::EntryNotFound)?;
+        let secret = Zeroizing::new(entry.totp_secret.ok_or(TotpError::NotConfigured)?);
+        
+        Ok(Totp::parse(&secret)?.current())
+    }
+    
+    // Decode a row of `password_entries` or `password_history` selected with
+    // `authenticated_select`, checking its MAC
+    fn entry_from_row(&self, table: &VaultTable, row: &SqliteRow) -> Result<PasswordEntry, PasswordManagerError> {
+        let id: String = row.get("owner_id");
+        let aad = |column| associated_data(table, &id, column);
+        
+        let encrypted_password: String = row.get("encrypted_password");
+        let password = self.decrypt_data(&encrypted_password, &aad("encrypted_password"))?;
+        
+        let encrypted_notes: Option<String> = row.get("encrypted_notes");
+        let notes = encrypted_notes
+            .map(|enc| self.decrypt_data(&enc, &aad("encrypted_notes")))
+            .transpose()?;
+        
+        let encrypted_totp: Option<String> = row.get("encrypted_totp");
+        let totp_secret = encrypted_totp
+            .map(|enc| self.decrypt_data(&enc, &aad("encrypted_totp")))
+            .transpose()?;
+            
+        let tags_json: String = row.get("tags");
+        let tags: Vec<String> = serde_json::from_str(&tags_json)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        self.check_row_mac(table, row)?;
+        
+        Ok(PasswordEntry {
+            id,
+            title: row.get("title"),
+            username: row.get("username"),
+            password,
+            url: row.get("url"),
+            notes,
+            folder: row.get("folder"),
+            tags,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+            last_used: row.get("last_used"),
+            is_favorite: row.get("is_favorite"),
+            totp_secret,
+            match_mode: MatchMode::from_db(row.get::<Option<&str>, _>("match_mode")),
+        })
+    }
+    
+    pub async fn store_note(&self, note: &SecureNote) -> Result<(), PasswordManagerError> {
+        let encrypted_content = self.encrypt_data(
+            &note.content,
+            &associated_data(&NOTES, &note.id, "encrypted_content"),
+        )?;
+        let tags_json = serde_json::to_string(&note.tags)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        let mac_key = self.mac_key()?;
+        
+        let mut tx = self.pool.begin().await?;
+        let row_set_intact = Self::row_set_intact(&mut tx, &mac_key).await?;
+        
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO secure_notes
+            (id, title, encrypted_content, folder, tags, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&note.id)
+        .bind(&note.title)
+        .bind(&encrypted_content)
+        .bind(&note.folder)
+        .bind(&tags_json)
+        .bind(note.created_at)
+        .bind(note.updated_at)
+        .execute(&mut *tx)
+        .await?;
+        
+        Self::seal_row(&mut tx, &mac_key, &NOTES, &note.id).await?;
+        if row_set_intact {
+            Self::seal_row_set(&mut tx, &mac_key).await?;
+        }
+        tx.commit().await?;
+        
+        self.update_index(ItemKind::Note, &note.id, |index| index.upsert_note(note));
+        Ok(())
+    }
+    
+    pub async fn get_note(&self, id: &str) -> Result<Option<SecureNote>, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let row = sqlx::query(&format!("{} WHERE id = ?", authenticated_select(&NOTES)))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        row.map(|row| self.note_from_row(&row)).transpose()
+    }
+    
+    /// List notes, optionally restricted to a folder and/or notes carrying `tag`
+    pub async fn list_notes(
+        &self,
+        folder: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<Vec<SecureNote>, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let rows = sqlx::query(&format!(
+            r#"
+            {}
+            WHERE (?1 IS NULL OR folder = ?1)
+              AND (?2 IS NULL OR EXISTS (SELECT 1 FROM json_each(secure_notes.tags) WHERE json_each.value = ?2))
+            ORDER BY title
+            "#,
+            authenticated_select(&NOTES)
+        ))
+        .bind(folder)
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        skip_tampered(rows.iter().map(|row| self.note_from_row(row)))
+    }
+    
+    pub async fn delete_note(&self, id: &str) -> Result<bool, PasswordManagerError> {
+        let mac_key = self.mac_key()?;
+        let mut tx = self.pool.begin().await?;
+        let row_set_intact = Self::row_set_intact(&mut tx, &mac_key).await?;
+        
+        let result = sqlx::query("DELETE FROM secure_notes WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        if row_set_intact {
+            Self::seal_row_set(&mut tx, &mac_key).await?;
+        }
+        tx.commit().await?;
+        
+        self.update_index(ItemKind::Note, id, |index| index.remove(ItemKind::Note, id));
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Fuzzy search over note titles, tags, folders and decrypted content
+    pub async fn search_notes(&self, query: &str) -> Result<Vec<SecureNote>, PasswordManagerError> {
+        let hits = self.search_vault(query, Some(ItemKind::Note), usize::MAX)?;
+        let rows = self.fetch_rows_by_id(&authenticated_select(&NOTES), &hits).await?;
+        
+        let mut notes: HashMap<String, SecureNote> = skip_tampered(
+            rows.iter().map(|row| self.note_from_row(row).map(|note| (note.id.clone(), note)))
+        )?.into_iter().collect();
+        Ok(hits.iter().filter_map(|hit| notes.remove(&hit.id)).collect())
+    }
+    
+    fn note_from_row(&self, row: &SqliteRow) -> Result<SecureNote, PasswordManagerError> {
+        let id: String = row.get("id");
+        let encrypted_content: String = row.get("encrypted_content");
+        let content = self.decrypt_data(
+            &encrypted_content,
+            &associated_data(&NOTES, &id, "encrypted_content"),
+        )?;
+        
+        let tags_json: String = row.get("tags");
+        let tags: Vec<String> = serde_json::from_str(&tags_json)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        self.check_row_mac(&NOTES, row)?;
+        
+        Ok(SecureNote {
+            id,
+            title: row.get("title"),
+            content,
+            folder: row.get("folder"),
+            tags,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+    
+    /// This vault's public key, created on first use. Share it with a
+    /// collection owner to be added to their collection.
+    pub async fn identity_public_key(&self) -> Result<String, PasswordManagerError> {
+        Ok(self.identity().await?.public_key())
+    }
+    
+    async fn identity(&self) -> Result<Identity, PasswordManagerError> {
+        let aad = associated_data(&IDENTITY, "self", "encrypted_private_key");
+        let stored: Option<String> = sqlx::query_scalar(
+            "SELECT encrypted_private_key FROM vault_identity WHERE id = 'self'"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        if let Some(encrypted) = stored {
+            let encoded = Zeroizing::new(self.decrypt_data(&encrypted, &aad)?);
+            let secret = Zeroizing::new(
+                base64::decode(encoded.as_str())
+                    .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?
+            );
+            return Ok(Identity::from_bytes(&secret)?);
+        }
+        
+        let identity = Identity::generate();
+        let encoded = Zeroizing::new(base64::encode(identity.to_bytes().as_ref()));
+        let encrypted = self.encrypt_data(&encoded, &aad)?;
+        let mac_key = self.mac_key()?;
+        
+        let mut tx = self.pool.begin().await?;
+        let row_set_intact = Self::row_set_intact(&mut tx, &mac_key).await?;
+        sqlx::query(
+            "INSERT INTO vault_identity (id, public_key, encrypted_private_key, created_at) VALUES ('self', ?, ?, ?)"
+        )
+        .bind(identity.public_key())
+        .bind(&encrypted)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        Self::seal_row(&mut tx, &mac_key, &IDENTITY, "self").await?;
+        if row_set_intact {
+            Self::seal_row_set(&mut tx, &mac_key).await?;
+        }
+        tx.commit().await?;
+        
+        log::info!("Created vault identity for team collections");
+        Ok(identity)
+    }
+    
+    /// Create a shared collection with this vault as its only member
+    pub async fn create_collection(&self, name: &str, member_name: &str) -> Result<Collection, PasswordManagerError> {
+        let identity = self.identity().await?;
+        let key = team_vault::generate_collection_key();
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        
+        let mut bundle = CollectionBundle {
+            format: team_vault::BUNDLE_FORMAT.to_string(),
+            version: team_vault::BUNDLE_VERSION,
+            members: vec![WrappedMember::new(&key, &id, member_name, &identity.public_key())?],
+            id,
+            name: name.to_string(),
+            key_version: 1,
+            created_at: now,
+            updated_at: now,
+            signed_by: String::new(),
+            signature: String::new(),
+            items: Vec::new(),
+        };
+        bundle.sign_roster(&identity);
+        
+        self.save_collection(&bundle).await?;
+        log::info!("Created team collection {}", bundle.id);
+        Ok(bundle.summary())
+    }
+    
+    pub async fn list_collections(&self) -> Result<Vec<Collection>, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM collections ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+        
+        let mut collections = Vec::with_capacity(ids.len());
+        for id in ids {
+            collections.push(self.load_collection(&id).await?.summary());
+        }
+        
+        Ok(collections)
+    }
+    
+    /// Remove the local copy of a collection; other members keep theirs
+    pub async fn delete_collection(&self, collection_id: &str) -> Result<bool, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        
+        let mut tx = self.pool.begin().await?;
+        Self::clear_collection(&mut tx, collection_id).await?;
+        let result = sqlx::query("DELETE FROM collections WHERE id = ?")
+            .bind(collection_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Wrap the collection key to `public_key`. The new member gets access
+    /// once they import a bundle exported after this.
+    pub async fn add_collection_member(
+        &self,
+        collection_id: &str,
+        name: &str,
+        public_key: &str,
+    ) -> Result<Collection, PasswordManagerError> {
+        let identity = self.identity().await?;
+        let mut bundle = self.load_collection(collection_id).await?;
+        let key = bundle.open_key(&identity)?;
+        
+        let member = WrappedMember::new(&key, collection_id, name, public_key.trim())?;
+        bundle.members.retain(|m| m.public_key != member.public_key);
+        bundle.members.push(member);
+        bundle.updated_at = Utc::now();
+        bundle.sign_roster(&identity);
+        
+        self.save_collection(&bundle).await?;
+        Ok(bundle.summary())
+    }
+    
+    /// Remove a member and replace the collection key, so bundles exported
+    /// from now on are unreadable to them. Items are re-encrypted under the new key.
+    pub async fn remove_collection_member(
+        &self,
+        collection_id: &str,
+        public_key: &str,
+    ) -> Result<Collection, PasswordManagerError> {
+        let identity = self.identity().await?;
+        let mut bundle = self.load_collection(collection_id).await?;
+        let old_key = bundle.open_key(&identity)?;
+        
+        if public_key.trim() == identity.public_key() {
+            return Err(PasswordManagerError::Encryption(
+                "Cannot remove this vault from a collection; delete the collection instead".to_string()
+            ));
+        }
+        if !bundle.members.iter().any(|m| m.public_key == public_key.trim()) {
+            return Err(PasswordManagerError::EntryNotFound);
+        }
+        
+        let new_key = team_vault::generate_collection_key();
+        let mut items = Vec::with_capacity(bundle.items.len());
+        for item in &bundle.items {
+            let entry = team_vault::open_item(&old_key, collection_id, item)?;
+            items.push(team_vault::seal_item(&new_key, collection_id, &item.id, item.updated_at, entry.as_ref())?);
+        }
+        
+        let mut members = Vec::with_capacity(bundle.members.len());
+        for member in bundle.members.iter().filter(|m| m.public_key != public_key.trim()) {
+            members.push(WrappedMember::new(&new_key, collection_id, &member.name, &member.public_key)?);
+        }
+        
+        bundle.items = items;
+        bundle.members = members;
+        bundle.key_version += 1;
+        bundle.updated_at = Utc::now();
+        bundle.sign_roster(&identity);
+        
+        self.save_collection(&bundle).await?;
+        log::info!("Removed a member from collection {} and rotated its key", collection_id);
+        Ok(bundle.summary())
+    }
+    
+    pub async fn store_collection_item(
+        &self,
+        collection_id: &str,
+        entry: &PasswordEntry,
+    ) -> Result<(), PasswordManagerError> {
+        self.put_collection_item(collection_id, &entry.id, Some(entry)).await
+    }
+    
+    /// Delete an item. A tombstone is kept so the deletion reaches other members.
+    pub async fn delete_collection_item(&self, collection_id: &str, item_id: &str) -> Result<bool, PasswordManagerError> {
+        let exists = self.list_collection_items(collection_id).await?
+            .iter()
+            .any(|entry| entry.id == item_id);
+        
+        if exists {
+            self.put_collection_item(collection_id, item_id, None).await?;
+        }
+        Ok(exists)
+    }
+    
+    pub async fn list_collection_items(&self, collection_id: &str) -> Result<Vec<PasswordEntry>, PasswordManagerError> {
+        let identity = self.identity().await?;
+        let bundle = self.load_collection(collection_id).await?;
+        let key = bundle.open_key(&identity)?;
+        
+        let mut entries = Vec::new();
+        for item in &bundle.items {
+            if let Some(entry) = team_vault::open_item(&key, collection_id, item)? {
+                entries.push(entry);
+            }
+        }
+        
+        entries.sort_by(|a, b| a.title.cmp(&b.title));
+        Ok(entries)
+    }
+    
+    /// Everything other members need to sync this collection
+    pub async fn export_collection(&self, collection_id: &str) -> Result<CollectionBundle, PasswordManagerError> {
+        self.ensure_unlocked()?;
+        self.load_collection(collection_id).await
+    }
+    
+    /// Merge a bundle from another member. The member list with the newer key
+    /// version (then the newer timestamp) wins, and for each item the newest
+    /// version wins. Bundles must be signed by someone on the local member
+    /// list, so a removed member can't forge a newer one, and bundles from
+    /// before a key rotation are ignored, so they can't replay an old one.
+    pub async fn import_collection(&self, bundle: &CollectionBundle) -> Result<Collection, PasswordManagerError> {
+        let identity = self.identity().await?;
+        let incoming_key = bundle.open_key(&identity)?;
+        
+        let local = match self.load_collection(&bundle.id).await {
+            Ok(local) => local,
+            Err(PasswordManagerError::TeamVault(TeamVaultError::CollectionNotFound(_))) => {
+                // Open every item once so a corrupt bundle is rejected up front
+                for item in &bundle.items {
+                    team_vault::open_item(&incoming_key, &bundle.id, item)?;
+                }
+                self.save_collection(bundle).await?;
+                return Ok(bundle.summary());
+            }
+            Err(e) => return Err(e),
+        };
+        let local_key = local.open_key(&identity)?;
+        
+        if !local.members.iter().any(|m| m.public_key == bundle.signed_by) {
+            return Err(TeamVaultError::UntrustedSigner(bundle.id.clone()).into());
+        }
+        if bundle.key_version < local.key_version {
+            log::warn!(
+                "Ignoring bundle for collection {} with outdated key version {}",
+                bundle.id, bundle.key_version
+            );
+            return Ok(local.summary());
+        }
+        
+        let mut newest: BTreeMap<String, (DateTime<Utc>, Option<PasswordEntry>)> = BTreeMap::new();
+        for (key, source) in [(&local_key, &local), (&incoming_key, bundle)] {
+            for item in &source.items {
+                let entry = team_vault::open_item(key, &source.id, item)?;
+                let newer = newest.get(&item.id).map(|(at, _)| item.updated_at > *at).unwrap_or(true);
+                if newer {
+                    newest.insert(item.id.clone(), (item.updated_at, entry));
+                }
+            }
+        }
+        
+        let incoming_roster_newer = (bundle.key_version, bundle.updated_at) > (local.key_version, local.updated_at);
+        let (mut merged, key) = if incoming_roster_newer {
+            (bundle.clone(), incoming_key)
+        } else {
+            (local, local_key)
+        };
+        
+        merged.items = newest
+            .iter()
+            .map(|(id, (updated_at, entry))| {
+                team_vault::seal_item(&key, &merged.id, id, *updated_at, entry.as_ref())
+            })
+            .collect::<Result<_, _>>()?;
+        
+        self.save_collection(&merged).await?;
+        Ok(merged.summary())
+    }
+    
+    async fn put_collection_item(
+        &self,
+        collection_id: &str,
+        item_id: &str,
+        entry: Option<&PasswordEntry>,
+    ) -> Result<(), PasswordManagerError> {
+        let identity = self.identity().await?;
+        let key = self.load_collection(collection_id).await?.open_key(&identity)?;
+        let item = team_vault::seal_item(&key, collection_id, item_id, Utc::now(), entry)?;
+        
+        sqlx::query(
+            "INSERT OR REPLACE INTO collection_items (collection_id, id, sealed, updated_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(collection_id)
+        .bind(&item.id)
+        .bind(&item.sealed)
+        .bind(item.updated_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    async fn load_collection(&self, collection_id: &str) -> Result<CollectionBundle, PasswordManagerError> {
+        let row = sqlx::query(
+            "SELECT id, name, key_version, signed_by, signature, created_at, updated_at FROM collections WHERE id = ?"
+        )
+        .bind(collection_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| TeamVaultError::CollectionNotFound(collection_id.to_string()))?;
+        
+        let members = sqlx::query(
+            "SELECT name, public_key, wrapped_key FROM collection_members WHERE collection_id = ? ORDER BY name"
+        )
+        .bind(collection_id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|member| WrappedMember {
+            name: member.get("name"),
+            public_key: member.get("public_key"),
+            wrapped_key: member.get("wrapped_key"),
+        })
+        .collect();
+        
+        let items = sqlx::query("SELECT id, sealed, updated_at FROM collection_items WHERE collection_id = ? ORDER BY id")
+            .bind(collection_id)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|item| SealedItem {
+                id: item.get("id"),
+                updated_at: item.get("updated_at"),
+                sealed: item.get("sealed"),
+            })
+            .collect();
+        
+        Ok(CollectionBundle {
+            format: team_vault::BUNDLE_FORMAT.to_string(),
+            version: team_vault::BUNDLE_VERSION,
+            id: row.get("id"),
+            name: row.get("name"),
+            key_version: row.get("key_version"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+            members,
+            signed_by: row.get("signed_by"),
+            signature: row.get("signature"),
+            items,
+        })
+    }
+    
+    // Replace the stored state of a collection with `bundle`
+    async fn save_collection(&self, bundle: &CollectionBundle) -> Result<(), PasswordManagerError> {
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO collections (id, name, key_version, signed_by, signature, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&bundle.id)
+        .bind(&bundle.name)
+        .bind(bundle.key_version)
+        .bind(&bundle.signed_by)
+        .bind(&bundle.signature)
+        .bind(bundle.created_at)
+        .bind(bundle.updated_at)
+        .execute(&mut *tx)
+        .await?;
+        
+        Self::clear_collection(&mut tx, &bundle.id).await?;
+        
+        for member in &bundle.members {
+            sqlx::query(
+                "INSERT INTO collection_members (collection_id, public_key, name, wrapped_key) VALUES (?, ?, ?, ?)"
+            )
+            .bind(&bundle.id)
+            .bind(&member.public_key)
+            .bind(&member.name)
+            .bind(&member.wrapped_key)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        for item in &bundle.items {
+            sqlx::query(
+                "INSERT INTO collection_items (collection_id, id, sealed, updated_at) VALUES (?, ?, ?, ?)"
+            )
+            .bind(&bundle.id)
+            .bind(&item.id)
+            .bind(&item.sealed)
+            .bind(item.updated_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    async fn clear_collection(tx: &mut Transaction<'_, Sqlite>, collection_id: &str) -> Result<(), PasswordManagerError> {
+        for table in ["collection_members", "collection_items"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE collection_id = ?", table))
+                .bind(collection_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        Ok(())
+    }
+    
+    /// Random alphanumeric password; see `password_generator` for the other modes
+    pub fn generate_password(&self, length: usize, include_symbols: bool) -> Result<String, PasswordManagerError> {
+        let options = GeneratorOptions {
+            length,
+            symbols: include_symbols,
+            min_lowercase: 0,
+            min_uppercase: 0,
+            min_digits: 0,
+            min_symbols: 0,
+            ..Default::default()
+        };
+        
+        Ok(password_generator::generate(&options)?.password)
+    }
+    
+    pub async fn store_github_token(&self, token: &str) -> Result<(), PasswordManagerError> {
+        let entry = PasswordEntry {
+            id: "github_api_token".to_string(),
+            title: "GitHub API Token".to_string(),
+            username: "api".to_string(),
+            password: token.to_string(),
+            url: Some("https://github.com".to_string()),
+            notes: Some("Auto-generated for PWA Marketplace".to_string()),
+            folder: Some("System".to_string()),
+            tags: vec!["api".to_string(), "github".to_string()],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_used: None,
+            is_favorite: false,
+            totp_secret: None,
+            match_mode: MatchMode::default(),
+        };
+        
+        self.store_password(&entry).await
+    }
+    
+    pub async fn get_github_token(&self) -> Result<Option<String>, PasswordManagerError> {
+        if let Some(entry) = self.get_password("github_api_token").await? {
+            Ok(Some(entry.password))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    // Private helper methods
+    
+    fn get_database_path() -> Result<PathBuf, PasswordManagerError> {
+        let mut path = dirs::config_dir()
+            .ok_or_else(|| PasswordManagerError::Io(
+                std::io::Error::new(std::io::ErrorKind::NotFound, "Config directory not found")
+            ))?;
+        path.push("PWA-Marketplace");
+        path.push("passwords.db");
+        Ok(path)
+    }
+    
+    fn derive_master_key(password: &str, params: &KdfParams) -> Result<Zeroizing<Vec<u8>>, PasswordManagerError> {
+        let mut key = Zeroizing::new(vec![0u8; 32]);
+        
+        params.cost.argon2()?
+            .hash_password_into(password.as_bytes(), &params.salt, &mut key)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        
+        Ok(key)
+    }
+    
+    /// Run `f` with the vault cipher, failing with `VaultLocked` when locked.
+    /// Every successful call counts as activity for the idle timer.
+    fn with_cipher<T>(
+        &self,
+        f: impl FnOnce(&Aes256Gcm) -> Result<T, PasswordManagerError>
+    ) -> Result<T, PasswordManagerError> {
+        let mut vault = self.vault.lock().unwrap();
+        vault.last_activity = Instant::now();
+        let unlocked = vault.unlocked.as_ref().ok_or(PasswordManagerError::VaultLocked)?;
+        f(&unlocked.cipher)
+    }
+    
+    fn ensure_unlocked(&self) -> Result<(), PasswordManagerError> {
+        self.with_cipher(|_| Ok(()))
+    }
+    
+    fn mac_key(&self) -> Result<hmac::Key, PasswordManagerError> {
+        let vault = self.vault.lock().unwrap();
+        vault.unlocked.as_ref()
+            .map(|unlocked| unlocked.mac_key.clone())
+            .ok_or(PasswordManagerError::VaultLocked)
+    }
+    
+    fn check_row_mac(&self, table: &VaultTable, row: &SqliteRow) -> Result<(), PasswordManagerError> {
+        if mac_matches(&self.mac_key()?, table, METADATA_MAC_VERSION, row) {
+            Ok(())
+        } else {
+            Err(PasswordManagerError::Tampered {
+                table: table.name.to_string(),
+                id: row.get("id"),
+            })
+        }
+    }
+    
+    fn encrypt_data(&self, data: &str, aad: &[u8]) -> Result<String, PasswordManagerError> {
+        self.with_cipher(|cipher| Self::encrypt_with(cipher, data, aad))
+    }
+    
+    fn decrypt_data(&self, encrypted_data: &str, aad: &[u8]) -> Result<String, PasswordManagerError> {
+        self.with_cipher(|cipher| Self::decrypt_with(cipher, encrypted_data, aad))
+    }
+    
+    fn encrypt_with(cipher: &Aes256Gcm, data: &str, aad: &[u8]) -> Result<String, PasswordManagerError> {
+        let mut nonce_bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+        
+        let ciphertext = cipher
+            .encrypt(nonce, Payload { msg: data.as_bytes(), aad })
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        
+        let mut result = nonce_bytes.to_vec();
+        result.extend_from_slice(&ciphertext);
+        
+        Ok(base64::encode(result))
+    }
+    
+    fn decrypt_with(cipher: &Aes256Gcm, encrypted_data: &str, aad: &[u8]) -> Result<String, PasswordManagerError> {
+        let data = base64::decode(encrypted_data)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        
+        if data.len() < 12 {
+            return Err(PasswordManagerError::Encryption("Invalid encrypted data".to_string()));
+        }
+        
+        let (nonce_bytes, ciphertext) = data.split_at(12);
+        let nonce = Nonce::from_slice(nonce_bytes);
+        
+        let plaintext = cipher
+            .decrypt(nonce, Payload { msg: ciphertext, aad })
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))?;
+        
+        String::from_utf8(plaintext)
+            .map_err(|e| PasswordManagerError::Encryption(e.to_string()))
+    }
+    
+    async fn init_database(&self) -> Result<(), PasswordManagerError> {
+        // Create master config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS master_config (
+                id INTEGER PRIMARY KEY,
+                master_password_hash TEXT NOT NULL,
+                kdf_salt TEXT,
+                kdf_memory_kib INTEGER,
+                kdf_iterations INTEGER,
+                kdf_parallelism INTEGER,
+                wrapped_data_key TEXT,
+                ciphertexts_bound INTEGER NOT NULL DEFAULT 0,
+                mac_version INTEGER NOT NULL DEFAULT 1,
+                vault_mac TEXT
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        // Create password entries table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS password_entries (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                username TEXT NOT NULL,
+                encrypted_password TEXT NOT NULL,
+                url TEXT,
+                encrypted_notes TEXT,
+                folder TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                last_used DATETIME,
+                is_favorite BOOLEAN NOT NULL DEFAULT FALSE,
+                encrypted_totp TEXT,
+                match_mode TEXT,
+                mac TEXT
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        // Prior versions of password entries, same columns plus version bookkeeping
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS password_history (
+                id TEXT PRIMARY KEY,
+                entry_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                username TEXT NOT NULL,
+                encrypted_password TEXT NOT NULL,
+                url TEXT,
+                encrypted_notes TEXT,
+                folder TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                last_used DATETIME,
+                is_favorite BOOLEAN NOT NULL DEFAULT FALSE,
+                encrypted_totp TEXT,
+                match_mode TEXT,
+                mac TEXT,
+                archived_at DATETIME NOT NULL,
+                UNIQUE (entry_id, version)
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        // Create secure notes table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS secure_notes (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                encrypted_content TEXT NOT NULL,
+                folder TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                mac TEXT
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        // Create indexes for better performance
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_entries_title ON password_entries(title)")
+            .execute(&self.pool)
+            .await?;
+            
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_entries_folder ON password_entries(folder)")
+            .execute(&self.pool)
+            .await?;
+        
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_secure_notes_folder ON secure_notes(folder)")
+            .execute(&self.pool)
+            .await?;
+        
+        // Team collections. Members hold the collection key wrapped to their
+        // public key; items are encrypted under the collection key.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS vault_identity (
+                id TEXT PRIMARY KEY,
+                public_key TEXT NOT NULL,
+                encrypted_private_key TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                mac TEXT
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS collections (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key_version INTEGER NOT NULL,
+                signed_by TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS collection_members (
+                collection_id TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                name TEXT NOT NULL,
+                wrapped_key TEXT NOT NULL,
+                PRIMARY KEY (collection_id, public_key)
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS collection_items (
+                collection_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                sealed TEXT NOT NULL,
+                updated_at DATETIME NOT NULL,
+                PRIMARY KEY (collection_id, id)
+            )
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+        
+        self.migrate_schema().await?;
+        
+        Ok(())
+    }
+    
+    // Bring vaults created by older builds up to `VAULT_SCHEMA_VERSION`
+    async fn migrate_schema(&self) -> Result<(), PasswordManagerError> {
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(&self.pool)
+            .await?;
+        
+        if version < 1 {
+            // Per-vault KDF salt and cost; NULL salt means the legacy fixed salt
+            for (column, column_type) in [
+                ("kdf_salt", "TEXT"),
+                ("kdf_memory_kib", "INTEGER"),
+                ("kdf_iterations", "INTEGER"),
+                ("kdf_parallelism", "INTEGER"),
+            ] {
+                self.add_column_if_missing("master_config", column, column_type).await?;
+            }
+        }
+        
+        if version < 2 {
+            // Data key wrapped by the password-derived key; NULL until first unlock
+            self.add_column_if_missing("master_config", "wrapped_data_key", "TEXT").await?;
+        }
+        
+        if version < 3 {
+            self.add_column_if_missing("password_entries", "encrypted_totp", "TEXT").await?;
+        }
+        
+        if version < 4 {
+            // Ciphertexts are bound to their row and rows MACed on the next unlock
+            self.add_column_if_missing("master_config", "ciphertexts_bound", "INTEGER NOT NULL DEFAULT 0").await?;
+            for table in VAULT_TABLES {
+                self.add_column_if_missing(table.name, "mac", "TEXT").await?;
+            }
+        }
+        
+        if version < 5 {
+            // NULL means the default match mode; rows are re-sealed on the next unlock
+            self.add_column_if_missing("password_entries", "match_mode", "TEXT").await?;
+            self.add_column_if_missing("password_history", "match_mode", "TEXT").await?;
+            self.add_column_if_missing("master_config", "mac_version", "INTEGER NOT NULL DEFAULT 1").await?;
+        }
+        
+        if version < 6 {
+            // MAC over the whole row set, written when the vault is next re-sealed
+            self.add_column_if_missing("master_config", "vault_mac", "TEXT").await?;
+        }
+        
+        if version < VAULT_SCHEMA_VERSION {
+            sqlx::query(&format!("PRAGMA user_version = {}", VAULT_SCHEMA_VERSION))
+                .execute(&self.pool)
+                .await?;
+        }
+        
+        Ok(())
+    }
+    
+    async fn add_column_if_missing(
+        &self,
+        table: &str,
+        column: &str,
+        column_type: &str,
+    ) -> Result<(), PasswordManagerError> {
+        let columns: Vec<String> = sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect();
+        
+        if !columns.iter().any(|c| c == column) {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, column_type))
+                .execute(&self.pool)
+                .await?;
+        }
+        
+        Ok(())
+    }
+}
+
+// Collect listed items, leaving out rows that fail their integrity check so
+// one tampered row doesn't hide the rest; `verify_vault` reports them
+fn skip_tampered<T>(
+    items: impl Iterator<Item = Result<T, PasswordManagerError>>,
+) -> Result<Vec<T>, PasswordManagerError> {
+    let mut verified = Vec::new();
+    for item in items {
+        match item {
+            Ok(item) => verified.push(item),
+            Err(PasswordManagerError::Tampered { table, id }) => {
+                log::warn!("Leaving out {} row {}, which failed its integrity check", table, id);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(verified)
+}
+
+fn associated_data(table: &VaultTable, id: &str, column: &str) -> Vec<u8> {
+    format!("{}:{}:{}", table.scope, id, column).into_bytes()
+}
+
+// Select whole rows plus every MACed column as text, exactly as stored, under
+// `<column>_text`
+fn authenticated_select(table: &VaultTable) -> String {
+    let columns: Vec<String> = table.columns
+        .iter()
+        .map(|column| format!("CAST({column} AS TEXT) AS {column}_text"))
+        .collect();
+    
+    format!(
+        "SELECT *, {} AS owner_id, {} FROM {}",
+        table.id_column, columns.join(", "), table.name
+    )
+}
+
+// Domain and columns a row MAC covered under layout `version`. Only the
+// current layout is written; older ones are kept to check rows before they
+// are re-sealed. Layouts before 3 shared the entry domain with history rows.
+fn mac_layout(table: &VaultTable, version: i64) -> (&'static str, impl Iterator<Item = &'static str> + '_) {
+    let domain = if version >= 3 { table.name } else { table.scope };
+    let columns = table.columns
+        .iter()
+        .copied()
+        .filter(move |column| version >= 3 || *column != "version")
+        .filter(move |column| version >= 2 || *column != "match_mode");
+    (domain, columns)
+}
+
+// HMAC over the domain, owning id and every column, each length-prefixed so
+// values can't bleed into their neighbours
+fn row_mac(mac_key: &hmac::Key, table: &VaultTable, version: i64, row: &SqliteRow) -> String {
+    let mut context = hmac::Context::with_key(mac_key);
+    let owner_id: String = row.get("owner_id");
+    let (domain, columns) = mac_layout(table, version);
+    
+    let values = [Some(domain.to_string()), Some(owner_id)]
+        .into_iter()
+        .chain(columns.map(|column| row.get::<Option<String>, _>(format!("{}_text", column).as_str())));
+    
+    for value in values {
+        update_length_prefixed(&mut context, value.as_deref());
+    }
+    
+    base64::encode(context.sign())
+}
+
+fn update_length_prefixed(context: &mut hmac::Context, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            context.update(&[1]);
+            context.update(&(value.len() as u64).to_be_bytes());
+            context.update(value.as_bytes());
+        }
+        None => context.update(&[0]),
+    }
+}
+
+// HMAC over each vault table's name, row count and the (id, MAC) of its rows
+// in id order, so rows that are deleted, added or swapped in are noticed even
+// though every remaining row verifies. Returns the MAC and the number of rows.
+async fn row_set_mac(
+    tx: &mut Transaction<'_, Sqlite>,
+    mac_key: &hmac::Key,
+) -> Result<(String, usize), PasswordManagerError> {
+    let mut context = hmac::Context::with_key(mac_key);
+    let mut total = 0;
+    
+    for table in VAULT_TABLES {
+        let rows = sqlx::query(&format!("SELECT id, mac FROM {} ORDER BY id", table.name))
+            .fetch_all(&mut **tx)
+            .await?;
+        
+        update_length_prefixed(&mut context, Some(table.name));
+        update_length_prefixed(&mut context, Some(&rows.len().to_string()));
+        for row in &rows {
+            update_length_prefixed(&mut context, Some(row.get::<&str, _>("id")));
+            update_length_prefixed(&mut context, row.get::<Option<&str>, _>("mac"));
+        }
+        total += rows.len();
+    }
+    
+    Ok((base64::encode(context.sign()), total))
+}
+
+fn mac_matches(mac_key: &hmac::Key, table: &VaultTable, version: i64, row: &SqliteRow) -> bool {
+    let stored_mac: Option<String> = row.get("mac");
+    stored_mac
+        .map(|mac| {
+            constant_time::verify_slices_are_equal(
+                mac.as_bytes(),
+                row_mac(mac_key, table, version, row).as_bytes(),
+            ).is_ok()
+        })
+        .unwrap_or(false)
+}
+
+// Tauri commands for frontend integration
+
+pub(crate) fn manager_from_state(state: &tauri::State<'_, AppState>) -> Result<PasswordManager, String> {
+    state.password_manager.lock().unwrap()
+        .clone()
+        .ok_or_else(|| "Password manager not initialized".to_string())
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    master_password: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let existing = state.password_manager.lock().unwrap().clone();
+    
+    let manager = match existing {
+        Some(manager) => manager,
+        None => {
+            let config = state.config.lock().unwrap().clone();
+            let settings = config.as_ref()
+                .map(|config| config.auto_lock_settings())
+                .unwrap_or_default();
+            let kdf_cost = config.as_ref()
+                .map(|config| config.vault.kdf)
+                .unwrap_or_default();
+            let history_retention = config.as_ref()
+                .map(|config| config.vault.history_retention)
+                .unwrap_or(DEFAULT_HISTORY_RETENTION);
+            
+            let manager = PasswordManager::open().await
+                .map_err(|e| format!("Failed to open password manager: {}", e))?
+                .with_kdf_cost(kdf_cost)
+                .with_history_retention(history_retention);
+
+            manager.spawn_auto_lock(settings, move |reason| {
+                use tauri::Manager;
+                if let Err(e) = app_handle.emit_all("vault-locked", reason) {
+                    log::warn!("Failed to emit vault-locked event: {}", e);
+                }
+            });
+            
+            *state.password_manager.lock().unwrap() = Some(manager.clone());
+            manager
+        }
+    };
+    
+    manager.unlock(&master_password).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    use tauri::Manager;
+    
+    if manager_from_state(&state)?.lock() {
+        app_handle.emit_all("vault-locked", LockReason::Manual)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_vault_status(state: tauri::State<'_, AppState>) -> Result<VaultStatus, String> {
+    match state.password_manager.lock().unwrap().as_ref() {
+        Some(manager) => Ok(manager.status()),
+        None => Ok(VaultStatus { locked: true, idle_seconds: 0 }),
+    }
+}
+
+#[tauri::command]
+pub async fn store_password_entry(
+    entry: PasswordEntry,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    manager_from_state(&state)?
+        .store_password(&entry).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_password_entry(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<PasswordEntry>, String> {
+    manager_from_state(&state)?
+        .get_password(&id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_password_entries(
+    folder: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PasswordEntry>, String> {
+    manager_from_state(&state)?
+        .list_passwords(folder.as_deref()).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_password_entries(
+    query: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PasswordEntry>, String> {
+    manager_from_state(&state)?
+        .search_passwords(&query).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_vault(
+    query: String,
+    limit: Option<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SearchHit>, String> {
+    manager_from_state(&state)?
+        .search_vault(&query, None, limit.unwrap_or(50))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_password_entry(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    manager_from_state(&state)?
+        .delete_password(&id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_entry_history(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<EntryVersion>, String> {
+    manager_from_state(&state)?
+        .get_entry_history(&id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_entry_version(
+    id: String,
+    version: i64,
+    state: tauri::State<'_, AppState>,
+) -> Result<PasswordEntry, String> {
+    manager_from_state(&state)?
+        .restore_entry_version(&id, version).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn generate_totp_code(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<TotpCode, String> {
+    manager_from_state(&state)?
+        .generate_totp(&id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn store_secure_note(
+    note: SecureNote,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    manager_from_state(&state)?
+        .store_note(&note).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_secure_note(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<SecureNote>, String> {
+    manager_from_state(&state)?
+        .get_note(&id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_secure_notes(
+    folder: Option<String>,
+    tag: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SecureNote>, String> {
+    manager_from_state(&state)?
+        .list_notes(folder.as_deref(), tag.as_deref()).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_secure_notes(
+    query: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SecureNote>, String> {
+    manager_from_state(&state)?
+        .search_notes(&query).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_secure_note(
+    id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    manager_from_state(&state)?
+        .delete_note(&id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn verify_vault(state: tauri::State<'_, AppState>) -> Result<IntegrityReport, String> {
+    manager_from_state(&state)?
+        .verify_vault().await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn change_master_password(
+    old_password: String,
+    new_password: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    PasswordPolicy::bundled().check(&new_password)?;
+    
+    manager_from_state(&state)?
+        .change_master_password(&old_password, &new_password).await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sample_entry(id: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: format!("Entry {}", id),
+            username: "alice".to_string(),
+            password: "correct horse battery staple".to_string(),
+            url: Some("https://example.com".to_string()),
+            notes: Some("recovery codes in the safe".to_string()),
+            folder: Some("Work".to_string()),
+            tags: vec!["shared".to_string()],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_used: None,
+            is_favorite: false,
+            totp_secret: None,
+            match_mode: MatchMode::default(),
+        }
+    }
+    
+    fn sample_note(id: &str, folder: &str, tags: &[&str]) -> SecureNote {
+        SecureNote {
+            id: id.to_string(),
+            title: format!("Note {}", id),
+            content: "recovery codes: 1234-5678".to_string(),
+            folder: Some(folder.to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_locked_vault_rejects_operations() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        assert!(manager.is_locked());
+        
+        let result = manager.store_password(&sample_entry("a")).await;
+        assert!(matches!(result, Err(PasswordManagerError::VaultLocked)));
+        assert!(matches!(manager.list_passwords(None).await, Err(PasswordManagerError::VaultLocked)));
+        assert!(matches!(manager.delete_password("a").await, Err(PasswordManagerError::VaultLocked)));
+        assert!(matches!(
+            manager.store_note(&sample_note("n", "Work", &[])).await,
+            Err(PasswordManagerError::VaultLocked)
+        ));
+        assert!(matches!(manager.list_notes(None, None).await, Err(PasswordManagerError::VaultLocked)));
+    }
+    
+    #[tokio::test]
+    async fn test_secure_notes_crud() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        manager.store_note(&sample_note("a", "Work", &["api", "aws"])).await.unwrap();
+        manager.store_note(&sample_note("b", "Work", &["recovery"])).await.unwrap();
+        manager.store_note(&sample_note("c", "Personal", &["recovery"])).await.unwrap();
+        
+        let note = manager.get_note("a").await.unwrap().unwrap();
+        assert_eq!(note.content, "recovery codes: 1234-5678");
+        assert_eq!(note.tags, vec!["api", "aws"]);
+        
+        let stored: String = sqlx::query_scalar("SELECT encrypted_content FROM secure_notes WHERE id = 'a'")
+            .fetch_one(&manager.pool)
+            .await
+            .unwrap();
+        assert!(!stored.contains("recovery"));
+        
+        assert_eq!(manager.list_notes(None, None).await.unwrap().len(), 3);
+        assert_eq!(manager.list_notes(Some("Work"), None).await.unwrap().len(), 2);
+        assert_eq!(manager.list_notes(None, Some("recovery")).await.unwrap().len(), 2);
+        
+        let filtered = manager.list_notes(Some("Work"), Some("recovery")).await.unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "b");
+        
+        let found = manager.search_notes("aws").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "a");
+        assert_eq!(manager.search_notes("1234").await.unwrap().len(), 3);
+        
+        assert!(manager.delete_note("a").await.unwrap());
+        assert!(!manager.delete_note("a").await.unwrap());
+        assert!(manager.get_note("a").await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_unlock_lock_cycle() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        
+        assert!(manager.lock());
+        assert!(!manager.lock());
+        assert!(matches!(manager.get_password("a").await, Err(PasswordManagerError::VaultLocked)));
+        
+        manager.unlock("master password").await.unwrap();
+        let entry = manager.get_password("a").await.unwrap().unwrap();
+        assert_eq!(entry.password, "correct horse battery staple");
+    }
+    
+    #[tokio::test]
+    async fn test_unlock_requires_set_up() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        assert!(matches!(
+            manager.unlock("anything").await,
+            Err(PasswordManagerError::VaultNotInitialized)
+        ));
+        assert!(manager.is_locked());
+        
+        manager.set_up("master password").await.unwrap();
+        manager.lock();
+        
+        // Setting up again doesn't replace the existing master password
+        assert!(matches!(
+            manager.set_up("other password").await,
+            Err(PasswordManagerError::InvalidMasterPassword)
+        ));
+        manager.unlock("master password").await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_unlock_with_wrong_password() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        manager.lock();
+        
+        let result = manager.unlock("wrong password").await;
+        assert!(matches!(result, Err(PasswordManagerError::InvalidMasterPassword)));
+        assert!(manager.is_locked());
+    }
+    
+    #[tokio::test]
+    async fn test_idle_lock() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        assert!(!manager.lock_if_idle(Duration::from_secs(60)));
+        assert!(manager.lock_if_idle(Duration::ZERO));
+        assert!(manager.is_locked());
+    }
+    
+    #[tokio::test]
+    async fn test_new_vault_gets_random_salt() {
+        let first = PasswordManager::open_in_memory().await.unwrap();
+        first.set_up("master password").await.unwrap();
+        let second = PasswordManager::open_in_memory().await.unwrap();
+        second.set_up("master password").await.unwrap();
+        
+        let first_params = first.load_kdf_params().await.unwrap();
+        let second_params = second.load_kdf_params().await.unwrap();
+        assert!(!first_params.is_legacy());
+        assert_eq!(first_params.salt.len(), 16);
+        assert_ne!(first_params.salt, second_params.salt);
+        assert_eq!(first_params.cost, first.kdf_cost);
+    }
+    
+    #[tokio::test]
+    async fn test_legacy_vault_is_upgraded_on_unlock() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        
+        // Recreate the layout older builds wrote: fixed salt, default Argon2
+        let hash = Argon2::default()
+            .hash_password(b"master password", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        sqlx::query("INSERT INTO master_config (id, master_password_hash) VALUES (1, ?)")
+            .bind(&hash)
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        
+        let legacy_key = PasswordManager::derive_master_key("master password", &KdfParams::legacy()).unwrap();
+        let legacy_cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&legacy_key));
+        let encrypted = PasswordManager::encrypt_with(&legacy_cipher, "hunter2", &[]).unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO password_entries (id, title, username, encrypted_password, tags, created_at, updated_at)
+            VALUES ('legacy', 'Legacy', 'bob', ?, '[]', ?, ?)
+            "#
+        )
+        .bind(&encrypted)
+        .bind(Utc::now())
+        .bind(Utc::now())
+        .execute(&manager.pool)
+        .await
+        .unwrap();
+        
+        manager.unlock("master password").await.unwrap();
+        
+        let params = manager.load_kdf_params().await.unwrap();
+        assert!(!params.is_legacy());
+        assert_eq!(params.cost, manager.kdf_cost);
+        
+        let entry = manager.get_password("legacy").await.unwrap().unwrap();
+        assert_eq!(entry.password, "hunter2");
+        
+        let stored: String = sqlx::query_scalar("SELECT encrypted_password FROM password_entries WHERE id = 'legacy'")
+            .fetch_one(&manager.pool)
+            .await
+            .unwrap();
+        assert!(PasswordManager::decrypt_with(&legacy_cipher, &stored, &[]).is_err());
+        assert!(manager.verify_vault().await.unwrap().is_intact());
+    }
+    
+    #[tokio::test]
+    async fn test_upgrade_kdf_raises_cost() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        
+        let stronger = KdfCost { memory_kib: 2048, iterations: 2, parallelism: 1 };
+        manager.upgrade_kdf("master password", stronger).await.unwrap();
+        assert_eq!(manager.load_kdf_params().await.unwrap().cost, stronger);
+        
+        let entry = manager.get_password("a").await.unwrap().unwrap();
+        assert_eq!(entry.password, "correct horse battery staple");
+        
+        manager.lock();
+        manager.unlock("master password").await.unwrap();
+        assert!(manager.get_password("a").await.unwrap().is_some());
+    }
+    
+    async fn stored_password_ciphertext(manager: &PasswordManager, id: &str) -> String {
+        sqlx::query_scalar("SELECT encrypted_password FROM password_entries WHERE id = ?")
+            .bind(id)
+            .fetch_one(&manager.pool)
+            .await
+            .unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_totp_secret_is_encrypted_and_generates_codes() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        let mut entry = sample_entry("a");
+        entry.totp_secret = Some("otpauth://totp/Example:alice?secret=GEZDGNBVGY3TQOJQ&issuer=Example".to_string());
+        manager.store_password(&entry).await.unwrap();
+        
+        let stored: String = sqlx::query_scalar("SELECT encrypted_totp FROM password_entries WHERE id = 'a'")
+            .fetch_one(&manager.pool)
+            .await
+            .unwrap();
+        assert!(!stored.contains("GEZDGNBV"));
+        
+        let code = manager.generate_totp("a").await.unwrap();
+        assert_eq!(code.code.len(), 6);
+        assert_eq!(code.period, 30);
+        
+        manager.store_password(&sample_entry("b")).await.unwrap();
+        assert!(matches!(
+            manager.generate_totp("b").await,
+            Err(PasswordManagerError::Totp(TotpError::NotConfigured))
+        ));
+        
+        entry.totp_secret = Some("not a secret!".to_string());
+        assert!(matches!(manager.store_password(&entry).await, Err(PasswordManagerError::Totp(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_search_index_follows_vault() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        let mut entry = sample_entry("a");
+        entry.url = Some("https://gitlab.example.com/users/sign_in".to_string());
+        manager.store_password(&entry).await.unwrap();
+        manager.store_password(&sample_entry("b")).await.unwrap();
+        
+        let found = manager.search_passwords("gitlab").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "a");
+        assert_eq!(manager.search_passwords("safe").await.unwrap().len(), 2);
+        
+        // Rebuilt from the database on the next unlock, gone while locked
+        manager.lock();
+        assert!(matches!(manager.search_vault("gitlab", None, 10), Err(PasswordManagerError::VaultLocked)));
+        manager.unlock("master password").await.unwrap();
+        assert_eq!(manager.search_vault("gitlab", None, 10).unwrap().len(), 1);
+        
+        manager.delete_password("a").await.unwrap();
+        assert!(manager.search_passwords("gitlab").await.unwrap().is_empty());
+        
+        // A write racing a rebuild still ends up in the new index
+        let mut racing = sample_entry("c");
+        racing.title = "Mailserver".to_string();
+        let (rebuilt, stored) = tokio::join!(manager.rebuild_search_index(), manager.store_password(&racing));
+        rebuilt.unwrap();
+        stored.unwrap();
+        assert_eq!(manager.search_passwords("mailserver").await.unwrap()[0].id, "c");
+        assert_eq!(manager.search_passwords("safe").await.unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_history_keeps_prior_versions() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        let mut entry = sample_entry("a");
+        manager.store_password(&entry).await.unwrap();
+        assert!(manager.get_entry_history("a").await.unwrap().is_empty());
+        
+        entry.last_used = Some(Utc::now());
+        manager.store_password(&entry).await.unwrap();
+        assert!(manager.get_entry_history("a").await.unwrap().is_empty());
+        
+        entry.password = "second".to_string();
+        manager.store_password(&entry).await.unwrap();
+        entry.password = "third".to_string();
+        manager.store_password(&entry).await.unwrap();
+        
+        let history = manager.get_entry_history("a").await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].version, 2);
+        assert_eq!(history[0].entry.password, "second");
+        assert_eq!(history[1].entry.password, "correct horse battery staple");
+        
+        let restored = manager.restore_entry_version("a", 1).await.unwrap();
+        assert_eq!(restored.password, "correct horse battery staple");
+        assert_eq!(manager.get_password("a").await.unwrap().unwrap().password, "correct horse battery staple");
+        assert_eq!(manager.get_entry_history("a").await.unwrap()[0].entry.password, "third");
+        
+        assert!(matches!(
+            manager.restore_entry_version("a", 99).await,
+            Err(PasswordManagerError::EntryNotFound)
+        ));
+        
+        manager.delete_password("a").await.unwrap();
+        assert!(manager.get_entry_history("a").await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_history_retention() {
+        let manager = PasswordManager::open_in_memory().await.unwrap().with_history_retention(2);
+        manager.set_up("master password").await.unwrap();
+        
+        let mut entry = sample_entry("a");
+        for i in 0..5 {
+            entry.password = format!("password {}", i);
+            manager.store_password(&entry).await.unwrap();
+        }
+        
+        let versions: Vec<i64> = manager.get_entry_history("a").await.unwrap()
+            .iter()
+            .map(|v| v.version)
+            .collect();
+        assert_eq!(versions, vec![4, 3]);
+        
+        // History ciphertexts follow the vault key
+        manager.rotate_data_key("master password").await.unwrap();
+        assert_eq!(manager.get_entry_history("a").await.unwrap()[0].entry.password, "password 3");
+    }
+    
+    #[tokio::test]
+    async fn test_change_master_password_rewraps_only() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("old password").await.unwrap();
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        let before = stored_password_ciphertext(&manager, "a").await;
+        
+        manager.change_master_password("old password", "new password").await.unwrap();
+        assert_eq!(stored_password_ciphertext(&manager, "a").await, before);
+        assert!(manager.get_password("a").await.unwrap().is_some());
+        
+        manager.lock();
+        assert!(matches!(
+            manager.unlock("old password").await,
+            Err(PasswordManagerError::InvalidMasterPassword)
+        ));
+        manager.unlock("new password").await.unwrap();
+        let entry = manager.get_password("a").await.unwrap().unwrap();
+        assert_eq!(entry.password, "correct horse battery staple");
+    }
+    
+    #[tokio::test]
+    async fn test_change_master_password_requires_old_password() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("old password").await.unwrap();
+        
+        let result = manager.change_master_password("not it", "new password").await;
+        assert!(matches!(result, Err(PasswordManagerError::InvalidMasterPassword)));
+        
+        manager.lock();
+        manager.unlock("old password").await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_ciphertexts_are_bound_to_their_row() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        manager.store_password(&sample_entry("b")).await.unwrap();
+        
+        // Copy a's password into b, and a's password into its own notes column
+        let a_password = stored_password_ciphertext(&manager, "a").await;
+        sqlx::query("UPDATE password_entries SET encrypted_password = ? WHERE id = 'b'")
+            .bind(&a_password)
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE password_entries SET encrypted_notes = encrypted_password WHERE id = 'a'")
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        
+        assert!(matches!(manager.get_password("a").await, Err(PasswordManagerError::Encryption(_))));
+        assert!(matches!(manager.get_password("b").await, Err(PasswordManagerError::Encryption(_))));
+        
+        let report = manager.verify_vault().await.unwrap();
+        let mut tampered: Vec<&str> = report.tampered.iter().map(|issue| issue.id.as_str()).collect();
+        tampered.sort();
+        assert_eq!(tampered, ["a", "b"]);
+    }
+    
+    #[tokio::test]
+    async fn test_verify_vault_reports_tampered_and_orphaned_rows() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        let mut b = sample_entry("b");
+        manager.store_password(&b).await.unwrap();
+        b.password = "new password".to_string();
+        manager.store_password(&b).await.unwrap();
+        manager.store_note(&sample_note("n", "Work", &[])).await.unwrap();
+        
+        let report = manager.verify_vault().await.unwrap();
+        assert!(report.is_intact(), "{:?}", report);
+        assert_eq!(report.rows_checked, 4);
+        
+        // Edit plaintext metadata and drop an entry behind the vault's back
+        for statement in [
+            "UPDATE password_entries SET url = 'https://evil.example' WHERE id = 'a'",
+            "UPDATE secure_notes SET folder = 'Personal' WHERE id = 'n'",
+            "DELETE FROM password_entries WHERE id = 'b'",
+        ] {
+            sqlx::query(statement).execute(&manager.pool).await.unwrap();
+        }
+        
+        let report = manager.verify_vault().await.unwrap();
+        let tampered: Vec<(&str, &str)> = report.tampered
+            .iter()
+            .map(|issue| (issue.table.as_str(), issue.id.as_str()))
+            .collect();
+        assert_eq!(tampered, [("password_entries", "a"), ("secure_notes", "n")]);
+        assert!(report.row_set_changed);
+        assert_eq!(report.orphaned.len(), 1);
+        assert_eq!(report.orphaned[0].table, "password_history");
+        
+        // Rotation would re-seal the tampered rows under the new key, so it is refused
+        assert!(matches!(
+            manager.rotate_data_key("master password").await,
+            Err(PasswordManagerError::IntegrityCheckFailed(report)) if report.tampered.len() == 2
+        ));
+        assert_eq!(manager.verify_vault().await.unwrap().tampered.len(), 2);
+        
+        // Once the damaged items are removed it goes ahead
+        manager.delete_password("a").await.unwrap();
+        manager.delete_password("b").await.unwrap();
+        manager.delete_note("n").await.unwrap();
+        manager.store_password(&sample_entry("c")).await.unwrap();
+        assert!(manager.verify_vault().await.unwrap().row_set_changed);
+        manager.rotate_data_key("master password").await.unwrap();
+        assert!(manager.verify_vault().await.unwrap().is_intact());
+        assert_eq!(manager.get_password("c").await.unwrap().unwrap().password, "correct horse battery staple");
+    }
+    
+    #[tokio::test]
+    async fn test_find_credentials_for_origin() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        let saved = [
+            ("site", "https://example.com", MatchMode::Domain),
+            ("login", "https://login.example.com/signin", MatchMode::Exact),
+            ("other-login", "https://login.example.com", MatchMode::Host),
+            ("hidden", "https://login.example.com", MatchMode::Never),
+            ("elsewhere", "https://example.org", MatchMode::Domain),
+        ];
+        for (id, url, match_mode) in saved {
+            let mut entry = sample_entry(id);
+            entry.url = Some(url.to_string());
+            entry.match_mode = match_mode;
+            manager.store_password(&entry).await.unwrap();
+        }
+        manager.mark_used("other-login").await.unwrap();
+        
+        let matches = manager.find_credentials_for_origin("https://Login.Example.com/account").await.unwrap();
+        let found: Vec<(&str, MatchStrength)> = matches
+            .iter()
+            .map(|(entry, strength)| (entry.id.as_str(), *strength))
+            .collect();
+        assert_eq!(found, [
+            ("other-login", MatchStrength::Exact),
+            ("login", MatchStrength::Exact),
+            ("site", MatchStrength::Domain),
+        ]);
+        
+        // Marking an entry used is bookkeeping, not a new version
+        assert!(matches[0].0.last_used.is_some());
+        assert!(manager.get_entry_history("other-login").await.unwrap().is_empty());
+        
+        assert!(manager.find_credentials_for_origin("http://example.com").await.unwrap().is_empty());
+        assert!(matches!(
+            manager.find_credentials_for_origin("file:///etc/passwd").await,
+            Err(PasswordManagerError::Autofill(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_rows_are_verified_on_read() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        let mut b = sample_entry("b");
+        manager.store_password(&b).await.unwrap();
+        b.password = "new password".to_string();
+        manager.store_password(&b).await.unwrap();
+        
+        // Roll b back to its archived version, MAC included
+        sqlx::query(
+            r#"
+            UPDATE password_entries
+            SET (encrypted_password, url, updated_at, mac) =
+                (SELECT encrypted_password, url, updated_at, mac FROM password_history WHERE entry_id = 'b')
+            WHERE id = 'b'
+            "#
+        )
+        .execute(&manager.pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE password_entries SET url = 'https://evil.example' WHERE id = 'a'")
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        
+        for id in ["a", "b"] {
+            assert!(matches!(
+                manager.get_password(id).await,
+                Err(PasswordManagerError::Tampered { ref table, id: ref found }) if table == "password_entries" && found == id
+            ));
+        }
+        assert!(manager.list_passwords(None).await.unwrap().is_empty());
+        assert_eq!(manager.get_entry_history("b").await.unwrap().len(), 1);
+        
+        // Overwriting a tampered entry repairs it without archiving it
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        assert_eq!(manager.list_passwords(None).await.unwrap().len(), 1);
+        assert!(manager.get_entry_history("a").await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_outdated_row_macs_are_resealed_on_unlock() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        
+        manager.store_password(&sample_entry("b")).await.unwrap();
+        
+        // As left by a build that MACed fewer columns
+        sqlx::query("UPDATE password_entries SET match_mode = NULL")
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        let mac_key = manager.mac_key().unwrap();
+        let rows = sqlx::query(&authenticated_select(&ENTRIES)).fetch_all(&manager.pool).await.unwrap();
+        for row in &rows {
+            sqlx::query("UPDATE password_entries SET mac = ? WHERE id = ?")
+                .bind(row_mac(&mac_key, &ENTRIES, 1, row))
+                .bind(row.get::<String, _>("id"))
+                .execute(&manager.pool)
+                .await
+                .unwrap();
+        }
+        // ...and then edited behind the vault's back
+        sqlx::query("UPDATE password_entries SET url = 'https://evil.example' WHERE id = 'b'")
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE master_config SET mac_version = 1")
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        assert!(!manager.verify_vault().await.unwrap().is_intact());
+        
+        // Only rows that verify under the old layout are re-sealed
+        manager.lock();
+        manager.unlock("master password").await.unwrap();
+        let report = manager.verify_vault().await.unwrap();
+        let tampered: Vec<&str> = report.tampered.iter().map(|issue| issue.id.as_str()).collect();
+        assert_eq!(tampered, ["b"]);
+        assert_eq!(manager.get_password("a").await.unwrap().unwrap().match_mode, MatchMode::Domain);
+        
+        // A forged version number can't get current MACs re-sealed either
+        sqlx::query("UPDATE master_config SET mac_version = 1")
+            .execute(&manager.pool)
+            .await
+            .unwrap();
+        manager.lock();
+        manager.unlock("master password").await.unwrap();
+        let report = manager.verify_vault().await.unwrap();
+        assert_eq!(report.tampered.len(), 1);
+        assert_eq!(report.rows_checked, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_team_collection_sync_between_vaults() {
+        let alice = PasswordManager::open_in_memory().await.unwrap();
+        let bob = PasswordManager::open_in_memory().await.unwrap();
+        alice.set_up("alice password").await.unwrap();
+        bob.set_up("bob password").await.unwrap();
+        
+        let collection = alice.create_collection("Ops", "alice").await.unwrap();
+        let bob_key = bob.identity_public_key().await.unwrap();
+        alice.add_collection_member(&collection.id, "bob", &bob_key).await.unwrap();
+        alice.store_collection_item(&collection.id, &sample_entry("db")).await.unwrap();
+        
+        // Bob picks up the collection and adds an item of his own
+        bob.import_collection(&alice.export_collection(&collection.id).await.unwrap()).await.unwrap();
+        assert_eq!(bob.list_collection_items(&collection.id).await.unwrap().len(), 1);
+        bob.store_collection_item(&collection.id, &sample_entry("vpn")).await.unwrap();
+        
+        alice.delete_collection_item(&collection.id, "db").await.unwrap();
+        alice.import_collection(&bob.export_collection(&collection.id).await.unwrap()).await.unwrap();
+        let items = alice.list_collection_items(&collection.id).await.unwrap();
+        assert_eq!(items.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["vpn"]);
+        
+        // Alice's identity survives a data key rotation
+        alice.rotate_data_key("alice password").await.unwrap();
+        assert_eq!(alice.list_collection_items(&collection.id).await.unwrap().len(), 1);
+        assert!(alice.verify_vault().await.unwrap().is_intact());
+        
+        // After removal Bob can't open new bundles, and his old one is ignored
+        let rotated = alice.remove_collection_member(&collection.id, &bob_key).await.unwrap();
+        assert_eq!(rotated.key_version, 2);
+        assert_eq!(rotated.members.len(), 1);
+        
+        let result = bob.import_collection(&alice.export_collection(&collection.id).await.unwrap()).await;
+        assert!(matches!(result, Err(PasswordManagerError::TeamVault(TeamVaultError::NotMember(_)))));
+        
+        bob.store_collection_item(&collection.id, &sample_entry("injected")).await.unwrap();
+        alice.import_collection(&bob.export_collection(&collection.id).await.unwrap()).await.unwrap();
+        assert_eq!(alice.list_collection_items(&collection.id).await.unwrap().len(), 1);
+        
+        // Nor can he forge a newer roster under a key of his own choosing
+        let bob_identity = bob.identity().await.unwrap();
+        let alice_key = alice.identity_public_key().await.unwrap();
+        let forged_key = team_vault::generate_collection_key();
+        let mut forged = bob.export_collection(&collection.id).await.unwrap();
+        forged.key_version = rotated.key_version + 1;
+        forged.updated_at = Utc::now();
+        forged.members = vec![
+            WrappedMember::new(&forged_key, &collection.id, "alice", &alice_key).unwrap(),
+            WrappedMember::new(&forged_key, &collection.id, "bob", &bob_key).unwrap(),
+        ];
+        forged.items = vec![
+            team_vault::seal_item(&forged_key, &collection.id, "injected", Utc::now(), Some(&sample_entry("injected"))).unwrap(),
+        ];
+        forged.sign_roster(&bob_identity);
+        
+        let result = alice.import_collection(&forged).await;
+        assert!(matches!(result, Err(PasswordManagerError::TeamVault(TeamVaultError::UntrustedSigner(_)))));
+        let collection = alice.list_collections().await.unwrap().remove(0);
+        assert_eq!((collection.key_version, collection.members.len()), (2, 1));
+        assert_eq!(alice.list_collection_items(&collection.id).await.unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_rotate_data_key_reencrypts_entries() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        manager.store_password(&sample_entry("a")).await.unwrap();
+        let before = stored_password_ciphertext(&manager, "a").await;
+        
+        manager.rotate_data_key("master password").await.unwrap();
+        assert_ne!(stored_password_ciphertext(&manager, "a").await, before);
+        
+        let entry = manager.get_password("a").await.unwrap().unwrap();
+        assert_eq!(entry.password, "correct horse battery staple");
+        
+        manager.lock();
+        manager.unlock("master password").await.unwrap();
+        assert!(manager.get_password("a").await.unwrap().is_some());
+    }
+    
+    #[test]
+    fn test_sleep_detection() {
+        let interval = Duration::from_secs(15);
+        let start = (Instant::now(), SystemTime::now());
+        
+        let awake = (start.0 + interval, start.1 + interval);
+        assert!(!PasswordManager::detect_sleep(start, awake, interval));
+        
+        let resumed = (start.0 + interval, start.1 + Duration::from_secs(3600));
+        assert!(PasswordManager::detect_sleep(start, resumed, interval));
+    }
+}
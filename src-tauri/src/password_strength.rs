@@ -0,0 +1,540 @@
+// src-tauri/src/password_strength.rs
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use crate::password_manager::{manager_from_state, PasswordEntry};
+use crate::AppState;
+
+/// Bundled copy of the marketplace security policies (JSON with `//` comments)
+const SECURITY_POLICIES: &str = include_str!("../../config/security-policies.json");
+
+/// Entries scoring below this are reported as weak
+pub const WEAK_SCORE: u8 = 2;
+
+/// Stored passwords seen most often in breach corpora. Matched case-insensitively
+/// after trailing digits and symbols are stripped.
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "passw0rd", "qwerty", "letmein", "welcome", "admin", "administrator",
+    "iloveyou", "monkey", "dragon", "master", "sunshine", "princess", "football",
+    "baseball", "shadow", "superman", "trustno", "login", "abc", "starwars",
+    "whatever", "freedom", "hello", "secret", "changeme", "default", "root", "test",
+];
+
+const KEYBOARD_ROWS: &[&str] = &["qwertyuiop", "asdfghjkl", "zxcvbnm", "1234567890"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordStrength {
+    /// 0 (trivially guessable) to 4 (very strong)
+    pub score: u8,
+    /// Estimated entropy after pattern penalties
+    pub entropy_bits: f64,
+    pub feedback: Vec<String>,
+}
+
+/// Estimate how hard `password` is to guess. Starts from the character-pool
+/// entropy and discounts repeats, sequences, keyboard walks, years and common
+/// passwords, which attackers try first.
+pub fn analyze(password: &str) -> PasswordStrength {
+    let chars: Vec<char> = password.chars().collect();
+    let mut feedback = Vec::new();
+
+    if chars.is_empty() {
+        return PasswordStrength { score: 0, entropy_bits: 0.0, feedback: vec!["Password is empty".to_string()] };
+    }
+
+    let bits_per_char = (pool_size(&chars) as f64).log2();
+
+    // Characters that are predictable from the one before them
+    let mut predictable = vec![false; chars.len()];
+
+    let repeats = mark_runs(&chars, &mut predictable, |a, b| a == b);
+    if repeats {
+        feedback.push("Avoid repeated characters".to_string());
+    }
+
+    let ascending = mark_runs(&chars, &mut predictable, |a, b| b as u32 == a as u32 + 1);
+    let descending = mark_runs(&chars, &mut predictable, |a, b| a as u32 == b as u32 + 1);
+    if ascending || descending {
+        feedback.push("Avoid sequences like abc or 123".to_string());
+    }
+
+    if mark_keyboard_walks(&chars, &mut predictable) {
+        feedback.push("Avoid keyboard patterns like qwerty".to_string());
+    }
+
+    if mark_years(&chars, &mut predictable) {
+        feedback.push("Avoid years and dates".to_string());
+    }
+
+    let random_chars = predictable.iter().filter(|p| !**p).count() as f64;
+    let predictable_chars = predictable.iter().filter(|p| **p).count() as f64;
+    let mut entropy_bits = random_chars * bits_per_char + predictable_chars;
+
+    let base: String = password
+        .trim_end_matches(|c: char| !c.is_alphabetic())
+        .to_lowercase();
+    if COMMON_PASSWORDS.contains(&base.as_str()) {
+        entropy_bits = entropy_bits.min(10.0);
+        feedback.push("This is a commonly used password".to_string());
+    }
+
+    let score = match entropy_bits {
+        bits if bits < 28.0 => 0,
+        bits if bits < 36.0 => 1,
+        bits if bits < 60.0 => 2,
+        bits if bits < 80.0 => 3,
+        _ => 4,
+    };
+
+    if score < WEAK_SCORE && chars.len() < 12 {
+        feedback.push("Use at least 12 characters".to_string());
+    }
+
+    PasswordStrength { score, entropy_bits, feedback }
+}
+
+fn pool_size(chars: &[char]) -> u32 {
+    let mut pool = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    pool.max(2)
+}
+
+// Mark every character after the first in runs of three or more where `step`
+// holds between neighbours. Returns whether any run was found.
+fn mark_runs(chars: &[char], predictable: &mut [bool], step: impl Fn(char, char) -> bool) -> bool {
+    let mut found = false;
+    let mut start = 0;
+
+    for i in 1..=chars.len() {
+        let continues = i < chars.len() && step(chars[i - 1].to_ascii_lowercase(), chars[i].to_ascii_lowercase());
+        if !continues {
+            if i - start >= 3 {
+                predictable[start + 1..i].iter_mut().for_each(|p| *p = true);
+                found = true;
+            }
+            start = i;
+        }
+    }
+
+    found
+}
+
+fn mark_keyboard_walks(chars: &[char], predictable: &mut [bool]) -> bool {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let mut found = false;
+
+    for start in 0..lower.len() {
+        let mut end = start;
+        while end + 1 < lower.len() && KEYBOARD_ROWS.iter().any(|row| {
+            let pair: String = [lower[end], lower[end + 1]].iter().collect();
+            row.contains(&pair)
+        }) {
+            end += 1;
+        }
+
+        if end - start + 1 >= 4 {
+            predictable[start + 1..=end].iter_mut().for_each(|p| *p = true);
+            found = true;
+        }
+    }
+
+    found
+}
+
+fn mark_years(chars: &[char], predictable: &mut [bool]) -> bool {
+    let mut found = false;
+
+    for start in 0..chars.len().saturating_sub(3) {
+        let window: String = chars[start..start + 4].iter().collect();
+        if let Ok(year) = window.parse::<u32>() {
+            if (1900..=2099).contains(&year) {
+                // A year is one of ~200 likely values, about two characters' worth
+                predictable[start + 2..start + 4].iter_mut().for_each(|p| *p = true);
+                found = true;
+            }
+        }
+    }
+
+    found
+}
+
+/// `passwordRequirements` from `config/security-policies.json`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_numbers: bool,
+    pub require_symbols: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyViolation {
+    TooShort { min_length: usize },
+    MissingUppercase,
+    MissingLowercase,
+    MissingNumber,
+    MissingSymbol,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::TooShort { min_length } => write!(f, "must be at least {} characters", min_length),
+            PolicyViolation::MissingUppercase => write!(f, "must contain an uppercase letter"),
+            PolicyViolation::MissingLowercase => write!(f, "must contain a lowercase letter"),
+            PolicyViolation::MissingNumber => write!(f, "must contain a number"),
+            PolicyViolation::MissingSymbol => write!(f, "must contain a symbol"),
+        }
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 12,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_numbers: true,
+            require_symbols: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Policy bundled with the app. Falls back to the defaults if the file
+    /// cannot be read, so a bad edit never disables the checks.
+    pub fn bundled() -> Self {
+        Self::from_policies_json(SECURITY_POLICIES).unwrap_or_else(|e| {
+            log::warn!("Invalid password requirements in security-policies.json: {}", e);
+            Self::default()
+        })
+    }
+
+    pub fn from_policies_json(content: &str) -> Result<Self, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(&strip_json_comments(content))?;
+        serde_json::from_value(value["policies"]["authentication"]["passwordRequirements"].clone())
+    }
+
+    pub fn validate(&self, password: &str) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(PolicyViolation::TooShort { min_length: self.min_length });
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push(PolicyViolation::MissingUppercase);
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push(PolicyViolation::MissingLowercase);
+        }
+        if self.require_numbers && !password.chars().any(|c| c.is_numeric()) {
+            violations.push(PolicyViolation::MissingNumber);
+        }
+        if self.require_symbols && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push(PolicyViolation::MissingSymbol);
+        }
+
+        violations
+    }
+
+    /// `validate` as a single user-facing error message
+    pub fn check(&self, password: &str) -> Result<(), String> {
+        let violations = self.validate(password);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        Err(format!("Password {}", reasons.join(", ")))
+    }
+}
+
+// Drop `//` line comments that sit outside of string literals
+fn strip_json_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        output.push(escaped);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '/' && chars.peek() == Some(&'/') {
+            while chars.peek().map(|next| *next != '\n').unwrap_or(false) {
+                chars.next();
+            }
+        } else {
+            if c == '"' {
+                in_string = true;
+            }
+            output.push(c);
+        }
+    }
+
+    output
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntrySummary {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeakEntry {
+    pub id: String,
+    pub title: String,
+    pub score: u8,
+    pub violations: Vec<PolicyViolation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleEntry {
+    pub id: String,
+    pub title: String,
+    pub days_since_update: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsecureUrlEntry {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultHealthReport {
+    pub generated_at: DateTime<Utc>,
+    pub total_entries: usize,
+    /// Groups of entries sharing the same password
+    pub reused: Vec<Vec<EntrySummary>>,
+    pub weak: Vec<WeakEntry>,
+    pub stale: Vec<StaleEntry>,
+    pub insecure_urls: Vec<InsecureUrlEntry>,
+}
+
+pub fn health_report(
+    entries: &[PasswordEntry],
+    policy: &PasswordPolicy,
+    stale_after_days: u32,
+    now: DateTime<Utc>,
+) -> VaultHealthReport {
+    // Group by digest so the map never holds another copy of each password
+    let mut by_password: HashMap<Vec<u8>, Vec<EntrySummary>> = HashMap::new();
+    let mut weak = Vec::new();
+    let mut stale = Vec::new();
+    let mut insecure_urls = Vec::new();
+
+    for entry in entries {
+        let summary = EntrySummary { id: entry.id.clone(), title: entry.title.clone() };
+
+        if !entry.password.is_empty() {
+            let hash = digest::digest(&digest::SHA256, entry.password.as_bytes());
+            by_password.entry(hash.as_ref().to_vec()).or_default().push(summary);
+
+            let strength = analyze(&entry.password);
+            let violations = policy.validate(&entry.password);
+            if strength.score < WEAK_SCORE || !violations.is_empty() {
+                weak.push(WeakEntry {
+                    id: entry.id.clone(),
+                    title: entry.title.clone(),
+                    score: strength.score,
+                    violations,
+                });
+            }
+        }
+
+        let days_since_update = (now - entry.updated_at).num_days();
+        if stale_after_days > 0 && days_since_update >= stale_after_days as i64 {
+            stale.push(StaleEntry { id: entry.id.clone(), title: entry.title.clone(), days_since_update });
+        }
+
+        if let Some(url) = &entry.url {
+            if url.trim().to_ascii_lowercase().starts_with("http://") {
+                insecure_urls.push(InsecureUrlEntry {
+                    id: entry.id.clone(),
+                    title: entry.title.clone(),
+                    url: url.clone(),
+                });
+            }
+        }
+    }
+
+    let mut reused: Vec<Vec<EntrySummary>> = by_password
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    reused.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].title.cmp(&b[0].title)));
+    stale.sort_by_key(|s| std::cmp::Reverse(s.days_since_update));
+
+    VaultHealthReport {
+        generated_at: now,
+        total_entries: entries.len(),
+        reused,
+        weak,
+        stale,
+        insecure_urls,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordCheck {
+    pub strength: PasswordStrength,
+    pub violations: Vec<PolicyViolation>,
+}
+
+#[tauri::command]
+pub fn check_password_strength(password: String) -> PasswordCheck {
+    PasswordCheck {
+        strength: analyze(&password),
+        violations: PasswordPolicy::bundled().validate(&password),
+    }
+}
+
+#[tauri::command]
+pub async fn get_vault_health_report(
+    stale_after_days: Option<u32>,
+    state: tauri::State<'_, AppState>,
+) -> Result<VaultHealthReport, String> {
+    let manager = manager_from_state(&state)?;
+    let stale_after_days = stale_after_days.unwrap_or_else(|| {
+        state.config.lock().unwrap()
+            .as_ref()
+            .map(|config| config.vault.stale_after_days)
+            .unwrap_or_default()
+    });
+
+    let entries = manager.list_passwords(None).await.map_err(|e| e.to_string())?;
+    Ok(health_report(&entries, &PasswordPolicy::bundled(), stale_after_days, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::autofill::MatchMode;
+
+    fn entry(id: &str, password: &str, url: &str, age_days: i64) -> PasswordEntry {
+        let updated = Utc::now() - Duration::days(age_days);
+        PasswordEntry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "alice".to_string(),
+            password: password.to_string(),
+            url: Some(url.to_string()),
+            notes: None,
+            folder: None,
+            tags: Vec::new(),
+            created_at: updated,
+            updated_at: updated,
+            last_used: None,
+            is_favorite: false,
+            totp_secret: None,
+            match_mode: MatchMode::default(),
+        }
+    }
+
+    #[test]
+    fn test_scores_rank_passwords() {
+        assert_eq!(analyze("").score, 0);
+        assert_eq!(analyze("password123").score, 0);
+        assert_eq!(analyze("Password1!").score, 0);
+        assert!(analyze("qwertyuiop").score <= 1);
+        assert!(analyze("aaaaaaaaaaaaaaaa").score <= 1);
+        assert!(analyze("R7#kq!Lm2@vZ9$wX").score >= 3);
+        assert!(analyze("R7#kq!Lm2@vZ9$wX").entropy_bits > analyze("summer2024").entropy_bits);
+    }
+
+    #[test]
+    fn test_patterns_reduce_entropy() {
+        let random = analyze("xkfqzmvt");
+        assert!(analyze("abcdefgh").entropy_bits < random.entropy_bits);
+        assert!(analyze("asdfghjk").entropy_bits < random.entropy_bits);
+        assert!(analyze("xkfq1987").entropy_bits < analyze("xkfq5831").entropy_bits);
+
+        let strength = analyze("abcdef");
+        assert!(strength.feedback.iter().any(|f| f.contains("sequences")));
+    }
+
+    #[test]
+    fn test_bundled_policy_matches_config() {
+        let policy = PasswordPolicy::from_policies_json(SECURITY_POLICIES).unwrap();
+        assert_eq!(policy, PasswordPolicy::default());
+    }
+
+    #[test]
+    fn test_comment_stripping_keeps_urls() {
+        let stripped = strip_json_comments("{\"url\": \"https://x.test\" // trailing\n}");
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value["url"], "https://x.test");
+    }
+
+    #[test]
+    fn test_policy_violations() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.validate("Str0ng&LongEnough").is_empty());
+        assert_eq!(
+            policy.validate("short"),
+            vec![
+                PolicyViolation::TooShort { min_length: 12 },
+                PolicyViolation::MissingUppercase,
+                PolicyViolation::MissingNumber,
+                PolicyViolation::MissingSymbol,
+            ]
+        );
+        assert!(policy.check("alllowercaseletters1!").unwrap_err().contains("uppercase"));
+    }
+
+    #[test]
+    fn test_health_report() {
+        let entries = vec![
+            entry("a", "Sh4red&Secret!x", "https://a.example.com", 10),
+            entry("b", "Sh4red&Secret!x", "http://b.example.com", 10),
+            entry("c", "password", "https://c.example.com", 400),
+            entry("d", "Un1que&Strong#Pw", "https://d.example.com", 30),
+        ];
+
+        let report = health_report(&entries, &PasswordPolicy::default(), 365, Utc::now());
+        assert_eq!(report.total_entries, 4);
+
+        assert_eq!(report.reused.len(), 1);
+        let mut reused: Vec<&str> = report.reused[0].iter().map(|e| e.id.as_str()).collect();
+        reused.sort();
+        assert_eq!(reused, vec!["a", "b"]);
+
+        assert_eq!(report.weak.len(), 1);
+        assert_eq!(report.weak[0].id, "c");
+
+        assert_eq!(report.stale.len(), 1);
+        assert_eq!(report.stale[0].id, "c");
+
+        assert_eq!(report.insecure_urls.len(), 1);
+        assert_eq!(report.insecure_urls[0].id, "b");
+    }
+}
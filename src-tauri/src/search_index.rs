@@ -0,0 +1,272 @@
+// src-tauri/src/search_index.rs
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use zeroize::Zeroizing;
+use crate::password_manager::{PasswordEntry, SecureNote};
+use crate::vault_transfer::url_host;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    Entry,
+    Note,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: ItemKind,
+    pub id: String,
+    pub title: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Title,
+    Username,
+    Host,
+    Tag,
+    Folder,
+    Notes,
+}
+
+impl Field {
+    fn weight(self) -> f32 {
+        match self {
+            Field::Title => 3.0,
+            Field::Username | Field::Host => 2.0,
+            Field::Tag => 1.5,
+            Field::Folder | Field::Notes => 1.0,
+        }
+    }
+}
+
+struct IndexedItem {
+    title: String,
+    tokens: Vec<(Field, Zeroizing<String>)>,
+}
+
+/// Decrypted, tokenised view of the vault used for fuzzy search. It only
+/// exists while the vault is unlocked; every token is zeroized when dropped.
+#[derive(Default)]
+pub struct SearchIndex {
+    items: HashMap<(ItemKind, String), IndexedItem>,
+}
+
+impl SearchIndex {
+    pub fn upsert_entry(&mut self, entry: &PasswordEntry) {
+        let mut tokens = Vec::new();
+        push_tokens(&mut tokens, Field::Title, &entry.title);
+        push_tokens(&mut tokens, Field::Username, &entry.username);
+        if let Some(host) = entry.url.as_deref().and_then(url_host) {
+            push_tokens(&mut tokens, Field::Host, &host);
+        }
+        for tag in &entry.tags {
+            push_tokens(&mut tokens, Field::Tag, tag);
+        }
+        if let Some(folder) = &entry.folder {
+            push_tokens(&mut tokens, Field::Folder, folder);
+        }
+        if let Some(notes) = &entry.notes {
+            push_tokens(&mut tokens, Field::Notes, notes);
+        }
+
+        self.items.insert(
+            (ItemKind::Entry, entry.id.clone()),
+            IndexedItem { title: entry.title.clone(), tokens },
+        );
+    }
+
+    pub fn upsert_note(&mut self, note: &SecureNote) {
+        let mut tokens = Vec::new();
+        push_tokens(&mut tokens, Field::Title, &note.title);
+        for tag in &note.tags {
+            push_tokens(&mut tokens, Field::Tag, tag);
+        }
+        if let Some(folder) = &note.folder {
+            push_tokens(&mut tokens, Field::Folder, folder);
+        }
+        push_tokens(&mut tokens, Field::Notes, &note.content);
+
+        self.items.insert(
+            (ItemKind::Note, note.id.clone()),
+            IndexedItem { title: note.title.clone(), tokens },
+        );
+    }
+
+    pub fn remove(&mut self, kind: ItemKind, id: &str) {
+        self.items.remove(&(kind, id.to_string()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Items matching every word of `query`, best first. Words match exactly,
+    /// by prefix, as a substring, or within a small edit distance.
+    pub fn search(&self, query: &str, kind: Option<ItemKind>, limit: usize) -> Vec<SearchHit> {
+        let terms: Vec<String> = tokenize(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<SearchHit> = self.items
+            .iter()
+            .filter(|((item_kind, _), _)| kind.map(|k| k == *item_kind).unwrap_or(true))
+            .filter_map(|((item_kind, id), item)| {
+                let mut score = 0.0;
+                for term in &terms {
+                    score += item.tokens
+                        .iter()
+                        .filter_map(|(field, token)| match_quality(term, token).map(|q| q * field.weight()))
+                        .reduce(f32::max)?;
+                }
+
+                Some(SearchHit { kind: *item_kind, id: id.clone(), title: item.title.clone(), score })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.title.cmp(&b.title))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+fn push_tokens(tokens: &mut Vec<(Field, Zeroizing<String>)>, field: Field, text: &str) {
+    tokens.extend(tokenize(text).map(|token| (field, Zeroizing::new(token))));
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+fn match_quality(term: &str, token: &str) -> Option<f32> {
+    if token == term {
+        Some(1.0)
+    } else if token.starts_with(term) {
+        Some(0.8)
+    } else if term.chars().count() >= 3 && token.contains(term) {
+        Some(0.6)
+    } else {
+        let max_edits = match term.chars().count() {
+            0..=3 => return None,
+            4..=7 => 1,
+            _ => 2,
+        };
+        within_edit_distance(term, token, max_edits).then_some(0.4)
+    }
+}
+
+// Levenshtein distance with an early exit once every cell exceeds `max`
+fn within_edit_distance(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|d| *d > max) {
+            return false;
+        }
+        previous = current;
+    }
+
+    previous[b.len()] <= max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::autofill::MatchMode;
+
+    fn entry(id: &str, title: &str, username: &str, url: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: title.to_string(),
+            username: username.to_string(),
+            password: "secret".to_string(),
+            url: Some(url.to_string()),
+            notes: None,
+            folder: Some("Work".to_string()),
+            tags: vec!["infra".to_string()],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_used: None,
+            is_favorite: false,
+            totp_secret: None,
+            match_mode: MatchMode::default(),
+        }
+    }
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::default();
+        index.upsert_entry(&entry("gh", "GitHub", "alice", "https://github.com/login"));
+        index.upsert_entry(&entry("gl", "GitLab staging", "deploy-bot", "https://gitlab.example.com"));
+        index.upsert_note(&SecureNote {
+            id: "n1".to_string(),
+            title: "Recovery codes".to_string(),
+            content: "GitHub backup codes: 1234-5678".to_string(),
+            folder: None,
+            tags: vec!["2fa".to_string()],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        });
+        index
+    }
+
+    #[test]
+    fn test_matches_fields_and_notes() {
+        let index = index();
+
+        let hits = index.search("github", None, 10);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, "gh", "title match should outrank note content");
+
+        assert_eq!(index.search("deploy", None, 10)[0].id, "gl");
+        assert_eq!(index.search("example", None, 10)[0].id, "gl");
+        assert_eq!(index.search("backup", None, 10)[0].id, "n1");
+        assert_eq!(index.search("github", Some(ItemKind::Note), 10).len(), 1);
+    }
+
+    #[test]
+    fn test_fuzzy_and_multi_word() {
+        let index = index();
+
+        assert_eq!(index.search("gitlap", None, 10)[0].id, "gl");
+        assert_eq!(index.search("git stag", None, 10).len(), 1);
+        assert!(index.search("zzzz", None, 10).is_empty());
+        assert!(index.search("  ", None, 10).is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut index = index();
+        index.remove(ItemKind::Entry, "gh");
+        assert_eq!(index.len(), 2);
+        assert!(index.search("alice", None, 10).is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert!(within_edit_distance("kitten", "sitten", 1));
+        assert!(!within_edit_distance("kitten", "sitting", 2));
+        assert!(within_edit_distance("kitten", "sitting", 3));
+    }
+}
@@ -0,0 +1,748 @@
+// src-tauri/src/service_spec.rs
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use crate::image_trust::{check_digest_format, decode_signature};
+
+/// Stack used unless the user provides their own `services.toml`
+const BUNDLED_SERVICES: &str = include_str!("../resources/services.toml");
+
+#[derive(Error, Debug)]
+pub enum ServiceSpecError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid services file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Service {service}: {message}")]
+    Invalid { service: String, message: String },
+    #[error("Services depend on each other in a cycle: {0}")]
+    DependencyCycle(String),
+}
+
+fn invalid(service: &str, message: impl Into<String>) -> ServiceSpecError {
+    ServiceSpecError::Invalid {
+        service: service.to_string(),
+        message: message.into(),
+    }
+}
+
+/// The marketplace services, keyed by container name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStack {
+    pub services: BTreeMap<String, ServiceSpec>,
+}
+
+/// One service, as in a docker-compose file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceSpec {
+    pub image: String,
+    /// `sha256:...` the image must have; the container isn't started otherwise
+    #[serde(default)]
+    pub digest: Option<String>,
+    /// Base64 Ed25519 signature over `<repository>@<digest>` by a bundled signing key
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub environment: BTreeMap<String, String>,
+    /// `[host_ip:][host_port:]container_port[/protocol]`
+    #[serde(default)]
+    pub ports: Vec<String>,
+    /// `source:target[:ro|:rw]`
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub healthcheck: Option<Healthcheck>,
+    /// Checked from the host when the container has no HEALTHCHECK of its own
+    #[serde(default)]
+    pub probe: Option<Probe>,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub security: Security,
+}
+
+/// Resources a service may use. Services that don't set them get the defaults,
+/// so no container runs unbounded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    /// Memory including swap, e.g. `512m` or `1g`
+    #[serde(default = "default_memory")]
+    pub memory: String,
+    /// CPUs the service may use, e.g. `0.5`
+    #[serde(default = "default_cpus")]
+    pub cpus: f64,
+    #[serde(default = "default_pids_limit")]
+    pub pids_limit: i64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            memory: default_memory(),
+            cpus: default_cpus(),
+            pids_limit: default_pids_limit(),
+        }
+    }
+}
+
+impl Limits {
+    pub fn memory_bytes(&self) -> Result<i64, String> {
+        parse_size(&self.memory)
+    }
+
+    /// `cpus` in the billionths Docker's `NanoCpus` takes
+    pub fn nano_cpus(&self) -> i64 {
+        (self.cpus * 1e9) as i64
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.memory_bytes()? < MIN_MEMORY {
+            return Err(format!("memory limit {} is below Docker's minimum of 6m", self.memory));
+        }
+        if !(self.cpus.is_finite() && self.cpus >= 0.01) {
+            return Err(format!("cpus must be at least 0.01, got {}", self.cpus));
+        }
+        if self.pids_limit < 1 {
+            return Err(format!("pids_limit must be positive, got {}", self.pids_limit));
+        }
+        Ok(())
+    }
+}
+
+/// Container hardening. Docker's default seccomp profile always applies, as
+/// services can neither run privileged nor set their own `security_opt`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Security {
+    /// ALL is dropped whether or not it is listed, see `dropped_capabilities`
+    #[serde(default = "default_cap_drop")]
+    pub cap_drop: Vec<String>,
+    /// Capabilities given back after `cap_drop`; ones that reach outside the container are refused
+    #[serde(default)]
+    pub cap_add: Vec<String>,
+    #[serde(default = "default_true")]
+    pub no_new_privileges: bool,
+    /// Mount the root filesystem read-only; writable paths go in `tmpfs` or `volumes`
+    #[serde(default)]
+    pub read_only: bool,
+    /// `target:options`, where the options must include a `size`, e.g. `/tmp:size=64m`
+    #[serde(default)]
+    pub tmpfs: Vec<String>,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Self {
+            cap_drop: default_cap_drop(),
+            cap_add: Vec::new(),
+            no_new_privileges: true,
+            read_only: false,
+            tmpfs: Vec::new(),
+        }
+    }
+}
+
+impl Security {
+    /// Docker `security_opt` entries
+    pub fn security_opt(&self) -> Vec<String> {
+        if self.no_new_privileges {
+            vec!["no-new-privileges:true".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Docker `cap_drop`, always starting with ALL so only `cap_add` gives anything back
+    pub fn dropped_capabilities(&self) -> Vec<String> {
+        let mut dropped = default_cap_drop();
+        dropped.extend(self.cap_drop.iter().filter(|cap| capability_name(cap) != "ALL").cloned());
+        dropped
+    }
+
+    /// Tmpfs mounts as Docker's target to options map
+    pub fn tmpfs_mounts(&self) -> Result<BTreeMap<String, String>, String> {
+        self.tmpfs.iter().map(|mount| parse_tmpfs(mount)).collect()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for capability in self.cap_drop.iter().chain(&self.cap_add) {
+            if capability.is_empty() || !capability.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') {
+                return Err(format!("invalid capability {:?}, expected e.g. \"NET_BIND_SERVICE\"", capability));
+            }
+        }
+        if let Some(capability) = self.cap_add.iter().find(|cap| FORBIDDEN_CAPABILITIES.contains(&capability_name(cap))) {
+            return Err(format!("capability {} is not allowed", capability));
+        }
+        self.tmpfs_mounts().map(|_| ())
+    }
+}
+
+/// Docker refuses memory limits below 6 MiB
+const MIN_MEMORY: i64 = 6 * 1024 * 1024;
+
+/// Capabilities that would let a container reach the host
+const FORBIDDEN_CAPABILITIES: &[&str] = &[
+    "ALL", "SYS_ADMIN", "SYS_MODULE", "SYS_PTRACE", "SYS_RAWIO", "SYS_BOOT", "SYS_TIME",
+    "NET_ADMIN", "DAC_READ_SEARCH", "MKNOD", "BPF", "PERFMON", "MAC_ADMIN", "MAC_OVERRIDE",
+];
+
+// Docker takes capabilities with or without the `CAP_` prefix
+fn capability_name(capability: &str) -> &str {
+    capability.strip_prefix("CAP_").unwrap_or(capability)
+}
+
+/// Host paths that are never bind-mounted, nor anything below them
+const FORBIDDEN_BIND_TREES: &[&str] = &[
+    "/proc", "/sys", "/dev", "/boot", "/etc", "/root", "/usr", "/bin", "/sbin", "/lib",
+    "/var/lib/docker", "/var/run/docker.sock", "/run/docker.sock",
+];
+
+/// Host paths that are never bind-mounted whole
+const FORBIDDEN_BIND_ROOTS: &[&str] = &["/", "/tmp", "/var", "/run", "/var/run", "/home", "/Users"];
+
+// Why a host path may not be bind-mounted, if it may not
+fn forbidden_bind_source(source: &str) -> Option<String> {
+    let normalized = normalize_path(source);
+    let normalized = normalized.as_str();
+
+    // Drive roots such as `C:/`
+    let is_drive_root = normalized.len() == 2 && normalized.ends_with(':');
+    let under_tree = FORBIDDEN_BIND_TREES.iter().any(|tree| {
+        normalized == *tree || normalized.strip_prefix(tree).is_some_and(|rest| rest.starts_with('/'))
+    });
+
+    if is_drive_root || under_tree || FORBIDDEN_BIND_ROOTS.contains(&normalized) {
+        Some(format!("bind mounting {} from the host is not allowed", source))
+    } else {
+        None
+    }
+}
+
+// Resolve `.`, `..` and repeated or trailing slashes the way Docker cleans a
+// bind source, with `/` separators. `..` never climbs above the root.
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                // A drive such as `C:` is the root
+                if parts.last().is_some_and(|last| !(parts.len() == 1 && last.ends_with(':'))) {
+                    parts.pop();
+                }
+            }
+            part => parts.push(part),
+        }
+    }
+
+    match parts.join("/") {
+        joined if path.starts_with('/') => format!("/{}", joined),
+        joined => joined,
+    }
+}
+
+/// Parse a size such as `512m`, `1g`, `64k` or a plain byte count
+pub fn parse_size(size: &str) -> Result<i64, String> {
+    let error = || format!("invalid size {:?}, expected e.g. 512m", size);
+    let lower = size.trim().to_ascii_lowercase();
+    let (digits, multiplier) = match lower.char_indices().last() {
+        Some((i, 'k')) => (&lower[..i], 1024),
+        Some((i, 'm')) => (&lower[..i], 1024 * 1024),
+        Some((i, 'g')) => (&lower[..i], 1024 * 1024 * 1024),
+        Some((i, 'b')) => (&lower[..i], 1),
+        _ => (lower.as_str(), 1),
+    };
+
+    digits.parse::<i64>().ok()
+        .filter(|value| *value > 0)
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or_else(error)
+}
+
+fn parse_tmpfs(mount: &str) -> Result<(String, String), String> {
+    let (target, options) = mount.split_once(':').unwrap_or((mount, ""));
+    if !target.starts_with('/') {
+        return Err(format!("invalid tmpfs {:?}, expected /target:size=...", mount));
+    }
+
+    // Unbounded tmpfs mounts live in host memory outside the memory limit
+    let size = options.split(',').find_map(|option| option.strip_prefix("size="))
+        .ok_or_else(|| format!("tmpfs {} needs a size, e.g. {}:size=64m", target, target))?;
+    parse_size(size)?;
+
+    Ok((target.to_string(), options.to_string()))
+}
+
+/// Docker HEALTHCHECK run inside the container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Healthcheck {
+    /// `["CMD", ...]`, `["CMD-SHELL", "..."]` or `["NONE"]`
+    pub test: Vec<String>,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    #[serde(default)]
+    pub start_period_secs: u64,
+}
+
+/// Probe of a container port. Published ports are probed through the host,
+/// others through the container's address on the marketplace network.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Probe {
+    /// Healthy when a GET of `path` returns a 2xx status
+    Http {
+        port: u16,
+        #[serde(default = "default_probe_path")]
+        path: String,
+    },
+    /// Healthy when the port accepts a connection
+    Tcp { port: u16 },
+}
+
+impl Probe {
+    pub fn port(&self) -> u16 {
+        match self {
+            Probe::Http { port, .. } | Probe::Tcp { port } => *port,
+        }
+    }
+}
+
+fn default_memory() -> String {
+    "512m".to_string()
+}
+
+fn default_cpus() -> f64 {
+    1.0
+}
+
+fn default_pids_limit() -> i64 {
+    256
+}
+
+fn default_cap_drop() -> Vec<String> {
+    vec!["ALL".to_string()]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_probe_path() -> String {
+    "/".to_string()
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortMapping {
+    pub host_ip: Option<String>,
+    pub host_port: Option<u16>,
+    pub container_port: u16,
+    pub protocol: String,
+}
+
+impl PortMapping {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (ports, protocol) = match spec.split_once('/') {
+            Some((ports, protocol)) if matches!(protocol, "tcp" | "udp") => (ports, protocol),
+            Some((_, protocol)) => return Err(format!("unsupported protocol {:?}", protocol)),
+            None => (spec, "tcp"),
+        };
+
+        let port = |value: &str| value.parse::<u16>().map_err(|_| format!("invalid port {:?}", value));
+        let parts: Vec<&str> = ports.split(':').collect();
+        let (host_ip, host_port, container_port) = match parts.as_slice() {
+            [container] => (None, None, port(container)?),
+            [host, container] => (None, Some(port(host)?), port(container)?),
+            [ip, host, container] => (Some(ip.to_string()), Some(port(host)?), port(container)?),
+            _ => return Err(format!("invalid port mapping {:?}", spec)),
+        };
+
+        Ok(Self {
+            host_ip,
+            host_port,
+            container_port,
+            protocol: protocol.to_string(),
+        })
+    }
+
+    /// Key used by the Docker API, e.g. `3000/tcp`
+    pub fn container_key(&self) -> String {
+        format!("{}/{}", self.container_port, self.protocol)
+    }
+}
+
+/// Expand `${NAME}` references. Unknown names are an error rather than
+/// silently becoming empty paths.
+pub fn substitute(value: &str, variables: &BTreeMap<&str, String>) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}')
+            .ok_or_else(|| format!("unterminated variable in {:?}", value))?;
+        let name = &rest[start + 2..start + end];
+        let expanded = variables.get(name)
+            .ok_or_else(|| format!("unknown variable ${{{}}}", name))?;
+        result.push_str(expanded);
+        rest = &rest[start + end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+// Split a volume from the right, so Windows sources like `C:\Apps` keep their drive letter
+fn split_volume(volume: &str) -> Result<(&str, &str, Option<&str>), String> {
+    let (rest, mode) = match volume.rsplit_once(':') {
+        Some((rest, mode)) if matches!(mode, "ro" | "rw") => (rest, Some(mode)),
+        _ => (volume, None),
+    };
+
+    match rest.rsplit_once(':') {
+        Some((source, target)) if !source.is_empty() && target.starts_with('/') => Ok((source, target, mode)),
+        _ => Err(format!("invalid volume {:?}, expected source:/target", volume)),
+    }
+}
+
+impl ServiceSpec {
+    pub fn port_mappings(&self) -> Result<Vec<PortMapping>, String> {
+        self.ports.iter().map(|port| PortMapping::parse(port)).collect()
+    }
+
+    /// Host address a container TCP port is published on, if any
+    pub fn published_port(&self, container_port: u16) -> Option<(String, u16)> {
+        self.port_mappings()
+            .ok()?
+            .into_iter()
+            .filter(|port| port.protocol == "tcp" && port.container_port == container_port)
+            .find_map(|port| {
+                let host_ip = match port.host_ip.as_deref() {
+                    None | Some("0.0.0.0") => "127.0.0.1".to_string(),
+                    Some(ip) => ip.to_string(),
+                };
+                Some((host_ip, port.host_port?))
+            })
+    }
+
+    /// `KEY=value` pairs with variables expanded
+    pub fn env(&self, variables: &BTreeMap<&str, String>) -> Result<Vec<String>, String> {
+        self.environment
+            .iter()
+            .map(|(key, value)| Ok(format!("{}={}", key, substitute(value, variables)?)))
+            .collect()
+    }
+
+    /// Bind mounts in Docker's `source:target[:mode]` form with variables expanded.
+    /// Sources are checked after expansion, so a chosen folder can't be `/` either.
+    pub fn binds(&self, variables: &BTreeMap<&str, String>) -> Result<Vec<String>, String> {
+        self.volumes
+            .iter()
+            .map(|volume| {
+                let (source, target, mode) = split_volume(volume)?;
+                let source = substitute(source, variables)?;
+                if let Some(e) = forbidden_bind_source(&source) {
+                    return Err(e);
+                }
+                Ok(match mode {
+                    Some(mode) => format!("{}:{}:{}", source, target, mode),
+                    None => format!("{}:{}", source, target),
+                })
+            })
+            .collect()
+    }
+
+    fn validate(&self, name: &str, stack: &ServiceStack) -> Result<(), ServiceSpecError> {
+        if self.image.trim().is_empty() {
+            return Err(invalid(name, "image is empty"));
+        }
+        if let Some(digest) = &self.digest {
+            check_digest_format(digest).map_err(|e| invalid(name, e.to_string()))?;
+        }
+        if let Some(signature) = &self.signature {
+            if self.digest.is_none() {
+                return Err(invalid(name, "a signature needs the digest it signs"));
+            }
+            decode_signature(&self.image, signature).map_err(|e| invalid(name, e.to_string()))?;
+        }
+
+        self.port_mappings().map_err(|e| invalid(name, e))?;
+        for volume in &self.volumes {
+            let (source, _, _) = split_volume(volume).map_err(|e| invalid(name, e))?;
+            if let Some(e) = forbidden_bind_source(source) {
+                return Err(invalid(name, e));
+            }
+        }
+        self.limits.validate().map_err(|e| invalid(name, e))?;
+        self.security.validate().map_err(|e| invalid(name, e))?;
+
+        for dependency in &self.depends_on {
+            if dependency == name {
+                return Err(invalid(name, "depends on itself"));
+            }
+            if !stack.services.contains_key(dependency) {
+                return Err(invalid(name, format!("depends on unknown service {}", dependency)));
+            }
+        }
+
+        if let Some(healthcheck) = &self.healthcheck {
+            match healthcheck.test.first().map(String::as_str) {
+                Some("NONE") => {}
+                Some("CMD") | Some("CMD-SHELL") if healthcheck.test.len() > 1 => {}
+                _ => return Err(invalid(name, "healthcheck test must be [\"CMD\", ...], [\"CMD-SHELL\", ...] or [\"NONE\"]")),
+            }
+        }
+
+        if let Some(Probe::Http { path, .. }) = &self.probe {
+            if !path.starts_with('/') {
+                return Err(invalid(name, "probe path must start with /"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ServiceStack {
+    pub fn bundled() -> Self {
+        Self::from_toml(BUNDLED_SERVICES).expect("bundled services.toml is valid")
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, ServiceSpecError> {
+        let stack: ServiceStack = toml::from_str(content)?;
+        stack.validate()?;
+        Ok(stack)
+    }
+
+    /// User override next to `config.json`
+    pub fn override_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("PWA-Marketplace").join("services.toml"))
+    }
+
+    /// The user's `services.toml` if there is one, the bundled stack otherwise
+    pub fn load() -> Result<Self, ServiceSpecError> {
+        match Self::override_path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(Self::bundled()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ServiceSpecError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn validate(&self) -> Result<(), ServiceSpecError> {
+        for (name, spec) in &self.services {
+            spec.validate(name, self)?;
+        }
+        self.start_order().map(|_| ())
+    }
+
+    /// Services with their dependencies first, ties broken by name
+    pub fn start_order(&self) -> Result<Vec<(&str, &ServiceSpec)>, ServiceSpecError> {
+        let mut order = Vec::with_capacity(self.services.len());
+        let mut started = BTreeSet::new();
+
+        while order.len() < self.services.len() {
+            let ready: Vec<&str> = self.services
+                .iter()
+                .filter(|(name, spec)| {
+                    !started.contains(name.as_str())
+                        && spec.depends_on.iter().all(|dep| started.contains(dep.as_str()))
+                })
+                .map(|(name, _)| name.as_str())
+                .collect();
+
+            if ready.is_empty() {
+                let stuck: Vec<&str> = self.services
+                    .keys()
+                    .map(String::as_str)
+                    .filter(|name| !started.contains(name))
+                    .collect();
+                return Err(ServiceSpecError::DependencyCycle(stuck.join(", ")));
+            }
+
+            for name in ready {
+                started.insert(name);
+                order.push((name, &self.services[name]));
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Services in the order they are stopped, dependents first
+    pub fn stop_order(&self) -> Result<Vec<(&str, &ServiceSpec)>, ServiceSpecError> {
+        let mut order = self.start_order()?;
+        order.reverse();
+        Ok(order)
+    }
+
+    /// Every distinct image the stack runs
+    pub fn images(&self) -> Vec<&str> {
+        let images: BTreeSet<&str> = self.services.values().map(|spec| spec.image.as_str()).collect();
+        images.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names<'a>(order: &[(&'a str, &ServiceSpec)]) -> Vec<&'a str> {
+        order.iter().map(|(name, _)| *name).collect()
+    }
+
+    fn folders() -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("APPS_FOLDER", r"C:\Users\me\PWA-Apps".to_string()),
+            ("DATA_FOLDER", "/home/me/PWA-Data".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_bundled_stack_starts_dependencies_first() {
+        let stack = ServiceStack::bundled();
+        let order = stack.start_order().unwrap();
+
+        assert_eq!(names(&order), ["mcp-bridge", "resource-controller", "pwa-marketplace"]);
+        assert_eq!(names(&stack.stop_order().unwrap()), ["pwa-marketplace", "resource-controller", "mcp-bridge"]);
+        assert_eq!(stack.images().len(), 3);
+
+        let marketplace = &stack.services["pwa-marketplace"];
+        assert_eq!(marketplace.port_mappings().unwrap(), [PortMapping {
+            host_ip: Some("127.0.0.1".to_string()),
+            host_port: Some(3000),
+            container_port: 3000,
+            protocol: "tcp".to_string(),
+        }]);
+        assert!(marketplace.healthcheck.is_some());
+        assert_eq!(marketplace.published_port(3000), Some(("127.0.0.1".to_string(), 3000)));
+        assert_eq!(stack.services["mcp-bridge"].published_port(3001), None);
+    }
+
+    #[test]
+    fn test_invalid_stacks_are_rejected() {
+        let cycle = r#"
+            [services.a]
+            image = "a"
+            depends_on = ["b"]
+            [services.b]
+            image = "b"
+            depends_on = ["a"]
+            [services.c]
+            image = "c"
+        "#;
+        assert!(matches!(
+            ServiceStack::from_toml(cycle),
+            Err(ServiceSpecError::DependencyCycle(stuck)) if stuck == "a, b"
+        ));
+
+        let unknown = "[services.a]\nimage = \"a\"\ndepends_on = [\"db\"]\n";
+        assert!(matches!(ServiceStack::from_toml(unknown), Err(ServiceSpecError::Invalid { .. })));
+
+        let bad_port = "[services.a]\nimage = \"a\"\nports = [\"localhost:http:80\"]\n";
+        assert!(matches!(ServiceStack::from_toml(bad_port), Err(ServiceSpecError::Invalid { .. })));
+
+        let probe = "[services.a]\nimage = \"a\"\nprobe = { type = \"http\", port = 80, path = \"health\" }\n";
+        assert!(matches!(ServiceStack::from_toml(probe), Err(ServiceSpecError::Invalid { .. })));
+
+        let unsigned = "[services.a]\nimage = \"a\"\nsignature = \"c2ln\"\n";
+        assert!(matches!(ServiceStack::from_toml(unsigned), Err(ServiceSpecError::Invalid { .. })));
+
+        let digest = "[services.a]\nimage = \"a\"\ndigest = \"latest\"\n";
+        assert!(matches!(ServiceStack::from_toml(digest), Err(ServiceSpecError::Invalid { .. })));
+
+        let typo = "[services.a]\nimage = \"a\"\nport = [\"80\"]\n";
+        assert!(matches!(ServiceStack::from_toml(typo), Err(ServiceSpecError::Parse(_))));
+    }
+
+    #[test]
+    fn test_folder_substitution() {
+        let spec: ServiceSpec = toml::from_str(r#"
+            image = "app"
+            volumes = ["${APPS_FOLDER}:/apps:ro", "${DATA_FOLDER}/cache:/cache", 'D:\scratch:/scratch']
+            environment = { DATA = "${DATA_FOLDER}", PLAIN = "x" }
+        "#).unwrap();
+
+        assert_eq!(spec.binds(&folders()).unwrap(), [
+            r"C:\Users\me\PWA-Apps:/apps:ro",
+            "/home/me/PWA-Data/cache:/cache",
+            r"D:\scratch:/scratch",
+        ]);
+        assert_eq!(spec.env(&folders()).unwrap(), ["DATA=/home/me/PWA-Data", "PLAIN=x"]);
+
+        assert!(substitute("${HOME}/x", &folders()).is_err());
+        assert!(substitute("${APPS_FOLDER", &folders()).is_err());
+    }
+
+    #[test]
+    fn test_limits_and_hardening() {
+        // Services get bounded and locked down without asking
+        let spec: ServiceSpec = toml::from_str("image = \"app\"").unwrap();
+        assert_eq!(spec.limits.memory_bytes().unwrap(), 512 * 1024 * 1024);
+        assert_eq!(spec.limits.nano_cpus(), 1_000_000_000);
+        assert_eq!(spec.security.cap_drop, ["ALL"]);
+        assert_eq!(spec.security.security_opt(), ["no-new-privileges:true"]);
+
+        let bundled = ServiceStack::bundled();
+        for spec in bundled.services.values() {
+            assert!(spec.security.read_only);
+            assert!(spec.volumes.iter().all(|volume| !volume.starts_with("/tmp:")));
+        }
+        let controller = &bundled.services["resource-controller"].security;
+        assert_eq!(controller.tmpfs_mounts().unwrap()["/tmp"], "size=64m,mode=1777");
+
+        let stack = |service: &str| ServiceStack::from_toml(&format!("[services.a]\nimage = \"a\"\n{}", service));
+        assert!(stack("limits = { memory = \"1m\" }").is_err());
+        assert!(stack("limits = { memory = \"lots\" }").is_err());
+        assert!(stack("limits = { cpus = 0.0 }").is_err());
+        assert!(stack("limits = { pids_limit = 0 }").is_err());
+        assert!(stack("security = { cap_add = [\"SYS_ADMIN\"] }").is_err());
+        assert!(stack("security = { cap_add = [\"CAP_SYS_ADMIN\"] }").is_err());
+        assert!(stack("security = { tmpfs = [\"/tmp\"] }").is_err());
+        assert!(stack("security = { privileged = true }").is_err());
+        assert!(stack("volumes = [\"/var/run/docker.sock:/var/run/docker.sock\"]").is_err());
+        assert!(stack("volumes = [\"/tmp:/host/tmp\"]").is_err());
+        assert!(stack("volumes = [\"/data/../etc:/etc\"]").is_err());
+        assert!(stack("volumes = [\"//var/run/docker.sock:/docker.sock\"]").is_err());
+        assert!(stack("volumes = [\"/home/x/../../etc:/etc\"]").is_err());
+        assert!(stack("volumes = [\"/home/x/./cache/:/cache\"]").is_ok());
+
+        // Listing other capabilities doesn't stop ALL from being dropped
+        let spec: ServiceSpec = toml::from_str("image = \"a\"\nsecurity = { cap_drop = [\"NET_RAW\"] }").unwrap();
+        assert_eq!(spec.security.dropped_capabilities(), ["ALL", "NET_RAW"]);
+        let spec: ServiceSpec = toml::from_str("image = \"a\"\nsecurity = { cap_drop = [\"CAP_ALL\"] }").unwrap();
+        assert_eq!(spec.security.dropped_capabilities(), ["ALL"]);
+        assert!(stack("security = { cap_add = [\"NET_BIND_SERVICE\"], tmpfs = [\"/run:size=1m\"] }").is_ok());
+
+        // Folders chosen during setup are checked once expanded
+        let spec: ServiceSpec = toml::from_str("image = \"a\"\nvolumes = [\"${DATA_FOLDER}:/data\"]").unwrap();
+        let root = BTreeMap::from([("DATA_FOLDER", "/".to_string())]);
+        assert!(spec.binds(&root).is_err());
+        assert!(spec.binds(&BTreeMap::from([("DATA_FOLDER", r"C:\".to_string())])).is_err());
+        assert!(spec.binds(&folders()).is_ok());
+    }
+}
@@ -0,0 +1,318 @@
+// src-tauri/src/service_supervisor.rs
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use crate::docker_manager::{ContainerEvent, HealthStatus, LiveState, ServiceStatus};
+use crate::system_tray;
+use crate::AppState;
+
+/// When and how often failed services are restarted
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// How often service health is checked
+    pub poll_interval: Duration,
+    /// Consecutive failed health checks before a running service is restarted
+    pub unhealthy_after: u32,
+    /// Delay before the first restart, doubled for every restart since
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Restarts within `crash_loop_window` after which the supervisor gives up
+    pub crash_loop_threshold: usize,
+    pub crash_loop_window: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(10),
+            unhealthy_after: 3,
+            base_backoff: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(300),
+            crash_loop_threshold: 5,
+            crash_loop_window: Duration::from_secs(600),
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn backoff(&self, restarts: usize) -> Duration {
+        let factor = 2u32.saturating_pow(restarts.min(16) as u32);
+        self.base_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    // Why a service should be restarted, if it should
+    fn restart_reason(&self, status: &ServiceStatus) -> Option<String> {
+        match status.health {
+            HealthStatus::Stopped => Some(format!("container is {}", status.status)),
+            HealthStatus::Unhealthy if status.failing_streak >= self.unhealthy_after => Some(format!(
+                "{} failed health checks: {}",
+                status.failing_streak,
+                status.health_detail.as_deref().unwrap_or("no output")
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// What the supervisor should do about a service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Fine, or waiting out the backoff
+    Wait,
+    Restart { attempt: usize, reason: String },
+    /// Crash loop; no more restarts until the service recovers on its own
+    GiveUp { restarts: usize, reason: String },
+}
+
+#[derive(Debug, Default)]
+struct ServiceTracker {
+    /// Restarts within the crash loop window, oldest first
+    restarts: VecDeque<Instant>,
+    /// When the pending restart is due
+    restart_at: Option<Instant>,
+    gave_up: bool,
+}
+
+impl ServiceTracker {
+    fn observe(&mut self, status: &ServiceStatus, now: Instant, policy: &RestartPolicy) -> Action {
+        while self.restarts.front().is_some_and(|at| now.duration_since(*at) > policy.crash_loop_window) {
+            self.restarts.pop_front();
+        }
+
+        let Some(reason) = policy.restart_reason(status) else {
+            self.restart_at = None;
+            if status.health.is_ready() && self.gave_up {
+                log::info!("{} recovered, supervising it again", status.name);
+                self.gave_up = false;
+            }
+            return Action::Wait;
+        };
+
+        if self.gave_up {
+            return Action::Wait;
+        }
+        if self.restarts.len() >= policy.crash_loop_threshold {
+            self.gave_up = true;
+            self.restart_at = None;
+            return Action::GiveUp { restarts: self.restarts.len(), reason };
+        }
+
+        match self.restart_at {
+            None => {
+                self.restart_at = Some(now + policy.backoff(self.restarts.len()));
+                Action::Wait
+            }
+            Some(due) if now >= due => {
+                self.restart_at = None;
+                self.restarts.push_back(now);
+                Action::Restart { attempt: self.restarts.len(), reason }
+            }
+            Some(_) => Action::Wait,
+        }
+    }
+}
+
+/// Payload of the `service-restarting` and `service-crash-loop` events
+#[derive(Debug, Clone, Serialize)]
+pub struct SupervisorEvent {
+    pub service: String,
+    pub restarts: usize,
+    pub reason: String,
+}
+
+/// Payload of the `service-event` event
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveServiceEvent {
+    pub event: ContainerEvent,
+    pub state: LiveState,
+}
+
+/// How long to wait before subscribing to Docker events again
+const EVENTS_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+fn emit<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app_handle.emit_all(event, payload) {
+        log::warn!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+/// Watch the marketplace services for as long as the app runs. Every poll
+/// updates the tray and emits `service-status`; stopped and persistently
+/// unhealthy services are restarted with exponential backoff.
+pub async fn run(app_handle: AppHandle, policy: RestartPolicy) {
+    let mut trackers: HashMap<String, ServiceTracker> = HashMap::new();
+    let mut interval = tokio::time::interval(policy.poll_interval);
+
+    loop {
+        interval.tick().await;
+
+        // Nothing to supervise before setup, or while services are shut down on purpose
+        let docker_manager = app_handle.state::<AppState>().docker_manager.lock().unwrap().clone();
+        let Some(docker_manager) = docker_manager.filter(|manager| manager.services_expected()) else {
+            trackers.clear();
+            continue;
+        };
+
+        let statuses = match docker_manager.get_services_status().await {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                log::warn!("Failed to check marketplace services: {}", e);
+                continue;
+            }
+        };
+        system_tray::update_service_status(&app_handle, &statuses);
+        emit(&app_handle, "service-status", statuses.clone());
+
+        for status in &statuses {
+            // Swapping containers looks like a failure from here
+            if docker_manager.is_upgrading(&status.name) {
+                trackers.remove(&status.name);
+                continue;
+            }
+            let tracker = trackers.entry(status.name.clone()).or_default();
+
+            match tracker.observe(status, Instant::now(), &policy) {
+                Action::Wait => {}
+                Action::Restart { attempt, reason } => {
+                    log::warn!("Restarting {} (attempt {}): {}", status.name, attempt, reason);
+                    emit(&app_handle, "service-restarting", SupervisorEvent {
+                        service: status.name.clone(),
+                        restarts: attempt,
+                        reason,
+                    });
+
+                    if let Err(e) = docker_manager.restart_service(&status.name).await {
+                        log::error!("Failed to restart {}: {}", status.name, e);
+                    }
+                }
+                Action::GiveUp { restarts, reason } => {
+                    log::error!(
+                        "{} keeps failing ({} restarts in {:?}), not restarting it again: {}",
+                        status.name, restarts, policy.crash_loop_window, reason
+                    );
+                    emit(&app_handle, "service-crash-loop", SupervisorEvent {
+                        service: status.name.clone(),
+                        restarts,
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Push container events to the frontend as `service-event` as they happen,
+/// resubscribing whenever Docker isn't set up yet or the stream breaks off.
+pub async fn forward_events(app_handle: AppHandle) {
+    loop {
+        let docker_manager = app_handle.state::<AppState>().docker_manager.lock().unwrap().clone();
+
+        if let Some(docker_manager) = docker_manager {
+            let result = docker_manager.watch_events(|event, state| {
+                emit(&app_handle, "service-event", LiveServiceEvent {
+                    event: event.clone(),
+                    state: state.clone(),
+                });
+            }).await;
+
+            if let Err(e) = result {
+                log::debug!("Docker event stream ended: {}", e);
+            }
+        }
+
+        tokio::time::sleep(EVENTS_RETRY_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(health: HealthStatus, failing_streak: u32) -> ServiceStatus {
+        ServiceStatus {
+            name: "mcp-bridge".to_string(),
+            status: if health == HealthStatus::Stopped { "exited" } else { "running" }.to_string(),
+            health,
+            failing_streak,
+            health_detail: None,
+            ports: Vec::new(),
+            uptime: None,
+        }
+    }
+
+    #[test]
+    fn test_restarts_back_off_exponentially() {
+        let policy = RestartPolicy::default();
+        let mut tracker = ServiceTracker::default();
+        let stopped = status(HealthStatus::Stopped, 0);
+        let start = Instant::now();
+
+        // First failure schedules a restart after the base backoff
+        assert_eq!(tracker.observe(&stopped, start, &policy), Action::Wait);
+        assert_eq!(tracker.observe(&stopped, start + Duration::from_secs(4), &policy), Action::Wait);
+        assert!(matches!(
+            tracker.observe(&stopped, start + Duration::from_secs(5), &policy),
+            Action::Restart { attempt: 1, .. }
+        ));
+
+        // The next one waits twice as long
+        let now = start + Duration::from_secs(6);
+        assert_eq!(tracker.observe(&stopped, now, &policy), Action::Wait);
+        assert_eq!(tracker.observe(&stopped, now + Duration::from_secs(9), &policy), Action::Wait);
+        assert!(matches!(
+            tracker.observe(&stopped, now + Duration::from_secs(10), &policy),
+            Action::Restart { attempt: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_unhealthy_services_get_a_few_chances() {
+        let policy = RestartPolicy::default();
+        let mut tracker = ServiceTracker::default();
+        let start = Instant::now();
+
+        for streak in 1..policy.unhealthy_after {
+            assert_eq!(tracker.observe(&status(HealthStatus::Unhealthy, streak), start, &policy), Action::Wait);
+        }
+        assert!(tracker.restart_at.is_none());
+
+        let failing = status(HealthStatus::Unhealthy, policy.unhealthy_after);
+        tracker.observe(&failing, start, &policy);
+        assert!(matches!(
+            tracker.observe(&failing, start + policy.base_backoff, &policy),
+            Action::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_crash_loop_gives_up_until_recovery() {
+        let policy = RestartPolicy {
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let mut tracker = ServiceTracker::default();
+        let stopped = status(HealthStatus::Stopped, 0);
+        let mut now = Instant::now();
+
+        let mut restarts = 0;
+        loop {
+            now += Duration::from_secs(1);
+            match tracker.observe(&stopped, now, &policy) {
+                Action::Restart { .. } => restarts += 1,
+                Action::GiveUp { restarts: given_up_after, .. } => {
+                    assert_eq!(given_up_after, policy.crash_loop_threshold);
+                    break;
+                }
+                Action::Wait => {}
+            }
+        }
+        assert_eq!(restarts, policy.crash_loop_threshold);
+        assert_eq!(tracker.observe(&stopped, now + Duration::from_secs(5), &policy), Action::Wait);
+
+        // Once it comes back by itself it is supervised again
+        tracker.observe(&status(HealthStatus::Healthy, 0), now, &policy);
+        assert!(!tracker.gave_up);
+    }
+}
@@ -4,6 +4,8 @@ use tauri::{
     SystemTrayMenu, SystemTrayMenuItem, Window
 };
 use crate::AppState;
+use crate::docker_manager::ServiceStatus;
+use crate::password_manager::LockReason;
 
 pub fn create_system_tray() -> SystemTray {
     let open_marketplace = CustomMenuItem::new("open_marketplace".to_string(), "Open PWA Marketplace")
@@ -12,6 +14,9 @@ pub fn create_system_tray() -> SystemTray {
     let password_manager = CustomMenuItem::new("password_manager".to_string(), "Password Manager")
         .accelerator("Cmd+P");
     
+    let lock_vault = CustomMenuItem::new("lock_vault".to_string(), "Lock Password Manager")
+        .accelerator("Cmd+L");
+    
     let settings = CustomMenuItem::new("settings".to_string(), "Settings");
     
     let status = CustomMenuItem::new("status".to_string(), "Status: Initializing...")
@@ -32,6 +37,7 @@ pub fn create_system_tray() -> SystemTray {
         .add_native_item(separator1)
         .add_item(open_marketplace)
         .add_item(password_manager)
+        .add_item(lock_vault)
         .add_item(settings)
         .add_native_item(separator2)
         .add_item(about)
@@ -41,6 +47,23 @@ pub fn create_system_tray() -> SystemTray {
     SystemTray::new().with_menu(tray_menu)
 }
 
+/// Show the health of the marketplace services in the tray's status line
+pub fn update_service_status(app: &AppHandle, statuses: &[ServiceStatus]) {
+    let failing: Vec<&ServiceStatus> = statuses.iter()
+        .filter(|status| !status.health.is_ready())
+        .collect();
+    
+    let title = match failing.as_slice() {
+        [] => "Status: All services running".to_string(),
+        [status] => format!("Status: {} is {}", status.name, status.health.as_str()),
+        _ => format!("Status: {} services need attention", failing.len()),
+    };
+    
+    if let Err(e) = app.tray_handle().get_item("status").set_title(title) {
+        log::warn!("Failed to update tray status: {}", e);
+    }
+}
+
 pub fn handle_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
     match event {
         SystemTrayEvent::LeftClick {
@@ -87,6 +110,10 @@ fn handle_menu_item_click(app: &AppHandle, id: &str) {
             open_password_manager_window(app);
         }
         
+        "lock_vault" => {
+            lock_password_manager(app);
+        }
+        
         "settings" => {
             open_settings_window(app);
         }
@@ -162,6 +189,20 @@ fn open_password_manager_window(app: &AppHandle) {
     }
 }
 
+fn lock_password_manager(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let password_manager = state.password_manager.lock().unwrap().clone();
+    
+    if let Some(password_manager) = password_manager {
+        if password_manager.lock() {
+            if let Err(e) = app.emit_all("vault-locked", LockReason::Manual) {
+                log::error!("Failed to emit vault-locked event: {}", e);
+            }
+            show_notification(app, "Password Manager", "Vault locked");
+        }
+    }
+}
+
 fn open_settings_window(app: &AppHandle) {
     let window_label = "settings";
     
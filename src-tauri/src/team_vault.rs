@@ -0,0 +1,695 @@
+// src-tauri/src/team_vault.rs
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::password_hash::rand_core::RngCore;
+use chrono::{DateTime, Utc};
+use ring::hkdf;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+use crate::password_manager::{manager_from_state, PasswordEntry};
+use crate::AppState;
+
+pub const BUNDLE_FORMAT: &str = "pwa-marketplace-collection";
+pub const BUNDLE_VERSION: u32 = 1;
+const BUNDLE_EXTENSION: &str = "pmcollection";
+
+const WRAP_INFO: &[u8] = b"pwa-marketplace collection key wrap v1";
+const SIGNING_INFO: &[u8] = b"pwa-marketplace member signing key v1";
+const ROSTER_CONTEXT: &[u8] = b"pwa-marketplace collection roster v1";
+
+#[derive(Error, Debug)]
+pub enum TeamVaultError {
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("This vault is not a member of collection {0}")]
+    NotMember(String),
+    #[error("Collection not found: {0}")]
+    CollectionNotFound(String),
+    #[error("Member list of collection {0} has been tampered with")]
+    RosterMismatch(String),
+    #[error("Collection {0} was changed by someone who is not a member")]
+    UntrustedSigner(String),
+    #[error("Collection data could not be decrypted")]
+    Decryption,
+    #[error("Unsupported bundle: {0}")]
+    UnsupportedBundle(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid bundle: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// This vault's X25519 key pair, plus an Ed25519 signing key derived from it.
+/// Collection keys are wrapped to the X25519 half and member lists are signed
+/// with the Ed25519 half.
+pub struct Identity {
+    secret: StaticSecret,
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        Self { secret: StaticSecret::random_from_rng(OsRng) }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TeamVaultError> {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        if bytes.len() != secret.len() {
+            return Err(TeamVaultError::Decryption);
+        }
+        secret.copy_from_slice(bytes);
+
+        Ok(Self { secret: StaticSecret::from(*secret) })
+    }
+
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.secret.to_bytes())
+    }
+
+    /// Base64 of the X25519 and Ed25519 public keys, as shared with collection owners
+    pub fn public_key(&self) -> String {
+        let mut public_key = PublicKey::from(&self.secret).as_bytes().to_vec();
+        public_key.extend_from_slice(self.signing_key().public_key().as_ref());
+        base64::encode(public_key)
+    }
+
+    /// Base64 Ed25519 signature over `message`
+    pub fn sign(&self, message: &[u8]) -> String {
+        base64::encode(self.signing_key().sign(message))
+    }
+
+    fn signing_key(&self) -> Ed25519KeyPair {
+        let secret = self.to_bytes();
+        let mut seed = Zeroizing::new([0u8; 32]);
+        hkdf::Salt::new(hkdf::HKDF_SHA256, &[])
+            .extract(secret.as_ref())
+            .expand(&[SIGNING_INFO], hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(seed.as_mut()))
+            .expect("32 bytes is within HKDF limits");
+
+        Ed25519KeyPair::from_seed_unchecked(seed.as_ref()).expect("Ed25519 seeds are 32 bytes")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CollectionMember {
+    pub name: String,
+    pub public_key: String,
+}
+
+/// A shared collection as shown to the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    /// Incremented whenever the collection key is replaced
+    pub key_version: i64,
+    pub members: Vec<CollectionMember>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A member together with the collection key wrapped to their public key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedMember {
+    pub name: String,
+    pub public_key: String,
+    pub wrapped_key: String,
+}
+
+impl WrappedMember {
+    pub fn new(
+        collection_key: &[u8],
+        collection_id: &str,
+        name: &str,
+        public_key: &str,
+    ) -> Result<Self, TeamVaultError> {
+        Ok(Self {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+            wrapped_key: wrap_collection_key(collection_key, collection_id, public_key)?,
+        })
+    }
+}
+
+/// One collection item encrypted under the collection key. Deleted items are
+/// kept as sealed tombstones so deletions sync too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedItem {
+    pub id: String,
+    pub updated_at: DateTime<Utc>,
+    pub sealed: String,
+}
+
+/// Complete state of a collection. This is both what the vault stores locally
+/// and the sync bundle written to a shared file or folder; nothing in it is
+/// readable without a member's private key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionBundle {
+    pub format: String,
+    pub version: u32,
+    pub id: String,
+    pub name: String,
+    pub key_version: i64,
+    pub created_at: DateTime<Utc>,
+    /// When the name or member list last changed
+    pub updated_at: DateTime<Utc>,
+    pub members: Vec<WrappedMember>,
+    /// Public key of the member who last changed the name or member list
+    pub signed_by: String,
+    /// Their signature over the id, name, key version, timestamp and members.
+    /// It shows which member wrote the roster; whether that member may still
+    /// change it is up to the importing vault, see `import_collection`.
+    pub signature: String,
+    pub items: Vec<SealedItem>,
+}
+
+impl CollectionBundle {
+    pub fn summary(&self) -> Collection {
+        Collection {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            key_version: self.key_version,
+            members: self.members
+                .iter()
+                .map(|m| CollectionMember { name: m.name.clone(), public_key: m.public_key.clone() })
+                .collect(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Check the member list was signed by one of its members, then unwrap
+    /// the collection key with `identity`
+    pub fn open_key(&self, identity: &Identity) -> Result<Zeroizing<Vec<u8>>, TeamVaultError> {
+        self.verify_roster()?;
+
+        let public_key = identity.public_key();
+        let member = self.members
+            .iter()
+            .find(|m| m.public_key == public_key)
+            .ok_or_else(|| TeamVaultError::NotMember(self.id.clone()))?;
+
+        unwrap_collection_key(identity, &self.id, &member.wrapped_key)
+    }
+
+    /// Sign the member list as `identity` after changing the name or members
+    pub fn sign_roster(&mut self, identity: &Identity) {
+        self.signed_by = identity.public_key();
+        self.signature = identity.sign(&self.roster_message());
+    }
+
+    fn verify_roster(&self) -> Result<(), TeamVaultError> {
+        let mismatch = || TeamVaultError::RosterMismatch(self.id.clone());
+        if !self.members.iter().any(|m| m.public_key == self.signed_by) {
+            return Err(mismatch());
+        }
+
+        let (_, signing_key) = parse_public_key(&self.signed_by)?;
+        let signature = base64::decode(&self.signature).map_err(|_| mismatch())?;
+        UnparsedPublicKey::new(&ED25519, signing_key)
+            .verify(&self.roster_message(), &signature)
+            .map_err(|_| mismatch())
+    }
+
+    fn roster_message(&self) -> Vec<u8> {
+        let mut members: Vec<&WrappedMember> = self.members.iter().collect();
+        members.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+
+        let key_version = self.key_version.to_string();
+        let updated_at = self.updated_at.to_rfc3339();
+        let header = [
+            self.id.as_str(),
+            self.name.as_str(),
+            key_version.as_str(),
+            updated_at.as_str(),
+            self.signed_by.as_str(),
+        ];
+        let member_fields = members
+            .iter()
+            .flat_map(|m| [m.public_key.as_str(), m.name.as_str(), m.wrapped_key.as_str()]);
+
+        let mut message = ROSTER_CONTEXT.to_vec();
+        for value in header.into_iter().chain(member_fields) {
+            message.extend_from_slice(&(value.len() as u64).to_be_bytes());
+            message.extend_from_slice(value.as_bytes());
+        }
+        message
+    }
+}
+
+pub fn generate_collection_key() -> Zeroizing<Vec<u8>> {
+    let mut key = Zeroizing::new(vec![0u8; 32]);
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+// Split a member public key into its X25519 key and Ed25519 signing key
+fn parse_public_key(public_key: &str) -> Result<(PublicKey, [u8; 32]), TeamVaultError> {
+    let bytes: [u8; 64] = base64::decode(public_key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| TeamVaultError::InvalidPublicKey(public_key.to_string()))?;
+
+    let (agreement, signing) = bytes.split_at(32);
+    Ok((PublicKey::from(<[u8; 32]>::try_from(agreement).unwrap()), signing.try_into().unwrap()))
+}
+
+/// Wrap `collection_key` to `recipient` with an ephemeral X25519 key
+/// agreement, HKDF-SHA256 and AES-256-GCM. Output is
+/// base64(ephemeral public key ‖ nonce ‖ ciphertext).
+pub fn wrap_collection_key(
+    collection_key: &[u8],
+    collection_id: &str,
+    recipient: &str,
+) -> Result<String, TeamVaultError> {
+    let (recipient_key, _) = parse_public_key(recipient)?;
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+
+    let shared = ephemeral.diffie_hellman(&recipient_key);
+    if !shared.was_contributory() {
+        return Err(TeamVaultError::InvalidPublicKey(recipient.to_string()));
+    }
+
+    let wrapping_key = derive_wrapping_key(shared.as_bytes(), &ephemeral_public, &recipient_key);
+    let sealed = seal(&*wrapping_key, collection_key, wrap_aad(collection_id, recipient).as_bytes())?;
+
+    let mut output = ephemeral_public.as_bytes().to_vec();
+    output.extend_from_slice(&sealed);
+    Ok(base64::encode(output))
+}
+
+pub fn unwrap_collection_key(
+    identity: &Identity,
+    collection_id: &str,
+    wrapped: &str,
+) -> Result<Zeroizing<Vec<u8>>, TeamVaultError> {
+    let data = base64::decode(wrapped).map_err(|_| TeamVaultError::Decryption)?;
+    if data.len() < 32 + 12 {
+        return Err(TeamVaultError::Decryption);
+    }
+
+    let (ephemeral, sealed) = data.split_at(32);
+    let ephemeral_public = PublicKey::from(<[u8; 32]>::try_from(ephemeral).unwrap());
+    let own_public = PublicKey::from(&identity.secret);
+
+    let shared = identity.secret.diffie_hellman(&ephemeral_public);
+    let wrapping_key = derive_wrapping_key(shared.as_bytes(), &ephemeral_public, &own_public);
+    open(&*wrapping_key, sealed, wrap_aad(collection_id, &identity.public_key()).as_bytes())
+}
+
+// Both public keys go into the salt so a wrapped key only opens for the
+// exchange it was made for
+fn derive_wrapping_key(shared: &[u8], ephemeral: &PublicKey, recipient: &PublicKey) -> Zeroizing<[u8; 32]> {
+    let mut salt = ephemeral.as_bytes().to_vec();
+    salt.extend_from_slice(recipient.as_bytes());
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf::Salt::new(hkdf::HKDF_SHA256, &salt)
+        .extract(shared)
+        .expand(&[WRAP_INFO], hkdf::HKDF_SHA256)
+        .and_then(|okm| okm.fill(key.as_mut()))
+        .expect("32 bytes is within HKDF limits");
+    key
+}
+
+fn wrap_aad(collection_id: &str, recipient: &str) -> String {
+    format!("collection:{}:member:{}", collection_id, recipient.trim())
+}
+
+/// Encrypt one item, or a tombstone when `entry` is `None`. The id and
+/// timestamp are bound in, so neither can be changed to win a merge.
+pub fn seal_item(
+    collection_key: &[u8],
+    collection_id: &str,
+    item_id: &str,
+    updated_at: DateTime<Utc>,
+    entry: Option<&PasswordEntry>,
+) -> Result<SealedItem, TeamVaultError> {
+    let plaintext = Zeroizing::new(serde_json::to_vec(&entry)?);
+    let aad = item_aad(collection_id, item_id, updated_at);
+
+    Ok(SealedItem {
+        id: item_id.to_string(),
+        updated_at,
+        sealed: base64::encode(seal(collection_key, &plaintext, aad.as_bytes())?),
+    })
+}
+
+pub fn open_item(
+    collection_key: &[u8],
+    collection_id: &str,
+    item: &SealedItem,
+) -> Result<Option<PasswordEntry>, TeamVaultError> {
+    let data = base64::decode(&item.sealed).map_err(|_| TeamVaultError::Decryption)?;
+    let aad = item_aad(collection_id, &item.id, item.updated_at);
+    let plaintext = open(collection_key, &data, aad.as_bytes())?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn item_aad(collection_id: &str, item_id: &str, updated_at: DateTime<Utc>) -> String {
+    format!("collection:{}:item:{}:{}", collection_id, item_id, updated_at.to_rfc3339())
+}
+
+fn seal(key: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, TeamVaultError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| TeamVaultError::Decryption)?;
+
+    let mut output = nonce.to_vec();
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+fn open(key: &[u8], data: &[u8], aad: &[u8]) -> Result<Zeroizing<Vec<u8>>, TeamVaultError> {
+    if data.len() < 12 {
+        return Err(TeamVaultError::Decryption);
+    }
+
+    let (nonce, ciphertext) = data.split_at(12);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|_| TeamVaultError::Decryption)
+}
+
+/// Write `bundle` to `target`. A directory gets `<collection id>.pmcollection`,
+/// so one synced folder can carry several collections.
+pub fn write_bundle(bundle: &CollectionBundle, target: &Path) -> Result<PathBuf, TeamVaultError> {
+    let path = if target.is_dir() {
+        target.join(format!("{}.{}", bundle.id, BUNDLE_EXTENSION))
+    } else {
+        target.to_path_buf()
+    };
+
+    // Sync clients must never pick up a half-written bundle
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, serde_json::to_vec_pretty(bundle)?)?;
+    std::fs::rename(&partial, &path)?;
+
+    Ok(path)
+}
+
+/// Read a bundle file, or every bundle in a directory
+pub fn read_bundles(source: &Path) -> Result<Vec<CollectionBundle>, TeamVaultError> {
+    if !source.is_dir() {
+        return Ok(vec![parse_bundle(&std::fs::read(source)?)?]);
+    }
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(source)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map(|ext| ext == BUNDLE_EXTENSION).unwrap_or(false))
+        .collect();
+    paths.sort();
+
+    paths.iter()
+        .map(|path| parse_bundle(&std::fs::read(path)?))
+        .collect()
+}
+
+fn parse_bundle(data: &[u8]) -> Result<CollectionBundle, TeamVaultError> {
+    let bundle: CollectionBundle = serde_json::from_slice(data)?;
+
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(TeamVaultError::UnsupportedBundle(format!("unknown format {}", bundle.format)));
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(TeamVaultError::UnsupportedBundle(format!(
+            "version {} is newer than supported version {}",
+            bundle.version, BUNDLE_VERSION
+        )));
+    }
+
+    Ok(bundle)
+}
+
+// Tauri commands
+
+#[tauri::command]
+pub async fn get_vault_public_key(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    manager_from_state(&state)?
+        .identity_public_key().await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_team_collection(
+    name: String,
+    member_name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Collection, String> {
+    manager_from_state(&state)?
+        .create_collection(&name, &member_name).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_team_collections(state: tauri::State<'_, AppState>) -> Result<Vec<Collection>, String> {
+    manager_from_state(&state)?
+        .list_collections().await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_team_collection(
+    collection_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    manager_from_state(&state)?
+        .delete_collection(&collection_id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_team_member(
+    collection_id: String,
+    name: String,
+    public_key: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Collection, String> {
+    manager_from_state(&state)?
+        .add_collection_member(&collection_id, &name, &public_key).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_team_member(
+    collection_id: String,
+    public_key: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Collection, String> {
+    manager_from_state(&state)?
+        .remove_collection_member(&collection_id, &public_key).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn store_team_item(
+    collection_id: String,
+    entry: PasswordEntry,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    manager_from_state(&state)?
+        .store_collection_item(&collection_id, &entry).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_team_items(
+    collection_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PasswordEntry>, String> {
+    manager_from_state(&state)?
+        .list_collection_items(&collection_id).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_team_item(
+    collection_id: String,
+    item_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<bool, String> {
+    manager_from_state(&state)?
+        .delete_collection_item(&collection_id, &item_id).await
+        .map_err(|e| e.to_string())
+}
+
+/// Write the collection to a file, or into a shared folder; returns the path written
+#[tauri::command]
+pub async fn export_team_collection(
+    collection_id: String,
+    path: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let bundle = manager_from_state(&state)?
+        .export_collection(&collection_id).await
+        .map_err(|e| e.to_string())?;
+
+    write_bundle(&bundle, Path::new(&path))
+        .map(|written| written.display().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Merge a bundle file, or every bundle in a shared folder, into the vault
+#[tauri::command]
+pub async fn import_team_collections(
+    path: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<Collection>, String> {
+    let manager = manager_from_state(&state)?;
+    let bundles = read_bundles(Path::new(&path)).map_err(|e| e.to_string())?;
+
+    let mut imported = Vec::new();
+    for bundle in bundles {
+        match manager.import_collection(&bundle).await {
+            Ok(collection) => imported.push(collection),
+            // Collections this vault was never (or is no longer) part of
+            Err(e) => log::warn!("Skipping collection bundle {}: {}", bundle.id, e),
+        }
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autofill::MatchMode;
+
+    fn bundle_for(members: &[(&str, &Identity)], key: &[u8]) -> CollectionBundle {
+        let now = Utc::now();
+        let mut bundle = CollectionBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            id: "ops".to_string(),
+            name: "Ops".to_string(),
+            key_version: 1,
+            created_at: now,
+            updated_at: now,
+            members: members
+                .iter()
+                .map(|(name, identity)| WrappedMember::new(key, "ops", name, &identity.public_key()).unwrap())
+                .collect(),
+            signed_by: String::new(),
+            signature: String::new(),
+            items: Vec::new(),
+        };
+        bundle.sign_roster(members[0].1);
+        bundle
+    }
+
+    #[test]
+    fn test_key_wrapping_per_member() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+        let mallory = Identity::generate();
+        let key = generate_collection_key();
+
+        let bundle = bundle_for(&[("alice", &alice), ("bob", &bob)], &key);
+        assert_eq!(*bundle.open_key(&alice).unwrap(), *key);
+        assert_eq!(*bundle.open_key(&bob).unwrap(), *key);
+        assert!(matches!(bundle.open_key(&mallory), Err(TeamVaultError::NotMember(_))));
+
+        // A wrapped key is bound to its collection and recipient
+        let wrapped = &bundle.members[0].wrapped_key;
+        assert!(unwrap_collection_key(&alice, "other", wrapped).is_err());
+        assert!(unwrap_collection_key(&bob, "ops", wrapped).is_err());
+    }
+
+    #[test]
+    fn test_identity_round_trip() {
+        let identity = Identity::generate();
+        let restored = Identity::from_bytes(identity.to_bytes().as_ref()).unwrap();
+        assert_eq!(identity.public_key(), restored.public_key());
+        assert!(Identity::from_bytes(&[1, 2, 3]).is_err());
+        assert!(matches!(
+            wrap_collection_key(&generate_collection_key(), "ops", "not a key"),
+            Err(TeamVaultError::InvalidPublicKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_roster_tampering_is_detected() {
+        let alice = Identity::generate();
+        let mallory = Identity::generate();
+        let key = generate_collection_key();
+        let mut bundle = bundle_for(&[("alice", &alice)], &key);
+
+        // Knowing the collection key is not enough to add a member
+        bundle.members.push(WrappedMember::new(&key, "ops", "mallory", &mallory.public_key()).unwrap());
+        assert!(matches!(bundle.open_key(&alice), Err(TeamVaultError::RosterMismatch(_))));
+
+        // ...and a signature only counts from someone on the list it signs
+        bundle.members.pop();
+        bundle.sign_roster(&mallory);
+        assert!(matches!(bundle.open_key(&alice), Err(TeamVaultError::RosterMismatch(_))));
+
+        let mut renamed = bundle_for(&[("alice", &alice)], &key);
+        renamed.key_version = 0;
+        assert!(matches!(renamed.open_key(&alice), Err(TeamVaultError::RosterMismatch(_))));
+    }
+
+    #[test]
+    fn test_items_are_bound_to_id_and_timestamp() {
+        let key = generate_collection_key();
+        let entry = PasswordEntry {
+            id: "db".to_string(),
+            title: "Database".to_string(),
+            username: "admin".to_string(),
+            password: "s3cret".to_string(),
+            url: None,
+            notes: None,
+            folder: None,
+            tags: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_used: None,
+            is_favorite: false,
+            totp_secret: None,
+            match_mode: MatchMode::default(),
+        };
+
+        let sealed = seal_item(&key, "ops", "db", Utc::now(), Some(&entry)).unwrap();
+        assert_eq!(open_item(&key, "ops", &sealed).unwrap().unwrap().password, "s3cret");
+
+        let mut bumped = sealed.clone();
+        bumped.updated_at = Utc::now() + chrono::Duration::days(1);
+        assert!(open_item(&key, "ops", &bumped).is_err());
+
+        let tombstone = seal_item(&key, "ops", "db", Utc::now(), None).unwrap();
+        assert!(open_item(&key, "ops", &tombstone).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_bundle_files_and_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let alice = Identity::generate();
+        let key = generate_collection_key();
+        let bundle = bundle_for(&[("alice", &alice)], &key);
+
+        let written = write_bundle(&bundle, dir.path()).unwrap();
+        assert_eq!(written, dir.path().join("ops.pmcollection"));
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let read = read_bundles(dir.path()).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(*read[0].open_key(&alice).unwrap(), *key);
+        assert_eq!(read_bundles(&written).unwrap().len(), 1);
+
+        std::fs::write(&written, r#"{"format":"other"}"#).unwrap();
+        assert!(read_bundles(&written).is_err());
+    }
+}
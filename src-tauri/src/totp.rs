@@ -0,0 +1,305 @@
+// src-tauri/src/totp.rs
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+#[derive(Error, Debug)]
+pub enum TotpError {
+    #[error("Invalid otpauth URI: {0}")]
+    InvalidUri(String),
+    #[error("Invalid base32 secret")]
+    InvalidSecret,
+    #[error("Unsupported TOTP parameter: {0}")]
+    Unsupported(String),
+    #[error("Entry has no TOTP secret")]
+    NotConfigured,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    fn hmac_algorithm(self) -> hmac::Algorithm {
+        match self {
+            TotpAlgorithm::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            TotpAlgorithm::Sha256 => hmac::HMAC_SHA256,
+            TotpAlgorithm::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// Current one-time code for an entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpCode {
+    pub code: String,
+    pub seconds_remaining: u64,
+    pub period: u64,
+}
+
+/// Decoded TOTP parameters. The shared secret is wiped when this is dropped.
+pub struct Totp {
+    secret: Zeroizing<Vec<u8>>,
+    pub algorithm: TotpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+impl Totp {
+    /// Parse an `otpauth://totp/...` URI or a bare base32 secret
+    pub fn parse(value: &str) -> Result<Self, TotpError> {
+        let value = value.trim();
+
+        if value.to_ascii_lowercase().starts_with("otpauth://") {
+            Self::from_uri(value)
+        } else {
+            Ok(Self {
+                secret: decode_base32(value)?,
+                algorithm: TotpAlgorithm::Sha1,
+                digits: DEFAULT_DIGITS,
+                period: DEFAULT_PERIOD,
+                issuer: None,
+                account: None,
+            })
+        }
+    }
+
+    fn from_uri(uri: &str) -> Result<Self, TotpError> {
+        let parsed = url::Url::parse(uri).map_err(|e| TotpError::InvalidUri(e.to_string()))?;
+
+        match parsed.host_str() {
+            Some(kind) if kind.eq_ignore_ascii_case("totp") => {}
+            Some(kind) => return Err(TotpError::Unsupported(format!("{} (only totp is supported)", kind))),
+            None => return Err(TotpError::InvalidUri("missing otp type".to_string())),
+        }
+
+        // The label is "Issuer:account" or just "account"
+        let label = percent_decode(parsed.path().trim_start_matches('/'));
+        let (label_issuer, account) = match label.split_once(':') {
+            Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+            None => (None, label.trim().to_string()),
+        };
+
+        let mut secret = None;
+        let mut issuer = label_issuer;
+        let mut algorithm = TotpAlgorithm::Sha1;
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+
+        for (key, value) in parsed.query_pairs() {
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => secret = Some(decode_base32(&value)?),
+                "issuer" => issuer = Some(value.into_owned()),
+                "algorithm" => {
+                    algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => TotpAlgorithm::Sha1,
+                        "SHA256" => TotpAlgorithm::Sha256,
+                        "SHA512" => TotpAlgorithm::Sha512,
+                        other => return Err(TotpError::Unsupported(format!("algorithm {}", other))),
+                    }
+                }
+                "digits" => {
+                    digits = value.parse()
+                        .ok()
+                        .filter(|d| (6..=8).contains(d))
+                        .ok_or_else(|| TotpError::Unsupported(format!("digits {}", value)))?;
+                }
+                "period" => {
+                    period = value.parse()
+                        .ok()
+                        .filter(|p| *p > 0)
+                        .ok_or_else(|| TotpError::Unsupported(format!("period {}", value)))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            secret: secret.ok_or_else(|| TotpError::InvalidUri("missing secret".to_string()))?,
+            algorithm,
+            digits,
+            period,
+            issuer,
+            account: Some(account).filter(|a| !a.is_empty()),
+        })
+    }
+
+    /// Code for the given Unix time, per RFC 6238
+    pub fn generate_at(&self, unix_time: u64) -> String {
+        let counter = unix_time / self.period;
+        let key = hmac::Key::new(self.algorithm.hmac_algorithm(), &self.secret);
+        let tag = hmac::sign(&key, &counter.to_be_bytes());
+        let digest = tag.as_ref();
+
+        // Dynamic truncation (RFC 4226 section 5.3)
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+
+        let code = binary % 10u32.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    pub fn current(&self) -> TotpCode {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        TotpCode {
+            code: self.generate_at(now),
+            seconds_remaining: self.period - (now % self.period),
+            period: self.period,
+        }
+    }
+}
+
+/// RFC 4648 base32, case-insensitive, ignoring spaces, dashes and padding
+fn decode_base32(input: &str) -> Result<Zeroizing<Vec<u8>>, TotpError> {
+    let mut output = Zeroizing::new(Vec::with_capacity(input.len() * 5 / 8));
+    let mut buffer: u64 = 0;
+    let mut bits = 0u32;
+
+    for c in input.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return Err(TotpError::InvalidSecret),
+        };
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if output.is_empty() {
+        return Err(TotpError::InvalidSecret);
+    }
+
+    Ok(output)
+}
+
+// `Url::path()` leaves the label percent-encoded
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc_totp(seed: &[u8], algorithm: TotpAlgorithm) -> Totp {
+        Totp {
+            secret: Zeroizing::new(seed.to_vec()),
+            algorithm,
+            digits: 8,
+            period: 30,
+            issuer: None,
+            account: None,
+        }
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let sha1 = rfc_totp(b"12345678901234567890", TotpAlgorithm::Sha1);
+        let sha256 = rfc_totp(b"12345678901234567890123456789012", TotpAlgorithm::Sha256);
+        let sha512 = rfc_totp(
+            b"1234567890123456789012345678901234567890123456789012345678901234",
+            TotpAlgorithm::Sha512,
+        );
+
+        // RFC 6238 Appendix B
+        let vectors: &[(u64, &str, &str, &str)] = &[
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+
+        for (time, expected_sha1, expected_sha256, expected_sha512) in vectors {
+            assert_eq!(sha1.generate_at(*time), *expected_sha1, "SHA1 at {}", time);
+            assert_eq!(sha256.generate_at(*time), *expected_sha256, "SHA256 at {}", time);
+            assert_eq!(sha512.generate_at(*time), *expected_sha512, "SHA512 at {}", time);
+        }
+    }
+
+    #[test]
+    fn test_parse_otpauth_uri() {
+        // "12345678901234567890" in base32
+        let totp = Totp::parse(
+            "otpauth://totp/ACME%20Co:ops@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co&algorithm=SHA1&digits=8&period=30",
+        ).unwrap();
+
+        assert_eq!(totp.issuer.as_deref(), Some("ACME Co"));
+        assert_eq!(totp.account.as_deref(), Some("ops@example.com"));
+        assert_eq!(totp.digits, 8);
+        assert_eq!(totp.generate_at(59), "94287082");
+    }
+
+    #[test]
+    fn test_parse_bare_secret() {
+        let totp = Totp::parse("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+        assert_eq!(totp.digits, DEFAULT_DIGITS);
+        assert_eq!(totp.generate_at(59), "287082");
+    }
+
+    #[test]
+    fn test_invalid_input_is_rejected() {
+        assert!(matches!(Totp::parse("not base32!"), Err(TotpError::InvalidSecret)));
+        assert!(matches!(Totp::parse("otpauth://hotp/x?secret=GEZDGNBV"), Err(TotpError::Unsupported(_))));
+        assert!(matches!(Totp::parse("otpauth://totp/x?issuer=y"), Err(TotpError::InvalidUri(_))));
+        assert!(matches!(
+            Totp::parse("otpauth://totp/x?secret=GEZDGNBV&digits=12"),
+            Err(TotpError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_seconds_remaining_within_period() {
+        let code = Totp::parse("GEZDGNBVGY3TQOJQ").unwrap().current();
+        assert!(code.seconds_remaining >= 1 && code.seconds_remaining <= code.period);
+        assert_eq!(code.code.len(), 6);
+    }
+}
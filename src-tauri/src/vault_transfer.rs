@@ -0,0 +1,979 @@
+// src-tauri/src/vault_transfer.rs
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::password_hash::rand_core::RngCore;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+use crate::autofill::MatchMode;
+use crate::password_manager::{
+    manager_from_state, KdfCost, PasswordEntry, PasswordManager, PasswordManagerError, SecureNote,
+};
+use crate::totp::Totp;
+use crate::AppState;
+
+const ARCHIVE_FORMAT: &str = "pwa-marketplace-vault";
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Upper bounds on the Argon2 cost an archive may ask for, so a crafted file
+/// cannot exhaust memory or CPU before the passphrase is even checked
+const MAX_ARCHIVE_KDF: KdfCost = KdfCost {
+    memory_kib: 1024 * 1024,
+    iterations: 10 * KdfCost::RECOMMENDED.iterations,
+    parallelism: 10 * KdfCost::RECOMMENDED.parallelism,
+};
+
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("Vault error: {0}")]
+    Vault(#[from] PasswordManagerError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+    #[error("Wrong passphrase or corrupted archive")]
+    InvalidPassphrase,
+    #[error("A passphrase is required for vault archives")]
+    PassphraseRequired,
+    #[error("Unsupported import: {0}")]
+    UnsupportedFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Passphrase-encrypted archive written by `export_vault`
+    Archive,
+    BitwardenJson,
+    BitwardenCsv,
+    /// KeePassXC and KeePass 2 CSV exports
+    KeePassCsv,
+    /// Any other CSV with a header row, e.g. browser password exports
+    GenericCsv,
+}
+
+/// What to do with an imported item that already exists in the vault
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    #[default]
+    Skip,
+    Overwrite,
+    KeepBoth,
+}
+
+/// CSV column names for each entry field. Unset fields are not imported.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FieldMapping {
+    pub title: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub folder: Option<String>,
+    pub totp: Option<String>,
+}
+
+// Header names used by the managers we import from, most specific first
+const TITLE_COLUMNS: &[&str] = &["title", "name", "account"];
+const USERNAME_COLUMNS: &[&str] = &["username", "login_username", "login name", "user", "login", "email"];
+const PASSWORD_COLUMNS: &[&str] = &["password", "login_password"];
+const URL_COLUMNS: &[&str] = &["url", "login_uri", "web site", "website", "uri"];
+const NOTES_COLUMNS: &[&str] = &["notes", "note", "comments", "extra"];
+const FOLDER_COLUMNS: &[&str] = &["folder", "group", "grouping", "category"];
+const TOTP_COLUMNS: &[&str] = &["totp", "login_totp", "otpauth", "otp"];
+
+impl FieldMapping {
+    /// Guess the mapping from a CSV header row
+    pub fn detect(headers: &[String]) -> Self {
+        let find = |aliases: &[&str]| {
+            aliases.iter().find_map(|alias| {
+                headers.iter().find(|h| h.trim().eq_ignore_ascii_case(alias)).cloned()
+            })
+        };
+
+        Self {
+            title: find(TITLE_COLUMNS),
+            username: find(USERNAME_COLUMNS),
+            password: find(PASSWORD_COLUMNS),
+            url: find(URL_COLUMNS),
+            notes: find(NOTES_COLUMNS),
+            folder: find(FOLDER_COLUMNS),
+            totp: find(TOTP_COLUMNS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    pub headers: Vec<String>,
+    pub mapping: Option<FieldMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub format: ImportFormat,
+    pub entries_imported: usize,
+    pub notes_imported: usize,
+    /// Titles of items that matched something already in the vault
+    pub duplicates: Vec<String>,
+    /// Rows or items that could not be imported, with the reason
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct ParsedImport {
+    entries: Vec<PasswordEntry>,
+    notes: Vec<SecureNote>,
+    skipped: Vec<String>,
+}
+
+impl ParsedImport {
+    /// Queue an entry, skipping it if the vault would reject its TOTP secret
+    fn push_entry(&mut self, entry: PasswordEntry) {
+        match entry.totp_secret.as_deref().map(Totp::parse) {
+            Some(Err(e)) => self.skipped.push(format!("{}: invalid TOTP secret ({})", entry.title, e)),
+            _ => self.entries.push(entry),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchiveEnvelope {
+    format: String,
+    version: u32,
+    kdf: KdfCost,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchivePayload {
+    exported_at: DateTime<Utc>,
+    entries: Vec<PasswordEntry>,
+    notes: Vec<SecureNote>,
+}
+
+/// Export every password entry and secure note as a passphrase-encrypted archive
+pub async fn export_vault(
+    manager: &PasswordManager,
+    passphrase: &str,
+    cost: KdfCost,
+) -> Result<String, TransferError> {
+    let payload = ArchivePayload {
+        exported_at: Utc::now(),
+        entries: manager.list_passwords(None).await?,
+        notes: manager.list_notes(None, None).await?,
+    };
+
+    let archive = seal_archive(&payload, passphrase, cost)?;
+    log::info!(
+        "Exported {} entries and {} notes",
+        payload.entries.len(), payload.notes.len()
+    );
+    Ok(archive)
+}
+
+/// Import `data` into the vault. The format is detected when not given.
+pub async fn import_vault(
+    manager: &PasswordManager,
+    data: &str,
+    format: Option<ImportFormat>,
+    passphrase: Option<&str>,
+    mapping: Option<FieldMapping>,
+    policy: DuplicatePolicy,
+) -> Result<ImportReport, TransferError> {
+    let format = match format {
+        Some(format) => format,
+        None => detect_format(data)?,
+    };
+
+    let parsed = match format {
+        ImportFormat::Archive => {
+            let passphrase = passphrase.ok_or(TransferError::PassphraseRequired)?;
+            let payload = open_archive(data, passphrase)?;
+            let mut parsed = ParsedImport { notes: payload.notes, ..ParsedImport::default() };
+            for entry in payload.entries {
+                parsed.push_entry(entry);
+            }
+            parsed
+        }
+        ImportFormat::BitwardenJson => parse_bitwarden_json(data)?,
+        ImportFormat::BitwardenCsv | ImportFormat::KeePassCsv | ImportFormat::GenericCsv => {
+            parse_csv(data, format, mapping)?
+        }
+    };
+
+    let report = store_imported(manager, parsed, format, policy).await?;
+    log::info!(
+        "Imported {} entries and {} notes from {:?} ({} duplicates, {} skipped)",
+        report.entries_imported, report.notes_imported, format,
+        report.duplicates.len(), report.skipped.len()
+    );
+    Ok(report)
+}
+
+/// Work out which manager produced `data`
+pub fn detect_format(data: &str) -> Result<ImportFormat, TransferError> {
+    let trimmed = data.trim_start_matches('\u{feff}').trim_start();
+
+    if trimmed.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(trimmed)?;
+        if value.get("format").and_then(|f| f.as_str()) == Some(ARCHIVE_FORMAT) {
+            return Ok(ImportFormat::Archive);
+        }
+        if value.get("items").map(|items| items.is_array()).unwrap_or(false) {
+            return Ok(ImportFormat::BitwardenJson);
+        }
+        return Err(TransferError::UnsupportedFormat("unrecognised JSON export".to_string()));
+    }
+
+    let headers = csv_headers(trimmed)?;
+    let has = |name: &str| headers.iter().any(|h| h.eq_ignore_ascii_case(name));
+
+    if has("login_password") && has("login_uri") {
+        Ok(ImportFormat::BitwardenCsv)
+    } else if (has("group") && has("title")) || (has("account") && has("login name")) {
+        Ok(ImportFormat::KeePassCsv)
+    } else if FieldMapping::detect(&headers).password.is_some() {
+        Ok(ImportFormat::GenericCsv)
+    } else {
+        Err(TransferError::UnsupportedFormat("no password column found".to_string()))
+    }
+}
+
+/// Detect the format and, for CSV files, the header row and guessed mapping
+pub fn preview_import(data: &str) -> Result<ImportPreview, TransferError> {
+    let format = detect_format(data)?;
+
+    match format {
+        ImportFormat::Archive | ImportFormat::BitwardenJson => {
+            Ok(ImportPreview { format, headers: Vec::new(), mapping: None })
+        }
+        _ => {
+            let headers = csv_headers(data)?;
+            let mapping = FieldMapping::detect(&headers);
+            Ok(ImportPreview { format, headers, mapping: Some(mapping) })
+        }
+    }
+}
+
+fn seal_archive(payload: &ArchivePayload, passphrase: &str, cost: KdfCost) -> Result<String, TransferError> {
+    if passphrase.is_empty() {
+        return Err(TransferError::PassphraseRequired);
+    }
+
+    let mut salt = [0u8; 16];
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let key = archive_key(passphrase, &salt, cost)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let plaintext = Zeroizing::new(serde_json::to_vec(payload)?);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_slice())
+        .map_err(|e| TransferError::Encryption(e.to_string()))?;
+
+    let envelope = ArchiveEnvelope {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        kdf: cost,
+        salt: base64::encode(salt),
+        nonce: base64::encode(nonce_bytes),
+        ciphertext: base64::encode(ciphertext),
+    };
+
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+fn open_archive(data: &str, passphrase: &str) -> Result<ArchivePayload, TransferError> {
+    let envelope: ArchiveEnvelope = serde_json::from_str(data.trim_start_matches('\u{feff}'))?;
+
+    if envelope.format != ARCHIVE_FORMAT {
+        return Err(TransferError::UnsupportedFormat(format!("unknown archive format {}", envelope.format)));
+    }
+    if envelope.version > ARCHIVE_VERSION {
+        return Err(TransferError::UnsupportedFormat(format!(
+            "archive version {} is newer than this build supports ({})",
+            envelope.version, ARCHIVE_VERSION
+        )));
+    }
+    if envelope.kdf.memory_kib > MAX_ARCHIVE_KDF.memory_kib
+        || envelope.kdf.iterations > MAX_ARCHIVE_KDF.iterations
+        || envelope.kdf.parallelism > MAX_ARCHIVE_KDF.parallelism
+    {
+        return Err(TransferError::UnsupportedFormat("archive KDF cost is out of range".to_string()));
+    }
+
+    let decode = |field: &str| base64::decode(field).map_err(|_| TransferError::InvalidPassphrase);
+    let salt = decode(&envelope.salt)?;
+    let nonce_bytes = decode(&envelope.nonce)?;
+    let ciphertext = decode(&envelope.ciphertext)?;
+    if nonce_bytes.len() != 12 {
+        return Err(TransferError::InvalidPassphrase);
+    }
+
+    let key = archive_key(passphrase, &salt, envelope.kdf)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce_bytes), ciphertext.as_slice())
+        .map(Zeroizing::new)
+        .map_err(|_| TransferError::InvalidPassphrase)?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn archive_key(passphrase: &str, salt: &[u8], cost: KdfCost) -> Result<Zeroizing<Vec<u8>>, TransferError> {
+    let mut key = Zeroizing::new(vec![0u8; 32]);
+
+    cost.argon2()?
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| TransferError::Encryption(e.to_string()))?;
+
+    Ok(key)
+}
+
+fn csv_headers(data: &str) -> Result<Vec<String>, TransferError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(data.trim_start_matches('\u{feff}').as_bytes());
+
+    Ok(reader.headers()?.iter().map(|h| h.trim().to_string()).collect())
+}
+
+fn parse_csv(
+    data: &str,
+    format: ImportFormat,
+    mapping: Option<FieldMapping>,
+) -> Result<ParsedImport, TransferError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::Headers)
+        .from_reader(data.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    let mapping = mapping.unwrap_or_else(|| FieldMapping::detect(&headers));
+
+    let column = |name: &Option<String>| {
+        name.as_ref().and_then(|name| headers.iter().position(|h| h.eq_ignore_ascii_case(name)))
+    };
+    let title_col = column(&mapping.title);
+    let username_col = column(&mapping.username);
+    let password_col = column(&mapping.password)
+        .ok_or_else(|| TransferError::UnsupportedFormat("no password column mapped".to_string()))?;
+    let url_col = column(&mapping.url);
+    let notes_col = column(&mapping.notes);
+    let folder_col = column(&mapping.folder);
+    let totp_col = column(&mapping.totp);
+    let type_col = match format {
+        ImportFormat::BitwardenCsv => headers.iter().position(|h| h == "type"),
+        _ => None,
+    };
+    let favorite_col = match format {
+        ImportFormat::BitwardenCsv => headers.iter().position(|h| h == "favorite"),
+        _ => None,
+    };
+
+    let mut parsed = ParsedImport::default();
+    let now = Utc::now();
+
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+        };
+        // Passwords and notes are kept byte for byte, whitespace included
+        let trimmed = |col: Option<usize>| {
+            field(col)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let row = index + 2;
+
+        let title = trimmed(title_col);
+        let notes = field(notes_col);
+        let folder = trimmed(folder_col);
+
+        if field(type_col).as_deref() == Some("note") {
+            parsed.notes.push(SecureNote {
+                id: Uuid::new_v4().to_string(),
+                title: title.unwrap_or_else(|| format!("Imported note {}", row)),
+                content: notes.unwrap_or_default(),
+                folder,
+                tags: Vec::new(),
+                created_at: now,
+                updated_at: now,
+            });
+            continue;
+        }
+
+        let username = trimmed(username_col);
+        let password = field(Some(password_col));
+        let url = trimmed(url_col);
+
+        if password.is_none() && username.is_none() {
+            parsed.skipped.push(format!("Row {}: no username or password", row));
+            continue;
+        }
+
+        parsed.push_entry(PasswordEntry {
+            id: Uuid::new_v4().to_string(),
+            title: title
+                .or_else(|| url.as_deref().and_then(url_host))
+                .unwrap_or_else(|| format!("Imported entry {}", row)),
+            username: username.unwrap_or_default(),
+            password: password.unwrap_or_default(),
+            url,
+            notes,
+            folder,
+            tags: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            last_used: None,
+            is_favorite: field(favorite_col).as_deref() == Some("1"),
+            totp_secret: field(totp_col),
+            match_mode: MatchMode::default(),
+        });
+    }
+
+    Ok(parsed)
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(rename = "type")]
+    item_type: u8,
+    name: String,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    login: Option<BitwardenLogin>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<String>,
+    #[serde(default)]
+    uris: Option<Vec<BitwardenUri>>,
+    totp: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+// Bitwarden item types
+const BITWARDEN_LOGIN: u8 = 1;
+const BITWARDEN_SECURE_NOTE: u8 = 2;
+
+fn parse_bitwarden_json(data: &str) -> Result<ParsedImport, TransferError> {
+    let export: BitwardenExport = serde_json::from_str(data.trim_start_matches('\u{feff}'))?;
+
+    if export.encrypted {
+        return Err(TransferError::UnsupportedFormat(
+            "encrypted Bitwarden exports are not supported, export as unencrypted JSON".to_string(),
+        ));
+    }
+
+    let folders: HashMap<String, String> = export.folders
+        .into_iter()
+        .map(|folder| (folder.id, folder.name))
+        .collect();
+
+    let mut parsed = ParsedImport::default();
+    let now = Utc::now();
+
+    for item in export.items {
+        let folder = item.folder_id.as_ref().and_then(|id| folders.get(id)).cloned();
+
+        match item.item_type {
+            BITWARDEN_LOGIN => {
+                let login = item.login.unwrap_or(BitwardenLogin { username: None, password: None, uris: None, totp: None });
+                let url = login.uris
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find_map(|uri| uri.uri);
+
+                parsed.push_entry(PasswordEntry {
+                    id: Uuid::new_v4().to_string(),
+                    title: item.name,
+                    username: login.username.unwrap_or_default(),
+                    password: login.password.unwrap_or_default(),
+                    url,
+                    notes: item.notes,
+                    folder,
+                    tags: Vec::new(),
+                    created_at: now,
+                    updated_at: now,
+                    last_used: None,
+                    is_favorite: item.favorite,
+                    totp_secret: login.totp,
+                    match_mode: MatchMode::default(),
+                });
+            }
+            BITWARDEN_SECURE_NOTE => {
+                parsed.notes.push(SecureNote {
+                    id: Uuid::new_v4().to_string(),
+                    title: item.name,
+                    content: item.notes.unwrap_or_default(),
+                    folder,
+                    tags: Vec::new(),
+                    created_at: now,
+                    updated_at: now,
+                });
+            }
+            other => {
+                parsed.skipped.push(format!("{}: unsupported Bitwarden item type {}", item.name, other));
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+async fn store_imported(
+    manager: &PasswordManager,
+    parsed: ParsedImport,
+    format: ImportFormat,
+    policy: DuplicatePolicy,
+) -> Result<ImportReport, TransferError> {
+    let mut report = ImportReport {
+        format,
+        entries_imported: 0,
+        notes_imported: 0,
+        duplicates: Vec::new(),
+        skipped: parsed.skipped,
+    };
+
+    let existing = manager.list_passwords(None).await?;
+    let mut existing_ids: HashSet<String> = existing.iter().map(|e| e.id.clone()).collect();
+    let created: HashMap<String, DateTime<Utc>> = existing.iter().map(|e| (e.id.clone(), e.created_at)).collect();
+    let mut by_login: HashMap<LoginKey, String> = existing
+        .iter()
+        .filter_map(|e| duplicate_key(e).map(|key| (key, e.id.clone())))
+        .collect();
+
+    for mut entry in parsed.entries {
+        let key = duplicate_key(&entry);
+        let existing_id = key.as_ref()
+            .and_then(|key| by_login.get(key).cloned())
+            .or_else(|| existing_ids.contains(&entry.id).then(|| entry.id.clone()));
+
+        if let Some(existing_id) = existing_id {
+            report.duplicates.push(entry.title.clone());
+            match policy {
+                DuplicatePolicy::Skip => continue,
+                DuplicatePolicy::Overwrite => {
+                    if let Some(created_at) = created.get(&existing_id) {
+                        entry.created_at = *created_at;
+                    }
+                    entry.id = existing_id;
+                }
+                DuplicatePolicy::KeepBoth => entry.id = Uuid::new_v4().to_string(),
+            }
+        }
+
+        // One bad row shouldn't abort the rest of the import without a report
+        if let Err(e) = manager.store_password(&entry).await {
+            report.skipped.push(format!("{}: {}", entry.title, e));
+            continue;
+        }
+        if let Some(key) = key {
+            by_login.entry(key).or_insert_with(|| entry.id.clone());
+        }
+        existing_ids.insert(entry.id);
+        report.entries_imported += 1;
+    }
+
+    let existing_notes = manager.list_notes(None, None).await?;
+    let mut note_ids: HashSet<String> = existing_notes.iter().map(|n| n.id.clone()).collect();
+    let note_created: HashMap<String, DateTime<Utc>> = existing_notes.iter().map(|n| (n.id.clone(), n.created_at)).collect();
+    let mut note_contents: HashMap<(String, String), String> = existing_notes
+        .into_iter()
+        .map(|n| ((n.title, n.content), n.id))
+        .collect();
+
+    for mut note in parsed.notes {
+        let key = (note.title.clone(), note.content.clone());
+        let existing_id = note_contents.get(&key).cloned()
+            .or_else(|| note_ids.contains(&note.id).then(|| note.id.clone()));
+
+        if let Some(existing_id) = existing_id {
+            report.duplicates.push(note.title.clone());
+            match policy {
+                DuplicatePolicy::Skip => continue,
+                DuplicatePolicy::Overwrite => {
+                    if let Some(created_at) = note_created.get(&existing_id) {
+                        note.created_at = *created_at;
+                    }
+                    note.id = existing_id;
+                }
+                DuplicatePolicy::KeepBoth => note.id = Uuid::new_v4().to_string(),
+            }
+        }
+
+        if let Err(e) = manager.store_note(&note).await {
+            report.skipped.push(format!("{}: {}", note.title, e));
+            continue;
+        }
+        note_contents.entry(key).or_insert_with(|| note.id.clone());
+        note_ids.insert(note.id);
+        report.notes_imported += 1;
+    }
+
+    Ok(report)
+}
+
+/// What makes two imported entries the same login
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum LoginKey {
+    /// Site host and username
+    Site(String, String),
+    /// Title and username, for entries without a URL
+    Titled(String, String),
+}
+
+/// Entries are the same login when the site host and username match. Entries
+/// without a URL only match on title as well, so shared usernames like `root`
+/// on different servers stay separate.
+fn duplicate_key(entry: &PasswordEntry) -> Option<LoginKey> {
+    let host = entry.url.as_deref().and_then(url_host).unwrap_or_default();
+    let username = entry.username.trim().to_lowercase();
+
+    if !host.is_empty() {
+        return Some(LoginKey::Site(host, username));
+    }
+
+    let title = entry.title.trim().to_lowercase();
+    if title.is_empty() && username.is_empty() {
+        None
+    } else {
+        Some(LoginKey::Titled(title, username))
+    }
+}
+
+pub(crate) fn url_host(raw: &str) -> Option<String> {
+    let parsed = url::Url::parse(raw)
+        .or_else(|_| url::Url::parse(&format!("https://{}", raw)))
+        .ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+#[tauri::command]
+pub async fn export_vault_archive(
+    path: String,
+    passphrase: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = manager_from_state(&state)?;
+    let cost = state.config.lock().unwrap()
+        .as_ref()
+        .map(|config| config.vault.kdf)
+        .unwrap_or_default();
+
+    let archive = export_vault(&manager, &passphrase, cost).await
+        .map_err(|e| e.to_string())?;
+    std::fs::write(&path, archive).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn preview_vault_import(path: String) -> Result<ImportPreview, String> {
+    let data = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    preview_import(&data).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn import_vault_file(
+    path: String,
+    format: Option<ImportFormat>,
+    passphrase: Option<String>,
+    mapping: Option<FieldMapping>,
+    on_duplicate: Option<DuplicatePolicy>,
+    state: tauri::State<'_, AppState>,
+) -> Result<ImportReport, String> {
+    let manager = manager_from_state(&state)?;
+    let data = Zeroizing::new(std::fs::read_to_string(&path).map_err(|e| e.to_string())?);
+
+    import_vault(
+        &manager,
+        &data,
+        format,
+        passphrase.as_deref(),
+        mapping,
+        on_duplicate.unwrap_or_default(),
+    ).await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_COST: KdfCost = KdfCost { memory_kib: 1024, iterations: 1, parallelism: 1 };
+
+    const BITWARDEN_CSV: &str = "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+        Work,1,login,GitLab,,,0,https://gitlab.example.com,alice,s3cret,GEZDGNBVGY3TQOJQ\n\
+        ,,note,Recovery codes,1111 2222 3333,,0,,,,\n";
+
+    const KEEPASS_CSV: &str = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\"\n\
+        \"Root/Servers\",\"Staging DB\",\"postgres\",\"pg-pass\",\"https://db.example.com\",\"read replica\"\n";
+
+    const CHROME_CSV: &str = "name,url,username,password\n\
+        example.com,https://www.example.com/login,bob,hunter2\n\
+        empty,https://empty.example.com,,\n";
+
+    const BITWARDEN_JSON: &str = r#"{
+        "encrypted": false,
+        "folders": [{ "id": "f1", "name": "Infra" }],
+        "items": [
+            { "type": 1, "name": "AWS", "folderId": "f1", "favorite": true, "notes": null,
+              "login": { "username": "root", "password": "aws-pass", "uris": [{ "uri": "https://aws.amazon.com" }] } },
+            { "type": 2, "name": "API keys", "notes": "key=abc", "folderId": null },
+            { "type": 3, "name": "Visa", "notes": null }
+        ]
+    }"#;
+
+    fn entry(id: &str, url: &str, username: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: username.to_string(),
+            password: "pw".to_string(),
+            url: Some(url.to_string()),
+            notes: None,
+            folder: None,
+            tags: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_used: None,
+            is_favorite: false,
+            totp_secret: None,
+            match_mode: MatchMode::default(),
+        }
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format(BITWARDEN_CSV).unwrap(), ImportFormat::BitwardenCsv);
+        assert_eq!(detect_format(KEEPASS_CSV).unwrap(), ImportFormat::KeePassCsv);
+        assert_eq!(detect_format(CHROME_CSV).unwrap(), ImportFormat::GenericCsv);
+        assert_eq!(detect_format(BITWARDEN_JSON).unwrap(), ImportFormat::BitwardenJson);
+        assert!(matches!(detect_format("a,b,c\n1,2,3\n"), Err(TransferError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let payload = ArchivePayload {
+            exported_at: Utc::now(),
+            entries: vec![entry("a", "https://example.com", "alice")],
+            notes: Vec::new(),
+        };
+
+        let archive = seal_archive(&payload, "export passphrase", TEST_COST).unwrap();
+        assert!(!archive.contains("alice"));
+        assert_eq!(detect_format(&archive).unwrap(), ImportFormat::Archive);
+
+        let opened = open_archive(&archive, "export passphrase").unwrap();
+        assert_eq!(opened.entries[0].username, "alice");
+
+        assert!(matches!(open_archive(&archive, "wrong"), Err(TransferError::InvalidPassphrase)));
+
+        let mut envelope: ArchiveEnvelope = serde_json::from_str(&archive).unwrap();
+        envelope.kdf.iterations = u32::MAX;
+        let crafted = serde_json::to_string(&envelope).unwrap();
+        assert!(matches!(open_archive(&crafted, "export passphrase"), Err(TransferError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn test_parse_bitwarden_csv() {
+        let parsed = parse_csv(BITWARDEN_CSV, ImportFormat::BitwardenCsv, None).unwrap();
+
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].title, "GitLab");
+        assert_eq!(parsed.entries[0].password, "s3cret");
+        assert_eq!(parsed.entries[0].folder.as_deref(), Some("Work"));
+        assert!(parsed.entries[0].is_favorite);
+        assert_eq!(parsed.entries[0].totp_secret.as_deref(), Some("GEZDGNBVGY3TQOJQ"));
+
+        assert_eq!(parsed.notes.len(), 1);
+        assert_eq!(parsed.notes[0].content, "1111 2222 3333");
+    }
+
+    #[test]
+    fn test_parse_keepass_and_generic_csv() {
+        let parsed = parse_csv(KEEPASS_CSV, ImportFormat::KeePassCsv, None).unwrap();
+        assert_eq!(parsed.entries[0].username, "postgres");
+        assert_eq!(parsed.entries[0].folder.as_deref(), Some("Root/Servers"));
+        assert_eq!(parsed.entries[0].notes.as_deref(), Some("read replica"));
+
+        let parsed = parse_csv(CHROME_CSV, ImportFormat::GenericCsv, None).unwrap();
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].url.as_deref(), Some("https://www.example.com/login"));
+        assert_eq!(parsed.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_totp_rows_are_skipped() {
+        let data = "name,username,password,totp\nGood,alice,pw,GEZDGNBVGY3TQOJQ\nBad,bob,pw,not base32!\n";
+
+        let parsed = parse_csv(data, ImportFormat::GenericCsv, None).unwrap();
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].title, "Good");
+        assert_eq!(parsed.skipped.len(), 1);
+        assert!(parsed.skipped[0].starts_with("Bad: invalid TOTP secret"));
+    }
+
+    #[test]
+    fn test_custom_field_mapping() {
+        let data = "Site,Login,Secret\nhttps://intranet.local,carol,pa55\n";
+        let mapping = FieldMapping {
+            url: Some("Site".to_string()),
+            username: Some("Login".to_string()),
+            password: Some("Secret".to_string()),
+            ..FieldMapping::default()
+        };
+
+        let parsed = parse_csv(data, ImportFormat::GenericCsv, Some(mapping)).unwrap();
+        assert_eq!(parsed.entries[0].title, "intranet.local");
+        assert_eq!(parsed.entries[0].password, "pa55");
+    }
+
+    #[test]
+    fn test_only_descriptive_fields_are_trimmed() {
+        let data = "name,url,username,password,note
+ GitLab , https://gitlab.com , alice ,  pass phrase , indented\n\n";
+
+        let parsed = parse_csv(data, ImportFormat::GenericCsv, None).unwrap();
+        assert_eq!(parsed.entries[0].title, "GitLab");
+        assert_eq!(parsed.entries[0].url.as_deref(), Some("https://gitlab.com"));
+        assert_eq!(parsed.entries[0].username, "alice");
+        assert_eq!(parsed.entries[0].password, "  pass phrase ");
+        assert_eq!(parsed.entries[0].notes.as_deref(), Some(" indented"));
+    }
+
+    #[test]
+    fn test_parse_bitwarden_json() {
+        let parsed = parse_bitwarden_json(BITWARDEN_JSON).unwrap();
+
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].folder.as_deref(), Some("Infra"));
+        assert_eq!(parsed.entries[0].url.as_deref(), Some("https://aws.amazon.com"));
+        assert_eq!(parsed.notes[0].content, "key=abc");
+        assert_eq!(parsed.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_key_ignores_scheme_path_and_case() {
+        assert_eq!(
+            duplicate_key(&entry("a", "https://www.Example.com/login", "Bob")),
+            duplicate_key(&entry("b", "http://example.com", "bob")),
+        );
+        assert_ne!(
+            duplicate_key(&entry("a", "https://example.com", "bob")),
+            duplicate_key(&entry("b", "https://example.com", "alice")),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_urlless_entries_sharing_a_username_are_not_duplicates() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        let mut existing = entry("db", "", "root");
+        existing.url = None;
+        existing.title = "Database server".to_string();
+        manager.store_password(&existing).await.unwrap();
+
+        let data = "Group,Title,Username,Password,URL,Notes\nServers,Web server,root,web-pass,,\nServers,Mail server,root,mail-pass,,\n";
+        let report = import_vault(&manager, data, None, None, None, DuplicatePolicy::Overwrite)
+            .await
+            .unwrap();
+        assert_eq!(report.entries_imported, 2);
+        assert!(report.duplicates.is_empty());
+
+        let entries = manager.list_passwords(None).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(manager.get_password("db").await.unwrap().unwrap().password, "pw");
+    }
+
+    #[tokio::test]
+    async fn test_import_skips_duplicates() {
+        let manager = PasswordManager::open_in_memory().await.unwrap();
+        manager.set_up("master password").await.unwrap();
+        let mut existing = entry("existing", "https://example.com", "bob");
+        existing.created_at = "2020-01-01T00:00:00Z".parse().unwrap();
+        manager.store_password(&existing).await.unwrap();
+
+        let report = import_vault(&manager, CHROME_CSV, None, None, None, DuplicatePolicy::Skip)
+            .await
+            .unwrap();
+        assert_eq!(report.entries_imported, 0);
+        assert_eq!(report.duplicates, vec!["example.com"]);
+
+        let report = import_vault(&manager, CHROME_CSV, None, None, None, DuplicatePolicy::Overwrite)
+            .await
+            .unwrap();
+        assert_eq!(report.entries_imported, 1);
+
+        let entries = manager.list_passwords(None).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].password, "hunter2");
+        assert_eq!(entries[0].created_at, existing.created_at);
+    }
+
+    #[tokio::test]
+    async fn test_export_import_between_vaults() {
+        let source = PasswordManager::open_in_memory().await.unwrap();
+        source.set_up("source password").await.unwrap();
+        source.store_password(&entry("a", "https://example.com", "alice")).await.unwrap();
+
+        let archive = export_vault(&source, "transfer", TEST_COST).await.unwrap();
+
+        let target = PasswordManager::open_in_memory().await.unwrap();
+        target.set_up("target password").await.unwrap();
+        assert!(matches!(
+            import_vault(&target, &archive, None, None, None, DuplicatePolicy::Skip).await,
+            Err(TransferError::PassphraseRequired)
+        ));
+
+        let report = import_vault(&target, &archive, None, Some("transfer"), None, DuplicatePolicy::Skip)
+            .await
+            .unwrap();
+        assert_eq!(report.format, ImportFormat::Archive);
+        assert_eq!(report.entries_imported, 1);
+        assert_eq!(target.get_password("a").await.unwrap().unwrap().username, "alice");
+    }
+}